@@ -0,0 +1,75 @@
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokio_util::codec::{Decoder, Encoder};
+
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::codec::MessageCodec;
+
+const SIZES: [usize; 3] = [1024, 64 * 1024, 1024 * 1024];
+const RECIPIENTS: usize = 16;
+
+fn received(size: usize) -> Message {
+    Message::new_file(1, 2, vec![7; size], None, None)
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decodificar");
+    for size in SIZES {
+        let mut frame = BytesMut::new();
+        MessageCodec::default()
+            .encode(received(size).frame(), &mut frame)
+            .unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &frame, |b, frame| {
+            let mut codec = MessageCodec::default();
+            b.iter(|| {
+                let mut buffer = frame.clone();
+                codec.decode(&mut buffer).unwrap().unwrap().unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Encaminha uma mensagem recebida para `RECIPIENTS` sessões TCP, até o
+/// buffer de escrita de cada uma. `copia` repete o caminho anterior ao
+/// `Bytes`: cada destinatário recebia uma cópia do conteúdo, serializada num
+/// novo `Vec` antes da escrita.
+fn fan_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encaminhar");
+    for size in SIZES {
+        let message = received(size);
+        group.throughput(Throughput::Bytes((size * RECIPIENTS) as u64));
+        group.bench_with_input(BenchmarkId::new("copia", size), &message, |b, message| {
+            let mut buffer = BytesMut::new();
+            b.iter(|| {
+                for _ in 0..RECIPIENTS {
+                    let content = message.content.to_vec();
+                    let forward = Message::new_file(1, 3, content, None, None);
+                    buffer.extend_from_slice(&forward.serialize());
+                    buffer.clear();
+                }
+            });
+        });
+        group.bench_with_input(
+            BenchmarkId::new("compartilhado", size),
+            &message,
+            |b, message| {
+                let mut codec = MessageCodec::default();
+                let mut buffer = BytesMut::new();
+                b.iter(|| {
+                    let forward = Message::new_forward(1, 3, message);
+                    let frame = forward.frame();
+                    for _ in 0..RECIPIENTS {
+                        codec.encode(frame.clone(), &mut buffer).unwrap();
+                        buffer.clear();
+                    }
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode, fan_out);
+criterion_main!(benches);
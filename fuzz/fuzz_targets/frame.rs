@@ -0,0 +1,49 @@
+#![no_main]
+
+use std::io::ErrorKind;
+
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use tokio_util::codec::Decoder;
+
+use t1_lab_redes::network::codec::MessageCodec;
+
+const MAX_SIZE: u64 = 4096;
+
+// O primeiro byte define o tamanho dos pedaços entregues ao codec, simulando
+// leituras parciais do stream; o restante é o próprio stream.
+fuzz_target!(|data: &[u8]| {
+    let Some((chunk, stream)) = data.split_first() else {
+        return;
+    };
+    let chunk = usize::from(*chunk).max(1);
+    let mut codec = MessageCodec::new(MAX_SIZE);
+    let mut buffer = BytesMut::new();
+    let mut consumed = 0;
+    for piece in stream.chunks(chunk) {
+        buffer.extend_from_slice(piece);
+        loop {
+            let item = match codec.decode(&mut buffer) {
+                Ok(Some(item)) => item,
+                Ok(None) => break,
+                // Um cabeçalho inválido encerra o stream, como encerraria a conexão.
+                Err(e) => {
+                    assert_eq!(e.kind(), ErrorKind::InvalidData);
+                    return;
+                }
+            };
+            match item {
+                Ok(message) => {
+                    assert!(message.metadata.message_length <= MAX_SIZE);
+                    assert_eq!(
+                        message.content.len() as u64,
+                        message.metadata.message_length
+                    );
+                    consumed += message.metadata.size() + message.content.len();
+                }
+                Err(rejected) => assert!(!rejected.error.message.is_empty()),
+            }
+        }
+    }
+    assert!(consumed <= stream.len());
+});
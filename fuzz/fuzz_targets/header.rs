@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use t1_lab_redes::config::{METADATA_BYTES, UDP_METADATA_BYTES};
+use t1_lab_redes::models::metadata::MsgMetadata;
+
+// Qualquer sequência de bytes vira um cabeçalho ou um erro; um cabeçalho
+// válido serializado de novo reproduz os bytes de onde foi lido.
+fuzz_target!(|data: &[u8]| {
+    let peeked = MsgMetadata::peek_frame(data);
+    assert_eq!(peeked.is_some(), data.len() >= METADATA_BYTES);
+
+    if let Ok(metadata) = MsgMetadata::deserialize(data, false) {
+        assert_eq!(peeked, Some((metadata.key, metadata.message_length)));
+        assert_eq!(metadata.serialize(), &data[..METADATA_BYTES]);
+    }
+    if let Ok(metadata) = MsgMetadata::deserialize(data, true) {
+        let size = METADATA_BYTES + UDP_METADATA_BYTES;
+        assert_eq!(metadata.serialize(), &data[..size]);
+    }
+});
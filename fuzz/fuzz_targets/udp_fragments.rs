@@ -0,0 +1,39 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use t1_lab_redes::config::{METADATA_BYTES, UDP_METADATA_BYTES, UDP_PENDING_MESSAGES};
+use t1_lab_redes::network::udp::{build_udp_message, Reassembly};
+
+const MAX_SIZE: u64 = 4096;
+
+// Cada datagrama vem precedido do seu tamanho em um byte. Os pacotes passam
+// pela remontagem na ordem em que aparecem, como se viessem todos do mesmo
+// client.
+fuzz_target!(|data: &[u8]| {
+    let mut reassembly = Reassembly::default();
+    let mut rest = data;
+    while let Some((size, tail)) = rest.split_first() {
+        let size = usize::from(*size).min(tail.len());
+        let (datagram, tail) = tail.split_at(size);
+        rest = tail;
+        match build_udp_message(datagram.to_vec(), &mut reassembly, MAX_SIZE) {
+            Ok(Some(message)) => {
+                assert_eq!(
+                    message.content.len() as u64,
+                    message.metadata.message_length
+                );
+                assert!(message.metadata.udp_id.is_none());
+                assert!(message.metadata.udp_seq.is_none());
+            }
+            Ok(None) => {}
+            Err(rejected) => {
+                assert!(datagram.len() >= METADATA_BYTES + UDP_METADATA_BYTES);
+                assert!(!rejected.error.message.is_empty());
+            }
+        }
+        let pending = reassembly.pending_messages();
+        assert!(pending <= UDP_PENDING_MESSAGES);
+        assert!(reassembly.pending_bytes() <= pending as u64 * MAX_SIZE);
+    }
+});
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Args, Parser, Subcommand};
+
+use crate::config::{ClientArgs, ServerArgs};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "t1-lab-redes",
+    version,
+    about = "Chat cliente/servidor sobre TCP e UDP"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Inicia o servidor
+    Server(ServerCommand),
+    /// Inicia o client com a interface no terminal
+    Client(ClientArgs),
+    /// Envia uma única mensagem por TCP, aguarda a confirmação e encerra
+    ///
+    /// Códigos de saída: 0 entregue, 1 recusada pelo servidor, 2 uso inválido,
+    /// 3 falha de conexão, 4 destinatário não encontrado, 5 tempo esgotado,
+    /// 6 falha ao ler o arquivo, 7 destinatário sem chave pública (--e2e).
+    Send(SendArgs),
+    /// Lista os clients conectados e encerra
+    List(ClientArgs),
+    /// Mede vazão e latência enviando mensagens para o próprio client
+    Bench(BenchArgs),
+    /// Gerencia o arquivo de contas do servidor
+    Accounts(AccountsCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct ServerCommand {
+    /// Executa sem interface, com logs no stdout ou em arquivo
+    #[arg(long)]
+    pub headless: bool,
+    #[command(flatten)]
+    pub config: ServerArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("payload").required(true).args(["text", "file"])))]
+pub struct SendArgs {
+    /// Nome ou ID do destinatário
+    #[arg(long)]
+    pub to: String,
+    /// Texto da mensagem
+    #[arg(long)]
+    pub text: Option<String>,
+    /// Arquivo enviado como mensagem do tipo File
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+    /// Segundos aguardando conexão e confirmação do servidor
+    #[arg(long, default_value_t = 10)]
+    pub timeout: u64,
+    #[command(flatten)]
+    pub config: ClientArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Quantidade de mensagens enviadas
+    #[arg(long, default_value_t = 1000)]
+    pub count: usize,
+    /// Tamanho de cada mensagem em bytes
+    #[arg(long, default_value_t = 64)]
+    pub size: usize,
+    #[command(flatten)]
+    pub config: ClientArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct AccountsCommand {
+    /// Arquivo de contas (json)
+    #[arg(long, env = "T1_ACCOUNTS_FILE")]
+    pub file: PathBuf,
+    #[command(subcommand)]
+    pub action: AccountsAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountsAction {
+    /// Cria a conta ou troca a senha; sem --password, lê a senha da entrada padrão
+    Add {
+        /// Nome da conta
+        name: String,
+        /// Senha da conta
+        #[arg(long, env = "T1_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// Gera um novo token para a conta e o imprime
+    Token {
+        /// Nome da conta
+        name: String,
+    },
+    /// Torna a conta administradora; com --revoke, retira o papel
+    Admin {
+        /// Nome da conta
+        name: String,
+        /// Retira o papel de administrador
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Remove a conta
+    Remove {
+        /// Nome da conta
+        name: String,
+    },
+    /// Lista as contas registradas
+    List,
+}
@@ -0,0 +1,61 @@
+use std::io::{self, BufRead};
+
+use crate::cli::{AccountsAction, AccountsCommand};
+use crate::network::accounts::Accounts;
+
+/// Edita o arquivo de contas. O servidor relê o arquivo ao iniciar e quando um
+/// administrador recarrega a configuração.
+pub fn run(command: AccountsCommand) -> Result<(), String> {
+    let mut accounts = Accounts::load(&command.file)?;
+    match command.action {
+        AccountsAction::Add { name, password } => {
+            let password = match password {
+                Some(password) => password,
+                None => read_password()?,
+            };
+            accounts.set_password(&name, &password)?;
+            accounts.save()?;
+            eprintln!("Conta {0} salva", name);
+        }
+        AccountsAction::Token { name } => {
+            let token = accounts.add_token(&name)?;
+            accounts.save()?;
+            println!("{0}", token);
+        }
+        AccountsAction::Admin { name, revoke } => {
+            accounts.set_admin(&name, !revoke)?;
+            accounts.save()?;
+            if revoke {
+                eprintln!("Conta {0} deixou de ser administradora", name);
+            } else {
+                eprintln!("Conta {0} agora é administradora", name);
+            }
+        }
+        AccountsAction::Remove { name } => {
+            if !accounts.remove(&name) {
+                return Err(format!("Conta {0} não encontrada", name));
+            }
+            accounts.save()?;
+            eprintln!("Conta {0} removida", name);
+        }
+        AccountsAction::List => {
+            for name in accounts.names() {
+                if accounts.is_admin(name) {
+                    println!("{0} (admin)", name);
+                } else {
+                    println!("{0}", name);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_password() -> Result<String, String> {
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("Falha ao ler a senha: {0}", e))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use tokio::time;
+
+use crate::commands::disconnect;
+use crate::models::error::{ErrorBody, ErrorCode};
+use crate::network::client::{Client, ClientResult};
+use crate::utilities::enums::MessageType;
+
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Espera antes de reenviar uma mensagem recusada pelo limite de taxa do
+/// servidor; dobra a cada recusa seguida, até `MAX_BACKOFF`.
+const MIN_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+pub struct BenchReport {
+    pub count: usize,
+    pub size: usize,
+    pub elapsed: Duration,
+    pub latencies: Vec<Duration>,
+    /// Reenvios de mensagens recusadas pelo limite de taxa do servidor.
+    pub throttled: usize,
+}
+
+impl BenchReport {
+    fn percentile(&self, percentile: f64) -> Duration {
+        let index = ((self.latencies.len() - 1) as f64 * percentile).round() as usize;
+        self.latencies[index]
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        writeln!(
+            f,
+            "{0} mensagens de {1} bytes em {2:.2?}",
+            self.count, self.size, self.elapsed
+        )?;
+        writeln!(
+            f,
+            "Vazão: {0:.0} msg/s ({1:.2} MB/s)",
+            self.count as f64 / seconds,
+            (self.count * self.size) as f64 / seconds / 1_000_000.0
+        )?;
+        if self.throttled > 0 {
+            writeln!(
+                f,
+                "Limite de taxa do servidor: {0} reenvios",
+                self.throttled
+            )?;
+        }
+        if self.latencies.is_empty() {
+            return Ok(());
+        }
+        let total: Duration = self.latencies.iter().sum();
+        write!(
+            f,
+            "Latência: média {0:.2?}, p50 {1:.2?}, p99 {2:.2?}, máx {3:.2?}",
+            total / self.latencies.len() as u32,
+            self.percentile(0.5),
+            self.percentile(0.99),
+            self.latencies[self.latencies.len() - 1]
+        )
+    }
+}
+
+/// Envia `count` mensagens para o próprio client, uma por vez, e mede o tempo
+/// até receber tanto a mensagem encaminhada quanto a confirmação do servidor.
+/// Mensagens recusadas pelo limite de taxa são reenviadas após uma espera, então
+/// a vazão medida respeita os limites do servidor.
+pub async fn run<C: Client>(client: &C, count: usize, size: usize) -> ClientResult<BenchReport> {
+    let payload = "x".repeat(size);
+    let mut latencies = Vec::with_capacity(count);
+    let mut throttled = 0;
+    let start = Instant::now();
+    for _ in 0..count {
+        let first_sent = Instant::now();
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let sent = Instant::now();
+            let key = client.send_text(payload.clone(), client.get_id()).await?;
+            let outcome = time::timeout(MESSAGE_TIMEOUT, round_trip(client, key))
+                .await
+                .map_err(|_| format!("Tempo esgotado aguardando a mensagem {0}", key))??;
+            let Some(reason) = outcome else {
+                latencies.push(sent.elapsed());
+                break;
+            };
+            if first_sent.elapsed() >= MESSAGE_TIMEOUT {
+                return Err(format!(
+                    "Servidor recusando mensagens pelo limite de taxa: {0}",
+                    reason
+                )
+                .into());
+            }
+            throttled += 1;
+            time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+    let elapsed = start.elapsed();
+    latencies.sort();
+    disconnect(client).await?;
+    Ok(BenchReport {
+        count,
+        size,
+        elapsed,
+        latencies,
+        throttled,
+    })
+}
+
+/// Aguarda a mensagem encaminhada e a confirmação. Retorna o motivo se a
+/// mensagem foi recusada pelo limite de taxa.
+async fn round_trip<C: Client>(client: &C, key: u16) -> ClientResult<Option<String>> {
+    let (mut forwarded, mut confirmed) = (false, false);
+    while !(forwarded && confirmed) {
+        let message = client.listen().await?;
+        if message.metadata.key != key {
+            continue;
+        }
+        match message.metadata.message_type {
+            MessageType::Text => forwarded = true,
+            MessageType::Success => confirmed = true,
+            MessageType::Error => {
+                return match ErrorBody::decode(&message.content) {
+                    Some(error) if error.code == ErrorCode::RateLimited => Ok(Some(error.message)),
+                    _ => Err(message.error_reason().into()),
+                };
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
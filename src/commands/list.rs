@@ -0,0 +1,18 @@
+use crate::commands::disconnect;
+use crate::network::client::{wait_response, Client, ClientResult};
+
+pub async fn list_clients<C: Client>(client: &C) -> ClientResult<Vec<(u16, String)>> {
+    let key = client.list_clients().await?;
+    let response = wait_response(client, key).await?;
+    let clients = serde_json::from_slice(&response.content)?;
+    Ok(clients)
+}
+
+pub async fn run<C: Client>(client: &C) -> ClientResult<()> {
+    let mut clients = list_clients(client).await?;
+    clients.sort();
+    for (id, name) in clients {
+        println!("{0}\t{1}", id, name);
+    }
+    disconnect(client).await
+}
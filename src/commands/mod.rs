@@ -0,0 +1,23 @@
+use crate::config::{ClientArgs, ClientConfig};
+use crate::network::client::{wait_response, Client, ClientResult};
+
+pub mod accounts;
+pub mod bench;
+pub mod list;
+pub mod send;
+
+/// Carrega a configuração de um comando não interativo. Sem nome explícito,
+/// usa `<comando>-<pid>` para não colidir com clients já conectados.
+pub fn load_config(args: &ClientArgs, command: &str) -> Result<ClientConfig, String> {
+    let mut config = ClientConfig::load(args)?;
+    if args.name.is_none() && config.name == ClientConfig::default().name {
+        config.name = format!("{0}-{1}", command, std::process::id());
+    }
+    Ok(config)
+}
+
+pub async fn disconnect<C: Client>(client: &C) -> ClientResult<()> {
+    let key = client.disconnect().await?;
+    wait_response(client, key).await?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::time;
+
+use crate::commands::disconnect;
+use crate::commands::list::list_clients;
+use crate::config::ClientConfig;
+use crate::models::message::Message;
+use crate::network::client::{wait_response, Client};
+use crate::network::e2e::{E2eKeys, PUBLIC_KEY_BYTES};
+use crate::network::tcp_client::TcpClient;
+use crate::utilities::enums::{MessageType, Protocol};
+
+pub enum Payload {
+    Text(String),
+    File(Vec<u8>),
+}
+
+impl Payload {
+    pub fn load(text: Option<String>, file: Option<&Path>) -> Result<Payload, SendError> {
+        match (text, file) {
+            (Some(text), _) => Ok(Payload::Text(text)),
+            (None, Some(path)) => fs::read(path)
+                .map(Payload::File)
+                .map_err(|e| SendError::File(format!("{0}: {1}", path.display(), e))),
+            (None, None) => Err(SendError::Usage("Informe --text ou --file".to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SendError {
+    Rejected(String),
+    Usage(String),
+    Connection(String),
+    NotFound(String),
+    Timeout,
+    File(String),
+    NoPublicKey(String),
+}
+
+impl SendError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SendError::Rejected(_) => 1,
+            SendError::Usage(_) => 2,
+            SendError::Connection(_) => 3,
+            SendError::NotFound(_) => 4,
+            SendError::Timeout => 5,
+            SendError::File(_) => 6,
+            SendError::NoPublicKey(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Rejected(reason) => write!(f, "Mensagem recusada: {0}", reason),
+            SendError::Usage(reason) => write!(f, "{0}", reason),
+            SendError::Connection(reason) => write!(f, "Falha de conexão: {0}", reason),
+            SendError::NotFound(to) => write!(f, "Destinatário não encontrado: {0}", to),
+            SendError::Timeout => write!(f, "Tempo esgotado aguardando o servidor"),
+            SendError::File(reason) => write!(f, "Falha ao ler arquivo: {0}", reason),
+            SendError::NoPublicKey(reason) => {
+                write!(f, "Não foi possível cifrar (e2e): {0}", reason)
+            }
+        }
+    }
+}
+
+/// Conecta por TCP, envia uma única mensagem para `to` (nome ou ID) e aguarda
+/// o `Success`/`Error` com a mesma chave. Retorna o ID do destinatário.
+pub async fn run(
+    config: &ClientConfig,
+    to: &str,
+    payload: Payload,
+    timeout: Duration,
+) -> Result<u16, SendError> {
+    if config.transport != Protocol::TCP {
+        return Err(SendError::Usage(
+            "O comando send usa somente TCP".to_string(),
+        ));
+    }
+    let client = time::timeout(timeout, TcpClient::new(config))
+        .await
+        .map_err(|_| SendError::Timeout)?
+        .map_err(|e| SendError::Connection(e.to_string()))?;
+    let e2e = config.e2e.then(E2eKeys::generate);
+    let result = time::timeout(timeout, deliver(&client, to, payload, e2e.as_ref()))
+        .await
+        .map_err(|_| SendError::Timeout)
+        .and_then(|result| result);
+    let _ = time::timeout(timeout, disconnect(&client)).await;
+    result
+}
+
+async fn deliver(
+    client: &TcpClient,
+    to: &str,
+    payload: Payload,
+    e2e: Option<&E2eKeys>,
+) -> Result<u16, SendError> {
+    let clients = list_clients(client)
+        .await
+        .map_err(|e| SendError::Connection(e.to_string()))?;
+    let receiver_id = resolve(&clients, to).ok_or(SendError::NotFound(to.to_string()))?;
+    let key = Message::generate_key();
+    let message = match (payload, e2e) {
+        (payload, Some(keys)) => {
+            let public_key = fetch_public_key(client, receiver_id).await?;
+            let envelope = match payload {
+                Payload::Text(text) => keys.seal(&public_key, MessageType::Text, text.as_bytes()),
+                Payload::File(content) => keys.seal(&public_key, MessageType::File, &content),
+            }
+            .map_err(SendError::NoPublicKey)?;
+            Message::new_encrypted(key, receiver_id, envelope)
+        }
+        (Payload::Text(text), None) => Message::new_text(key, receiver_id, text, None, None),
+        (Payload::File(content), None) => Message::new_file(key, receiver_id, content, None, None),
+    };
+    client
+        .send(message)
+        .await
+        .map_err(|e| SendError::Connection(e.to_string()))?;
+    wait_response(client, key)
+        .await
+        .map_err(|e| SendError::Rejected(e.to_string()))?;
+    Ok(receiver_id)
+}
+
+async fn fetch_public_key(
+    client: &TcpClient,
+    owner_id: u16,
+) -> Result<[u8; PUBLIC_KEY_BYTES], SendError> {
+    let key = Message::generate_key();
+    client
+        .send(Message::new_public_key_request(key, owner_id))
+        .await
+        .map_err(|e| SendError::Connection(e.to_string()))?;
+    let response = wait_response(client, key)
+        .await
+        .map_err(|e| SendError::NoPublicKey(e.to_string()))?;
+    response.content[..]
+        .try_into()
+        .map_err(|_| SendError::NoPublicKey("Chave pública inválida".to_string()))
+}
+
+/// Procura primeiro pelo nome e, se nenhum client tiver esse nome, pelo ID.
+fn resolve(clients: &[(u16, String)], to: &str) -> Option<u16> {
+    clients
+        .iter()
+        .find(|(_, name)| name == to)
+        .or_else(|| {
+            let id = to.parse::<u16>().ok()?;
+            clients.iter().find(|(client_id, _)| *client_id == id)
+        })
+        .map(|(id, _)| *id)
+}
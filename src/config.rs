@@ -1,7 +1,819 @@
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use serde::Deserialize;
+
+use crate::models::credentials::Credentials;
+use crate::network::udp_crypto::SEAL_OVERHEAD;
+use crate::utilities::enums::{Protocol, RateLimitAction};
+
 pub const TCP_PORT: u16 = 8080;
 pub const UDP_PORT: u16 = 8081;
-pub const CLIENT_UDP_PORT: u16 = 8082;
+pub const CLIENT_UDP_PORT: u16 = 0;
 pub const HOST_ADDRESS: &str = "localhost";
 pub const BUFFER_SIZE: usize = 512;
-pub const METADATA_BYTES: usize = 21;
+pub const MAX_DATAGRAM_SIZE: usize = 65535;
+pub const METADATA_BYTES: usize = 13;
+pub const MAX_MESSAGE_SIZE: u64 = 16 * 1024 * 1024;
 pub const UDP_METADATA_BYTES: usize = 4;
+/// A sequência dos pacotes UDP tem 16 bits, o que limita os pacotes de uma mensagem.
+pub const MAX_UDP_PACKETS: u64 = u16::MAX as u64 + 1;
+pub const EVENT_LOG_CAPACITY: usize = 1000;
+pub const UDP_PENDING_MESSAGES: usize = 64;
+pub const UDP_COMPLETED_WINDOW: usize = 256;
+/// O `Connection` por UDP é reenviado com intervalos que dobram a partir de
+/// `UDP_CONNECT_RETRY_MS`, até `UDP_CONNECT_TIMEOUT_MS` sem resposta.
+pub const UDP_CONNECT_RETRY_MS: u64 = 250;
+pub const UDP_CONNECT_TIMEOUT_MS: u64 = 5_000;
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+pub const MIN_UDP_PSK_BYTES: usize = 16;
+pub const OFFLINE_TTL: u64 = 7 * 24 * 60 * 60;
+pub const OFFLINE_MAX_MESSAGES: usize = 100;
+pub const OFFLINE_MAX_BYTES: usize = 1024 * 1024;
+pub const OFFLINE_MAX_PER_SENDER: usize = 20;
+pub const OFFLINE_SAVE_INTERVAL_MS: u64 = 500;
+pub const HISTORY_PAGE_SIZE: usize = 50;
+pub const MAX_HISTORY_PAGE_SIZE: usize = 200;
+pub const MEMORY_HISTORY_CAPACITY: usize = 10_000;
+pub const RATE_MESSAGES: u64 = 50;
+pub const RATE_BYTES: u64 = 1024 * 1024;
+pub const IP_RATE_MESSAGES: u64 = 100;
+pub const IP_RATE_BYTES: u64 = 4 * 1024 * 1024;
+pub const RATE_BURST: u64 = 4;
+pub const RATE_MUTE_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub tcp_port: u16,
+    pub udp_port: u16,
+    pub buffer_size: usize,
+    pub max_message_size: u64,
+    pub event_log_capacity: usize,
+    pub log_level: String,
+    pub log_file: Option<PathBuf>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub udp_psk: Option<String>,
+    pub accounts_file: Option<PathBuf>,
+    pub require_auth: bool,
+    pub offline_queue_file: Option<PathBuf>,
+    pub offline_ttl: u64,
+    pub offline_max_messages: usize,
+    pub offline_max_bytes: usize,
+    pub offline_max_per_sender: usize,
+    pub history_file: Option<PathBuf>,
+    pub rate_messages: u64,
+    pub rate_bytes: u64,
+    pub ip_rate_messages: u64,
+    pub ip_rate_bytes: u64,
+    pub rate_burst: u64,
+    pub rate_action: RateLimitAction,
+    pub rate_mute_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: HOST_ADDRESS.to_string(),
+            tcp_port: TCP_PORT,
+            udp_port: UDP_PORT,
+            buffer_size: BUFFER_SIZE,
+            max_message_size: MAX_MESSAGE_SIZE,
+            event_log_capacity: EVENT_LOG_CAPACITY,
+            log_level: DEFAULT_LOG_LEVEL.to_string(),
+            log_file: None,
+            tls_cert: None,
+            tls_key: None,
+            udp_psk: None,
+            accounts_file: None,
+            require_auth: false,
+            offline_queue_file: None,
+            offline_ttl: OFFLINE_TTL,
+            offline_max_messages: OFFLINE_MAX_MESSAGES,
+            offline_max_bytes: OFFLINE_MAX_BYTES,
+            offline_max_per_sender: OFFLINE_MAX_PER_SENDER,
+            history_file: None,
+            rate_messages: RATE_MESSAGES,
+            rate_bytes: RATE_BYTES,
+            ip_rate_messages: IP_RATE_MESSAGES,
+            ip_rate_bytes: IP_RATE_BYTES,
+            rate_burst: RATE_BURST,
+            rate_action: RateLimitAction::Drop,
+            rate_mute_secs: RATE_MUTE_SECS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    pub host: String,
+    pub tcp_port: u16,
+    pub udp_port: u16,
+    pub udp_bind_port: u16,
+    pub buffer_size: usize,
+    pub name: String,
+    pub transport: Protocol,
+    pub log_level: String,
+    pub tls: bool,
+    pub tls_ca: Option<PathBuf>,
+    pub tls_server_name: Option<String>,
+    pub udp_psk: Option<String>,
+    pub e2e: bool,
+    pub password: Option<String>,
+    pub token: Option<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            host: HOST_ADDRESS.to_string(),
+            tcp_port: TCP_PORT,
+            udp_port: UDP_PORT,
+            udp_bind_port: CLIENT_UDP_PORT,
+            buffer_size: BUFFER_SIZE,
+            name: String::from("Client"),
+            transport: Protocol::TCP,
+            log_level: DEFAULT_LOG_LEVEL.to_string(),
+            tls: false,
+            tls_ca: None,
+            tls_server_name: None,
+            udp_psk: None,
+            e2e: false,
+            password: None,
+            token: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    server: Option<toml::Table>,
+    client: Option<toml::Table>,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct ServerArgs {
+    /// Arquivo de configuração TOML (seção [server])
+    #[arg(long, env = "T1_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Endereço em que o servidor escuta
+    #[arg(short = 'H', long, env = "T1_HOST")]
+    pub host: Option<String>,
+    /// Porta TCP do servidor
+    #[arg(short = 'p', long, visible_alias = "port", env = "T1_TCP_PORT")]
+    pub tcp_port: Option<u16>,
+    /// Porta UDP do servidor
+    #[arg(long, env = "T1_UDP_PORT")]
+    pub udp_port: Option<u16>,
+    /// Tamanho máximo dos datagramas UDP enviados
+    #[arg(long, env = "T1_BUFFER_SIZE")]
+    pub buffer_size: Option<usize>,
+    /// Tamanho máximo, em bytes, do conteúdo de uma mensagem recebida
+    #[arg(long, env = "T1_MAX_MESSAGE_SIZE")]
+    pub max_message_size: Option<u64>,
+    /// Quantidade de eventos mantidos em memória
+    #[arg(long, env = "T1_EVENT_LOG_CAPACITY")]
+    pub event_log_capacity: Option<usize>,
+    /// Nível dos logs (error, warn, info, debug, trace)
+    #[arg(long, env = "T1_LOG_LEVEL")]
+    pub log_level: Option<String>,
+    /// Grava os logs em arquivo, com rotação diária
+    #[arg(long, env = "T1_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+    /// Certificado TLS (PEM) do listener TCP; exige --tls-key
+    #[arg(long, env = "T1_TLS_CERT")]
+    pub tls_cert: Option<PathBuf>,
+    /// Chave privada TLS (PEM) do listener TCP; exige --tls-cert
+    #[arg(long, env = "T1_TLS_KEY")]
+    pub tls_key: Option<PathBuf>,
+    /// Chave pré-compartilhada que ativa a cifragem autenticada do UDP
+    #[arg(long, env = "T1_UDP_PSK", hide_env_values = true)]
+    pub udp_psk: Option<String>,
+    /// Arquivo de contas (json) usado para autenticar os clients
+    #[arg(long, env = "T1_ACCOUNTS_FILE")]
+    pub accounts_file: Option<PathBuf>,
+    /// Recusa clients sem conta; exige --accounts-file
+    #[arg(long, env = "T1_REQUIRE_AUTH", num_args = 0..=1, default_missing_value = "true")]
+    pub require_auth: Option<bool>,
+    /// Arquivo (json) em que as mensagens para usuários offline são guardadas
+    #[arg(long, env = "T1_OFFLINE_QUEUE_FILE")]
+    pub offline_queue_file: Option<PathBuf>,
+    /// Segundos que uma mensagem espera na fila offline antes de ser descartada
+    #[arg(long, env = "T1_OFFLINE_TTL")]
+    pub offline_ttl: Option<u64>,
+    /// Máximo de mensagens na fila offline de cada usuário
+    #[arg(long, env = "T1_OFFLINE_MAX_MESSAGES")]
+    pub offline_max_messages: Option<usize>,
+    /// Máximo de bytes na fila offline de cada usuário
+    #[arg(long, env = "T1_OFFLINE_MAX_BYTES")]
+    pub offline_max_bytes: Option<usize>,
+    /// Máximo de mensagens de um mesmo remetente na fila offline de cada usuário
+    #[arg(long, env = "T1_OFFLINE_MAX_PER_SENDER")]
+    pub offline_max_per_sender: Option<usize>,
+    /// Arquivo (uma entrada json por linha) em que o histórico de mensagens é gravado
+    #[arg(long, env = "T1_HISTORY_FILE")]
+    pub history_file: Option<PathBuf>,
+    /// Mensagens por segundo aceitas de cada sessão (0 desativa)
+    #[arg(long, env = "T1_RATE_MESSAGES")]
+    pub rate_messages: Option<u64>,
+    /// Bytes por segundo aceitos de cada sessão (0 desativa)
+    #[arg(long, env = "T1_RATE_BYTES")]
+    pub rate_bytes: Option<u64>,
+    /// Mensagens e novas sessões por segundo aceitas de cada IP (0 desativa)
+    #[arg(long, env = "T1_IP_RATE_MESSAGES")]
+    pub ip_rate_messages: Option<u64>,
+    /// Bytes por segundo aceitos de cada IP (0 desativa)
+    #[arg(long, env = "T1_IP_RATE_BYTES")]
+    pub ip_rate_bytes: Option<u64>,
+    /// Segundos de envio acumulados que uma rajada pode usar
+    #[arg(long, env = "T1_RATE_BURST")]
+    pub rate_burst: Option<u64>,
+    /// Ação sobre quem excede os limites
+    #[arg(long, value_enum, env = "T1_RATE_ACTION")]
+    pub rate_action: Option<RateLimitAction>,
+    /// Segundos de silêncio com --rate-action mute
+    #[arg(long, env = "T1_RATE_MUTE_SECS")]
+    pub rate_mute_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct ClientArgs {
+    /// Arquivo de configuração TOML (seção [client])
+    #[arg(long, env = "T1_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Endereço do servidor
+    #[arg(short = 'H', long, env = "T1_HOST")]
+    pub host: Option<String>,
+    /// Porta TCP do servidor
+    #[arg(short = 'p', long, visible_alias = "port", env = "T1_TCP_PORT")]
+    pub tcp_port: Option<u16>,
+    /// Porta UDP do servidor
+    #[arg(long, env = "T1_UDP_PORT")]
+    pub udp_port: Option<u16>,
+    /// Porta UDP local do client (0 escolhe uma porta livre)
+    #[arg(long, env = "T1_UDP_BIND_PORT")]
+    pub udp_bind_port: Option<u16>,
+    /// Tamanho máximo dos datagramas UDP enviados
+    #[arg(long, env = "T1_BUFFER_SIZE")]
+    pub buffer_size: Option<usize>,
+    /// Nome usado ao conectar
+    #[arg(short = 'n', long, env = "T1_NAME")]
+    pub name: Option<String>,
+    /// Protocolo de transporte
+    #[arg(short = 't', long, value_enum, env = "T1_TRANSPORT")]
+    pub transport: Option<Protocol>,
+    /// Nível dos logs (error, warn, info, debug, trace)
+    #[arg(long, env = "T1_LOG_LEVEL")]
+    pub log_level: Option<String>,
+    /// Conecta ao servidor TCP usando TLS
+    #[arg(long, env = "T1_TLS", num_args = 0..=1, default_missing_value = "true")]
+    pub tls: Option<bool>,
+    /// Certificado (PEM) de uma CA própria em que confiar; implica --tls
+    #[arg(long, env = "T1_TLS_CA")]
+    pub tls_ca: Option<PathBuf>,
+    /// Nome esperado no certificado do servidor (padrão: --host)
+    #[arg(long, env = "T1_TLS_SERVER_NAME")]
+    pub tls_server_name: Option<String>,
+    /// Chave pré-compartilhada que ativa a cifragem autenticada do UDP
+    #[arg(long, env = "T1_UDP_PSK", hide_env_values = true)]
+    pub udp_psk: Option<String>,
+    /// Cifra de ponta a ponta as mensagens diretas (Text e File)
+    #[arg(long, env = "T1_E2E", num_args = 0..=1, default_missing_value = "true")]
+    pub e2e: Option<bool>,
+    /// Senha da conta registrada com --name; não combina com --token
+    #[arg(
+        long,
+        env = "T1_PASSWORD",
+        hide_env_values = true,
+        conflicts_with = "token"
+    )]
+    pub password: Option<String>,
+    /// Token da conta registrada com --name
+    #[arg(long, env = "T1_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+}
+
+impl ServerConfig {
+    pub fn load(args: &ServerArgs) -> Result<Self, String> {
+        let mut config = match &args.config {
+            Some(path) => read_section(path, |file| file.server)?,
+            None => ServerConfig::default(),
+        };
+        if let Some(host) = &args.host {
+            config.host = host.clone();
+        }
+        if let Some(tcp_port) = args.tcp_port {
+            config.tcp_port = tcp_port;
+        }
+        if let Some(udp_port) = args.udp_port {
+            config.udp_port = udp_port;
+        }
+        if let Some(buffer_size) = args.buffer_size {
+            config.buffer_size = buffer_size;
+        }
+        if let Some(max_message_size) = args.max_message_size {
+            config.max_message_size = max_message_size;
+        }
+        if let Some(event_log_capacity) = args.event_log_capacity {
+            config.event_log_capacity = event_log_capacity;
+        }
+        if let Some(log_level) = &args.log_level {
+            config.log_level = log_level.clone();
+        }
+        if let Some(log_file) = &args.log_file {
+            config.log_file = Some(log_file.clone());
+        }
+        if let Some(tls_cert) = &args.tls_cert {
+            config.tls_cert = Some(tls_cert.clone());
+        }
+        if let Some(tls_key) = &args.tls_key {
+            config.tls_key = Some(tls_key.clone());
+        }
+        validate_host(&config.host)?;
+        if config.tls_cert.is_some() != config.tls_key.is_some() {
+            return Err("tls_cert e tls_key devem ser informados juntos".to_string());
+        }
+        if let Some(udp_psk) = &args.udp_psk {
+            config.udp_psk = Some(udp_psk.clone());
+        }
+        validate_udp_psk(config.udp_psk.as_deref())?;
+        validate_buffer_size(config.buffer_size, config.udp_psk.is_some())?;
+        validate_max_message_size(
+            config.max_message_size,
+            config.buffer_size,
+            config.udp_psk.is_some(),
+        )?;
+        if let Some(accounts_file) = &args.accounts_file {
+            config.accounts_file = Some(accounts_file.clone());
+        }
+        if let Some(require_auth) = args.require_auth {
+            config.require_auth = require_auth;
+        }
+        if config.require_auth && config.accounts_file.is_none() {
+            return Err("require_auth exige accounts_file".to_string());
+        }
+        if let Some(offline_queue_file) = &args.offline_queue_file {
+            config.offline_queue_file = Some(offline_queue_file.clone());
+        }
+        if let Some(offline_ttl) = args.offline_ttl {
+            config.offline_ttl = offline_ttl;
+        }
+        if let Some(offline_max_messages) = args.offline_max_messages {
+            config.offline_max_messages = offline_max_messages;
+        }
+        if let Some(offline_max_bytes) = args.offline_max_bytes {
+            config.offline_max_bytes = offline_max_bytes;
+        }
+        if let Some(offline_max_per_sender) = args.offline_max_per_sender {
+            config.offline_max_per_sender = offline_max_per_sender;
+        }
+        if let Some(history_file) = &args.history_file {
+            config.history_file = Some(history_file.clone());
+        }
+        if let Some(rate_messages) = args.rate_messages {
+            config.rate_messages = rate_messages;
+        }
+        if let Some(rate_bytes) = args.rate_bytes {
+            config.rate_bytes = rate_bytes;
+        }
+        if let Some(ip_rate_messages) = args.ip_rate_messages {
+            config.ip_rate_messages = ip_rate_messages;
+        }
+        if let Some(ip_rate_bytes) = args.ip_rate_bytes {
+            config.ip_rate_bytes = ip_rate_bytes;
+        }
+        if let Some(rate_burst) = args.rate_burst {
+            config.rate_burst = rate_burst;
+        }
+        if let Some(rate_action) = args.rate_action {
+            config.rate_action = rate_action;
+        }
+        if let Some(rate_mute_secs) = args.rate_mute_secs {
+            config.rate_mute_secs = rate_mute_secs;
+        }
+        Ok(config)
+    }
+
+    /// Configuração recarregada a partir de `next`. Opções presas a sockets,
+    /// logs e arquivos abertos só mudam com um reinício: mantêm o valor em uso e
+    /// seus nomes são retornados quando `next` as altera.
+    pub fn reloaded(&self, mut next: ServerConfig) -> (ServerConfig, Vec<String>) {
+        let mut restart = Vec::new();
+        macro_rules! keep {
+            ($($field:ident),*) => {
+                $(
+                    if next.$field != self.$field {
+                        restart.push(stringify!($field).to_string());
+                        next.$field = self.$field.clone();
+                    }
+                )*
+            };
+        }
+        keep!(
+            host,
+            tcp_port,
+            udp_port,
+            buffer_size,
+            event_log_capacity,
+            log_level,
+            log_file,
+            tls_cert,
+            tls_key,
+            udp_psk,
+            offline_queue_file
+        );
+        (next, restart)
+    }
+
+    pub fn tcp_address(&self) -> String {
+        format!("{0}:{1}", self.host, self.tcp_port)
+    }
+
+    pub fn udp_address(&self) -> String {
+        format!("{0}:{1}", self.host, self.udp_port)
+    }
+}
+
+impl ClientConfig {
+    pub fn load(args: &ClientArgs) -> Result<Self, String> {
+        let mut config = match &args.config {
+            Some(path) => read_section(path, |file| file.client)?,
+            None => ClientConfig::default(),
+        };
+        if let Some(host) = &args.host {
+            config.host = host.clone();
+        }
+        if let Some(tcp_port) = args.tcp_port {
+            config.tcp_port = tcp_port;
+        }
+        if let Some(udp_port) = args.udp_port {
+            config.udp_port = udp_port;
+        }
+        if let Some(udp_bind_port) = args.udp_bind_port {
+            config.udp_bind_port = udp_bind_port;
+        }
+        if let Some(buffer_size) = args.buffer_size {
+            config.buffer_size = buffer_size;
+        }
+        if let Some(name) = &args.name {
+            config.name = name.clone();
+        }
+        if let Some(transport) = args.transport {
+            config.transport = transport;
+        }
+        if let Some(log_level) = &args.log_level {
+            config.log_level = log_level.clone();
+        }
+        if let Some(tls) = args.tls {
+            config.tls = tls;
+        }
+        if let Some(tls_ca) = &args.tls_ca {
+            config.tls_ca = Some(tls_ca.clone());
+        }
+        if let Some(tls_server_name) = &args.tls_server_name {
+            config.tls_server_name = Some(tls_server_name.clone());
+        }
+        validate_host(&config.host)?;
+        validate_port("tcp_port", config.tcp_port)?;
+        validate_port("udp_port", config.udp_port)?;
+        if config.tls_ca.is_some() {
+            config.tls = true;
+        }
+        if let Some(e2e) = args.e2e {
+            config.e2e = e2e;
+        }
+        if let Some(udp_psk) = &args.udp_psk {
+            config.udp_psk = Some(udp_psk.clone());
+        }
+        validate_udp_psk(config.udp_psk.as_deref())?;
+        validate_buffer_size(config.buffer_size, config.udp_psk.is_some())?;
+        if let Some(password) = &args.password {
+            config.password = Some(password.clone());
+        }
+        if let Some(token) = &args.token {
+            config.token = Some(token.clone());
+        }
+        if config.password.is_some() && config.token.is_some() {
+            return Err("password e token não podem ser informados juntos".to_string());
+        }
+        Ok(config)
+    }
+
+    pub fn credentials(&self) -> Credentials {
+        Credentials {
+            name: self.name.clone(),
+            password: self.password.clone(),
+            token: self.token.clone(),
+        }
+    }
+
+    pub fn tcp_address(&self) -> String {
+        format!("{0}:{1}", self.host, self.tcp_port)
+    }
+
+    pub fn udp_address(&self) -> String {
+        format!("{0}:{1}", self.host, self.udp_port)
+    }
+}
+
+fn read_section<T: Default + for<'de> Deserialize<'de>>(
+    path: &Path,
+    section: fn(ConfigFile) -> Option<toml::Table>,
+) -> Result<T, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Falha ao ler {0}: {1}", path.display(), e))?;
+    let file: ConfigFile = toml::from_str(&content)
+        .map_err(|e| format!("Configuração inválida em {0}: {1}", path.display(), e))?;
+    match section(file) {
+        Some(table) => table
+            .try_into()
+            .map_err(|e| format!("Configuração inválida em {0}: {1}", path.display(), e)),
+        None => Ok(T::default()),
+    }
+}
+
+/// O host entra em `host:porta`, então precisa ser um IPv4 ou um nome de
+/// domínio: rótulos de letras, dígitos e hífens separados por pontos.
+fn validate_host(host: &str) -> Result<(), String> {
+    let label = |label: &str| {
+        (1..=63).contains(&label.len())
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if host.parse::<Ipv4Addr>().is_ok() || (host.len() <= 253 && host.split('.').all(label)) {
+        return Ok(());
+    }
+    Err(format!("host inválido: {0:?}", host))
+}
+
+/// Portas do servidor no client; 0 só faz sentido para escutar.
+fn validate_port(name: &str, port: u16) -> Result<(), String> {
+    if port == 0 {
+        return Err(format!("{0} deve ser maior que zero", name));
+    }
+    Ok(())
+}
+
+fn validate_udp_psk(udp_psk: Option<&str>) -> Result<(), String> {
+    match udp_psk {
+        Some(psk) if psk.len() < MIN_UDP_PSK_BYTES => Err(format!(
+            "udp_psk deve ter pelo menos {0} bytes",
+            MIN_UDP_PSK_BYTES
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn udp_header_size(sealed: bool) -> usize {
+    let mut header = METADATA_BYTES + UDP_METADATA_BYTES;
+    if sealed {
+        header += SEAL_OVERHEAD;
+    }
+    header
+}
+
+fn validate_buffer_size(buffer_size: usize, sealed: bool) -> Result<(), String> {
+    let header = udp_header_size(sealed);
+    if buffer_size <= header || buffer_size > MAX_DATAGRAM_SIZE {
+        return Err(format!(
+            "buffer_size deve estar entre {0} e {1}",
+            header + 1,
+            MAX_DATAGRAM_SIZE
+        ));
+    }
+    Ok(())
+}
+
+/// Uma mensagem do tamanho máximo precisa caber em `MAX_UDP_PACKETS` pacotes
+/// de `buffer_size` bytes. Chamada depois de `validate_buffer_size`.
+fn validate_max_message_size(
+    max_message_size: u64,
+    buffer_size: usize,
+    sealed: bool,
+) -> Result<(), String> {
+    if max_message_size == 0 {
+        return Err("max_message_size deve ser maior que zero".to_string());
+    }
+    let limit = (buffer_size - udp_header_size(sealed)) as u64 * MAX_UDP_PACKETS;
+    if max_message_size > limit {
+        return Err(format!(
+            "max_message_size deve ser no máximo {0} com buffer_size {1}",
+            limit, buffer_size
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::{Cli, Command};
+
+    /// Arquivo de configuração num diretório temporário próprio do teste,
+    /// removido no fim.
+    struct TempConfig {
+        dir: PathBuf,
+        path: PathBuf,
+    }
+
+    impl TempConfig {
+        fn new(test: &str, content: &str) -> Self {
+            let dir = env::temp_dir().join(format!("t1-config-{0}-{1}", test, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("t1.toml");
+            fs::write(&path, content).unwrap();
+            TempConfig { dir, path }
+        }
+    }
+
+    impl Drop for TempConfig {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn parse(args: &[&str]) -> Command {
+        Cli::try_parse_from([&["t1-lab-redes"], args].concat())
+            .unwrap()
+            .command
+    }
+
+    // As variáveis de ambiente valem para o processo inteiro, então todas as
+    // camadas são verificadas num só teste.
+    #[test]
+    fn each_layer_overrides_the_one_below() {
+        let file = TempConfig::new(
+            "layers",
+            "[server]\ntcp_port = 1001\nudp_port = 1002\nrate_burst = 7\n\n\
+             [client]\ntcp_port = 2001\nudp_port = 2002\nname = \"arquivo\"\n",
+        );
+        env::set_var("T1_CONFIG", &file.path);
+        env::set_var("T1_TCP_PORT", "3001");
+        env::set_var("T1_UDP_PORT", "3002");
+        let server = parse(&["server", "--tcp-port", "4001"]);
+        let client = parse(&["list", "--tcp-port", "4001"]);
+        for name in ["T1_CONFIG", "T1_TCP_PORT", "T1_UDP_PORT"] {
+            env::remove_var(name);
+        }
+
+        let Command::Server(server) = server else {
+            panic!("esperado o comando server");
+        };
+        let config = ServerConfig::load(&server.config).unwrap();
+        assert_eq!(config.rate_burst, 7);
+        assert_eq!(config.udp_port, 3002);
+        assert_eq!(config.tcp_port, 4001);
+        assert_eq!(config.host, HOST_ADDRESS);
+
+        let Command::List(client) = client else {
+            panic!("esperado o comando list");
+        };
+        let config = ClientConfig::load(&client).unwrap();
+        assert_eq!(config.name, "arquivo");
+        assert_eq!(config.udp_port, 3002);
+        assert_eq!(config.tcp_port, 4001);
+        assert_eq!(config.host, HOST_ADDRESS);
+    }
+
+    #[test]
+    fn missing_config_files_are_errors() {
+        let path = env::temp_dir().join("t1-config-inexistente.toml");
+        let server = ServerConfig::load(&ServerArgs {
+            config: Some(path.clone()),
+            ..ServerArgs::default()
+        });
+        assert!(server
+            .unwrap_err()
+            .starts_with(&format!("Falha ao ler {0}", path.display())));
+        let client = ClientConfig::load(&ClientArgs {
+            config: Some(path.clone()),
+            ..ClientArgs::default()
+        });
+        assert!(client
+            .unwrap_err()
+            .starts_with(&format!("Falha ao ler {0}", path.display())));
+    }
+
+    #[test]
+    fn malformed_config_files_are_errors() {
+        for (test, content) in [
+            ("syntax", "[server\ntcp_port = 1"),
+            (
+                "types",
+                "[server]\ntcp_port = \"alta\"\n\n[client]\ntcp_port = -1\n",
+            ),
+        ] {
+            let file = TempConfig::new(test, content);
+            let invalid = format!("Configuração inválida em {0}", file.path.display());
+            let server = ServerConfig::load(&ServerArgs {
+                config: Some(file.path.clone()),
+                ..ServerArgs::default()
+            });
+            assert!(server.unwrap_err().starts_with(&invalid));
+            let client = ClientConfig::load(&ClientArgs {
+                config: Some(file.path.clone()),
+                ..ClientArgs::default()
+            });
+            assert!(client.unwrap_err().starts_with(&invalid));
+        }
+    }
+
+    #[test]
+    fn messages_must_fit_the_udp_sequence() {
+        let limit = (BUFFER_SIZE - METADATA_BYTES - UDP_METADATA_BYTES) as u64 * MAX_UDP_PACKETS;
+        let at_limit = ServerConfig::load(&ServerArgs {
+            max_message_size: Some(limit),
+            ..ServerArgs::default()
+        });
+        assert_eq!(at_limit.unwrap().max_message_size, limit);
+        let above = ServerConfig::load(&ServerArgs {
+            max_message_size: Some(limit + 1),
+            ..ServerArgs::default()
+        });
+        assert_eq!(
+            above.unwrap_err(),
+            "max_message_size deve ser no máximo 32440320 com buffer_size 512"
+        );
+    }
+
+    #[test]
+    fn zero_sizes_are_rejected() {
+        let empty_messages = ServerConfig::load(&ServerArgs {
+            max_message_size: Some(0),
+            ..ServerArgs::default()
+        });
+        assert_eq!(
+            empty_messages.unwrap_err(),
+            "max_message_size deve ser maior que zero"
+        );
+        let empty_buffer = ServerConfig::load(&ServerArgs {
+            buffer_size: Some(0),
+            ..ServerArgs::default()
+        });
+        assert_eq!(
+            empty_buffer.unwrap_err(),
+            "buffer_size deve estar entre 18 e 65535"
+        );
+        let empty_buffer = ClientConfig::load(&ClientArgs {
+            buffer_size: Some(0),
+            ..ClientArgs::default()
+        });
+        assert_eq!(
+            empty_buffer.unwrap_err(),
+            "buffer_size deve estar entre 18 e 65535"
+        );
+    }
+
+    #[test]
+    fn invalid_addresses_are_rejected() {
+        for host in [
+            "",
+            "dois nomes",
+            "::1",
+            "-servidor",
+            "servidor..lab",
+            "lab:8080",
+        ] {
+            let expected = format!("host inválido: {0:?}", host);
+            let server = ServerConfig::load(&ServerArgs {
+                host: Some(host.to_string()),
+                ..ServerArgs::default()
+            });
+            assert_eq!(server.unwrap_err(), expected);
+            let client = ClientConfig::load(&ClientArgs {
+                host: Some(host.to_string()),
+                ..ClientArgs::default()
+            });
+            assert_eq!(client.unwrap_err(), expected);
+        }
+        for host in ["localhost", "127.0.0.1", "chat-01.lab.example"] {
+            let args = ServerArgs {
+                host: Some(host.to_string()),
+                ..ServerArgs::default()
+            };
+            assert_eq!(ServerConfig::load(&args).unwrap().host, host);
+        }
+
+        let client = ClientConfig::load(&ClientArgs {
+            tcp_port: Some(0),
+            ..ClientArgs::default()
+        });
+        assert_eq!(client.unwrap_err(), "tcp_port deve ser maior que zero");
+        let client = ClientConfig::load(&ClientArgs {
+            udp_port: Some(0),
+            ..ClientArgs::default()
+        });
+        assert_eq!(client.unwrap_err(), "udp_port deve ser maior que zero");
+    }
+}
@@ -1,4 +1,8 @@
+pub mod cli;
+pub mod commands;
 pub mod config;
+pub mod logging;
 pub mod models;
 pub mod network;
+pub mod ui;
 pub mod utilities;
@@ -0,0 +1,58 @@
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone)]
+pub enum LogOutput {
+    Stdout,
+    Stderr,
+    File(PathBuf),
+}
+
+pub fn init_logging(level: &str, output: Option<LogOutput>) -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let ansi = match output {
+        Some(LogOutput::Stdout) => std::io::stdout().is_terminal(),
+        Some(LogOutput::Stderr) => std::io::stderr().is_terminal(),
+        _ => false,
+    };
+    let (writer, guard) = match output {
+        Some(LogOutput::Stdout) => {
+            let (writer, guard) = tracing_appender::non_blocking(std::io::stdout());
+            (Some(writer), Some(guard))
+        }
+        Some(LogOutput::Stderr) => {
+            let (writer, guard) = tracing_appender::non_blocking(std::io::stderr());
+            (Some(writer), Some(guard))
+        }
+        Some(LogOutput::File(path)) => {
+            let directory = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            let prefix = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(String::from("server.log"));
+            let appender = rolling::daily(directory, prefix);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(writer), Some(guard))
+        }
+        None => (None, None),
+    };
+    let fmt_layer = writer.map(|writer| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+    });
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .init();
+    guard
+}
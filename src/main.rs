@@ -1,161 +1,168 @@
-use std::{env, io};
-use std::collections::HashMap;
-use std::io::{BufRead, stdin};
+use std::process;
 use std::sync::Arc;
 use std::time::Duration;
 
-use bimap::BiMap;
-use crossterm::execute;
-use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
-};
-use tokio::io::AsyncBufReadExt;
-use tokio::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
-use tokio::time;
-use tui::{Frame, Terminal};
-use tui::backend::{Backend, CrosstermBackend};
-use tui::layout::{Constraint, Direction, Layout};
-use tui::widgets::{Block, Borders, List, ListItem};
+use clap::Parser;
 
+use t1_lab_redes::cli::{Cli, Command};
+use t1_lab_redes::commands::{self, accounts, bench, list, send};
+use t1_lab_redes::config::ClientConfig;
+use t1_lab_redes::logging::{init_logging, LogOutput};
 use t1_lab_redes::network::client::Client;
+use t1_lab_redes::network::e2e::E2eKeys;
 use t1_lab_redes::network::server::Server;
 use t1_lab_redes::network::tcp_client::TcpClient;
-use t1_lab_redes::utilities::enums::MessageType;
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::ui::client::run_client_ui;
+use t1_lab_redes::ui::server::run_server_ui;
+use t1_lab_redes::ui::terminal::TerminalGuard;
+use t1_lab_redes::utilities::enums::Protocol;
+use t1_lab_redes::utilities::signal::shutdown_signal;
+
+/// Conecta um client com o transporte da configuração e executa `$body` com ele.
+macro_rules! with_client {
+    ($config:expr, |$client:ident| $body:expr) => {
+        match $config.transport {
+            Protocol::UDP => {
+                let $client = UdpClient::new(&$config)
+                    .await
+                    .map_err(|e| e as Box<dyn std::error::Error>)?;
+                $body
+            }
+            Protocol::TCP => {
+                let $client = TcpClient::new(&$config)
+                    .await
+                    .map_err(|e| e as Box<dyn std::error::Error>)?;
+                $body
+            }
+        }
+    };
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
 
-    match args.get(1).map(String::as_str) {
-        Some("server") => {
-            enable_raw_mode()?;
-            let mut stdout = io::stdout();
-            execute!(stdout, EnterAlternateScreen)?;
-            let server = Server::new();
-            let backend = CrosstermBackend::new(stdout);
-            let mut terminal = Terminal::new(backend)?;
-            let id_table = server.id_table.clone();
-            let name_table = server.name_table.clone();
-            let logs = server.log.clone();
-            tokio::spawn(async move {
-                server.start().await;
-            });
-            let ui_result = draw_server_ui(&mut terminal, id_table, name_table, logs).await;
-            disable_raw_mode()?;
-            execute!(io::stdout(), LeaveAlternateScreen)?;
-
-            ui_result
-        }
-        Some("client") => {
-            let udp = if let Some(arg) = args.get(2) {
-                arg == "udp"
-            } else {
-                false
-            };
-            let client = if udp {
-                TcpClient::new("Client UDP".to_string()).await.unwrap()
+    match cli.command {
+        Command::Server(command) => {
+            let server = Server::from_args(command.config)?;
+            if command.headless {
+                run_headless_server(server).await
             } else {
-                TcpClient::new("Client TCP".to_string()).await.unwrap()
-            };
-            let client_arc = Arc::new(Mutex::new(client));
-            let sender = {
-                let client = Arc::clone(&client_arc);
-                tokio::spawn(async move {
-                    let mut input = String::new();
-                    let mut stdin = stdin();
-                    println!("\nComandos disponiveis:\nmsg <id destino> <conteúdo>\n\n");
-                    while stdin.read_line(&mut input)? > 0 {
-                        let mut client = client.lock().await;
-                        let (message_type, destination_id, content) =
-                            client.create_command(input.clone());
-                        match message_type {
-                            MessageType::SetName => {
-                                client.set_name(content).await.unwrap();
-                            }
-                            MessageType::Text => {
-                                println!("Enviando mensagem para {}\n", destination_id);
-                                client.send_text(content, destination_id).await.unwrap();
-                            }
-                            _ => {}
-                        }
-                        input.clear();
-                    }
-                    Ok::<(), io::Error>(())
-                })
+                run_server(server).await
+            }
+        }
+        Command::Client(args) => {
+            let config = ClientConfig::load(&args)?;
+            let e2e = config.e2e.then(E2eKeys::generate);
+            with_client!(config, |client| run_client(Arc::new(client), e2e).await)
+        }
+        Command::Send(args) => {
+            let config = match commands::load_config(&args.config, "send") {
+                Ok(config) => config,
+                Err(e) => {
+                    let e = send::SendError::Usage(e);
+                    eprintln!("Erro: {0}", e);
+                    process::exit(e.exit_code());
+                }
             };
-
-            let receiver = {
-                let client = Arc::clone(&client_arc);
-                tokio::spawn(async move {
-                    loop {
-                        let mut client = client.lock().await;
-                        let message = client.listen().await.unwrap();
-                        println!(
-                            "\nMensagem Recebida de {}:\n{}\n",
-                            message.metadata.receiver_id,
-                            String::from_utf8(message.content).unwrap()
-                        );
-                    }
-                })
+            let log_guard = init_logging(&config.log_level, Some(LogOutput::Stderr));
+            let timeout = Duration::from_secs(args.timeout);
+            let result = match send::Payload::load(args.text, args.file.as_deref()) {
+                Ok(payload) => send::run(&config, &args.to, payload, timeout).await,
+                Err(e) => Err(e),
             };
-
-            let _ = tokio::try_join!(sender, receiver);
+            if let Err(e) = result {
+                eprintln!("Erro: {0}", e);
+                drop(log_guard);
+                process::exit(e.exit_code());
+            }
             Ok(())
         }
-        _ => {
-            disable_raw_mode()?;
-            execute!(io::stdout(), LeaveAlternateScreen)?;
-            Err(Box::try_from(io::Error::new(io::ErrorKind::Other, "Erro")).unwrap())
+        Command::List(args) => {
+            let config = commands::load_config(&args, "list")?;
+            let _log_guard = init_logging(&config.log_level, Some(LogOutput::Stderr));
+            with_client!(config, |client| list::run(&client)
+                .await
+                .map_err(|e| e as Box<dyn std::error::Error>))
         }
+        Command::Bench(args) => {
+            let config = commands::load_config(&args.config, "bench")?;
+            let _log_guard = init_logging(&config.log_level, Some(LogOutput::Stderr));
+            let report = with_client!(config, |client| bench::run(&client, args.count, args.size)
+                .await
+                .map_err(|e| e as Box<dyn std::error::Error>))?;
+            println!("{0}", report);
+            Ok(())
+        }
+        Command::Accounts(command) => Ok(accounts::run(command)?),
     }
 }
 
-async fn draw_server_ui<B: Backend>(
-    terminal: &mut Terminal<B>,
-    id_table: Arc<RwLock<BiMap<u16, String>>>,
-    name_table: Arc<RwLock<HashMap<u16, String>>>,
-    logs: Arc<RwLock<String>>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    loop {
-        let id_table = id_table.write().await;
-        let name_table = name_table.write().await;
-        let logs = logs.read().await;
-        terminal.draw(|f| {
-            render_clients(f, id_table, name_table, logs);
-        })?;
-        time::sleep(Duration::from_millis(500)).await;
+async fn run_server(server: Server) -> Result<(), Box<dyn std::error::Error>> {
+    let config = server.config().await;
+    let _log_guard = init_logging(
+        &config.log_level,
+        config.log_file.clone().map(LogOutput::File),
+    );
+    let runner = server.clone();
+    let mut server_task = tokio::spawn(async move { runner.start().await });
+    let (ui_result, stopped) = {
+        let mut guard = TerminalGuard::new()?;
+        tokio::select! {
+            result = run_server_ui(guard.terminal(), server.clone()) => (result, false),
+            result = &mut server_task => (Err(server_stopped(result)), true),
+            _ = shutdown_signal() => (Ok(()), false),
+        }
+    };
+    server.shutdown().await;
+    if !stopped {
+        let _ = server_task.await;
     }
+
+    ui_result
 }
 
-fn render_clients<B: Backend>(
-    f: &mut Frame<B>,
-    id_table: RwLockWriteGuard<BiMap<u16, String>>,
-    name_table: RwLockWriteGuard<HashMap<u16, String>>,
-    logs: RwLockReadGuard<String>,
-) {
-    let mut items: Vec<ListItem> = Vec::new();
-    items.push(ListItem::new("\n"));
-    for (id, addr) in id_table.iter() {
-        let name = match name_table.get(id) {
-            Some(name) => name,
-            None => "Sem nome",
-        };
-        let item = ListItem::new(format!("Client ID:{0}\n\"{1}\" - {2}\n", id, name, addr));
-        items.push(item);
+/// Erro de um servidor que parou sem `shutdown`: uma falha ao iniciar, como uma
+/// porta em uso, ou o fim inesperado da tarefa.
+fn server_stopped(
+    result: Result<Result<(), String>, tokio::task::JoinError>,
+) -> Box<dyn std::error::Error> {
+    match result {
+        Ok(Err(e)) => e.into(),
+        result => format!("Servidor encerrado inesperadamente: {0:?}", result).into(),
     }
-    let client_list =
-        List::new(items).block(Block::default().title("Server").borders(Borders::ALL));
+}
 
-    let lines = logs.lines();
-    let log_items: Vec<ListItem> = lines.map(|log| ListItem::new(log.to_string())).collect();
-    let log_list = List::new(log_items).block(Block::default().title("Logs").borders(Borders::ALL));
+async fn run_headless_server(server: Server) -> Result<(), Box<dyn std::error::Error>> {
+    let config = server.config().await;
+    let output = match &config.log_file {
+        Some(path) => LogOutput::File(path.clone()),
+        None => LogOutput::Stdout,
+    };
+    let _log_guard = init_logging(&config.log_level, Some(output));
+    let runner = server.clone();
+    let mut server_task = tokio::spawn(async move { runner.start().await });
+    tokio::select! {
+        result = &mut server_task => return Err(server_stopped(result)),
+        _ = shutdown_signal() => {}
+    }
+    server.shutdown().await;
+    let _ = server_task.await;
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(f.size());
+    Ok(())
+}
 
-    f.render_widget(client_list, chunks[0]);
-    f.render_widget(log_list, chunks[1]);
+async fn run_client<C: Client + Send + Sync + 'static>(
+    client: Arc<C>,
+    e2e: Option<E2eKeys>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut guard = TerminalGuard::new()?;
+    tokio::select! {
+        result = run_client_ui(guard.terminal(), client.clone(), e2e) => result,
+        _ = shutdown_signal() => {
+            let _ = client.disconnect().await;
+            Ok(())
+        }
+    }
 }
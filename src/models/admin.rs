@@ -0,0 +1,50 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Conteúdo (json) de um `Admin`. Só é aceito de contas administradoras ou da
+/// interface do próprio servidor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum AdminCommand {
+    Kick {
+        user: u16,
+    },
+    Rename {
+        user: u16,
+        name: String,
+    },
+    /// Aviso do servidor para um usuário, ou para todos sem `user`.
+    Notice {
+        user: Option<u16>,
+        text: String,
+    },
+    Stats,
+    ReloadConfig,
+}
+
+/// Resposta de `AdminCommand::Stats`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerStats {
+    pub users: usize,
+    pub sessions: usize,
+    pub authenticated: usize,
+    pub rooms: usize,
+    pub offline_messages: usize,
+    pub uptime_secs: u64,
+}
+
+impl fmt::Display for ServerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{0} usuários ({1} autenticados) em {2} sessões, {3} salas, {4} mensagens offline, no ar há {5}s",
+            self.users,
+            self.authenticated,
+            self.sessions,
+            self.rooms,
+            self.offline_messages,
+            self.uptime_secs
+        )
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Conteúdo de uma mensagem `Connection`. Sem senha nem token é enviado apenas o
+/// nome, como antes; com credenciais, um json `{"name", "password" | "token"}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Credentials {
+    pub fn anonymous(name: String) -> Self {
+        Credentials {
+            name,
+            ..Credentials::default()
+        }
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.password.is_none() && self.token.is_none()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        if self.is_anonymous() {
+            return self.name.as_bytes().to_vec();
+        }
+        serde_json::to_vec(self).unwrap()
+    }
+
+    pub fn decode(content: &[u8]) -> Self {
+        let mut credentials = match serde_json::from_slice::<Credentials>(content) {
+            Ok(credentials) => credentials,
+            Err(_) => Credentials::anonymous(String::from_utf8_lossy(content).to_string()),
+        };
+        credentials.name = credentials.name.trim().to_string();
+        credentials
+    }
+}
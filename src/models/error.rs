@@ -0,0 +1,48 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Motivo de uma recusa, para clients que precisam distinguir os casos sem
+/// depender do texto.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    RoomNotFound,
+    RoomExists,
+    NotInRoom,
+    NotOperator,
+    Banned,
+    InviteOnly,
+    WrongPassword,
+    Muted,
+    RateLimited,
+    MessageTooLarge,
+}
+
+/// Conteúdo (json) de um `Error` estruturado: o código e a mensagem para o
+/// usuário. Erros sem código continuam sendo só o texto.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ErrorBody {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ErrorBody {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn decode(content: &[u8]) -> Option<Self> {
+        serde_json::from_slice(content).ok()
+    }
+}
+
+impl fmt::Display for ErrorBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{0}", self.message)
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt;
+
+use chrono::{DateTime, Local, Utc};
+
+use crate::utilities::enums::{MessageType, Protocol, RateLimitAction};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Debug => "DEBUG",
+            Severity::Info => "INFO",
+            Severity::Warning => "WARN",
+            Severity::Error => "ERROR",
+        };
+        f.pad(label)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ServerEventKind {
+    Started {
+        tcp_port: u16,
+        udp_port: u16,
+    },
+    Stopping,
+    Stopped {
+        clients: usize,
+    },
+    Connected {
+        id: u16,
+        addr: String,
+        protocol: Protocol,
+    },
+    Registered {
+        id: u16,
+        name: String,
+    },
+    Disconnected {
+        id: u16,
+        name: String,
+        reason: Option<String>,
+    },
+    SessionClosed {
+        id: u16,
+        addr: String,
+        reason: Option<String>,
+    },
+    Renamed {
+        id: u16,
+        name: String,
+        by_server: bool,
+    },
+    /// `to` é 0 num `Broadcast`.
+    MessageRouted {
+        from: u16,
+        to: u16,
+        message_type: MessageType,
+        bytes: usize,
+        content: Option<String>,
+    },
+    Queued {
+        from: u16,
+        to: u16,
+        bytes: usize,
+    },
+    Flushed {
+        id: u16,
+        count: usize,
+    },
+    RoomCreated {
+        room: u16,
+        name: String,
+        by: u16,
+    },
+    RoomJoined {
+        room: u16,
+        id: u16,
+    },
+    RoomLeft {
+        room: u16,
+        id: u16,
+        closed: bool,
+    },
+    RoomMessage {
+        from: u16,
+        room: u16,
+        content: String,
+    },
+    RoomModerated {
+        room: u16,
+        by: u16,
+        action: String,
+    },
+    Notice {
+        id: u16,
+        text: String,
+    },
+    Kicked {
+        id: u16,
+    },
+    RateLimited {
+        id: u16,
+        addr: String,
+        reason: String,
+        action: RateLimitAction,
+    },
+    /// `by` 0 é a interface do servidor. `restart` lista as opções alteradas que
+    /// só valem depois de um reinício.
+    ConfigReloaded {
+        by: u16,
+        restart: Vec<String>,
+    },
+    Rejected {
+        id: u16,
+        reason: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl fmt::Display for ServerEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerEventKind::Started { tcp_port, udp_port } => write!(
+                f,
+                "Servidor executando TCP na porta {0} e UDP porta {1}",
+                tcp_port, udp_port
+            ),
+            ServerEventKind::Stopping => write!(f, "Servidor encerrando..."),
+            ServerEventKind::Stopped { clients } => {
+                write!(f, "Servidor encerrado. {0} clients desconectados", clients)
+            }
+            ServerEventKind::Connected { id, addr, protocol } => {
+                write!(f, "Nova conexão {0:?}: {1} - ID {2}", protocol, addr, id)
+            }
+            ServerEventKind::Registered { id, name } => {
+                write!(f, "Client ID {0} - Nome: {1} - Conectado", id, name)
+            }
+            ServerEventKind::Disconnected { id, name, reason } => match reason {
+                Some(reason) => write!(
+                    f,
+                    "Client ID {0} - Nome: {1} - Desconectado ({2})",
+                    id, name, reason
+                ),
+                None => write!(f, "Client ID {0} - Nome: {1} - Desconectado", id, name),
+            },
+            ServerEventKind::SessionClosed { id, addr, reason } => match reason {
+                Some(reason) => write!(
+                    f,
+                    "Client ID {0} - Sessão {1} encerrada ({2})",
+                    id, addr, reason
+                ),
+                None => write!(f, "Client ID {0} - Sessão {1} encerrada", id, addr),
+            },
+            ServerEventKind::Renamed {
+                id,
+                name,
+                by_server,
+            } => {
+                if *by_server {
+                    write!(f, "Client ID {0} renomeado pelo servidor: {1}", id, name)
+                } else {
+                    write!(f, "Client ID {0} - Novo nome: {1}", id, name)
+                }
+            }
+            ServerEventKind::MessageRouted {
+                from,
+                to,
+                message_type,
+                bytes,
+                content,
+            } => match content {
+                Some(content) if *to == 0 => write!(
+                    f,
+                    "Mensagem de {0} para todos: {1}",
+                    from,
+                    content.replace('\n', " ")
+                ),
+                Some(content) => write!(
+                    f,
+                    "Mensagem de {0} para {1}: {2}",
+                    from,
+                    to,
+                    content.replace('\n', " ")
+                ),
+                None => write!(
+                    f,
+                    "{0:?} de {1} para {2}: {3} bytes",
+                    message_type, from, to, bytes
+                ),
+            },
+            ServerEventKind::Queued { from, to, bytes } => write!(
+                f,
+                "Mensagem de {0} para {1} guardada na fila offline: {2} bytes",
+                from, to, bytes
+            ),
+            ServerEventKind::Flushed { id, count } => write!(
+                f,
+                "{0} mensagens da fila offline entregues ao Client ID {1}",
+                count, id
+            ),
+            ServerEventKind::RoomCreated { room, name, by } => {
+                write!(
+                    f,
+                    "Sala {0} ({1}) criada pelo Client ID {2}",
+                    room, name, by
+                )
+            }
+            ServerEventKind::RoomJoined { room, id } => {
+                write!(f, "Client ID {0} entrou na sala {1}", id, room)
+            }
+            ServerEventKind::RoomLeft { room, id, closed } => {
+                if *closed {
+                    write!(
+                        f,
+                        "Client ID {0} saiu da sala {1}, que foi fechada",
+                        id, room
+                    )
+                } else {
+                    write!(f, "Client ID {0} saiu da sala {1}", id, room)
+                }
+            }
+            ServerEventKind::RoomMessage {
+                from,
+                room,
+                content,
+            } => write!(
+                f,
+                "Mensagem de {0} na sala {1}: {2}",
+                from,
+                room,
+                content.replace('\n', " ")
+            ),
+            ServerEventKind::RoomModerated { room, by, action } => write!(
+                f,
+                "Sala {0} moderada pelo Client ID {1}: {2}",
+                room, by, action
+            ),
+            ServerEventKind::Notice { id: 0, text } => {
+                write!(f, "Aviso do servidor para todos: {0}", text)
+            }
+            ServerEventKind::Notice { id, text } => {
+                write!(f, "Aviso do servidor para ID {0}: {1}", id, text)
+            }
+            ServerEventKind::Kicked { id } => write!(f, "Client ID {0} expulso pelo servidor", id),
+            ServerEventKind::RateLimited {
+                id,
+                addr,
+                reason,
+                action,
+            } => write!(
+                f,
+                "Client ID {0} ({1}) acima do limite: {2} - ação {3:?}",
+                id, addr, reason, action
+            ),
+            ServerEventKind::ConfigReloaded { by, restart } => {
+                write!(f, "Configuração recarregada por ID {0}", by)?;
+                if !restart.is_empty() {
+                    write!(f, " (exigem reinício: {0})", restart.join(", "))?;
+                }
+                Ok(())
+            }
+            ServerEventKind::Rejected { id, reason } => {
+                write!(f, "Requisição do Client ID {0} recusada: {1}", id, reason)
+            }
+            ServerEventKind::Error { message } => write!(f, "{0}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerEvent {
+    pub timestamp: DateTime<Utc>,
+    pub severity: Severity,
+    pub kind: ServerEventKind,
+}
+
+impl ServerEvent {
+    pub fn new(severity: Severity, kind: ServerEventKind) -> Self {
+        ServerEvent {
+            timestamp: Utc::now(),
+            severity,
+            kind,
+        }
+    }
+
+    pub fn string(&self) -> String {
+        format!(
+            "{0} {1:>5} {2}",
+            self.timestamp.with_timezone(&Local).format("%H:%M:%S"),
+            self.severity,
+            self.kind
+        )
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::models::message::Message;
+use crate::utilities::enums::MessageType;
+
+/// Mensagem roteada pelo servidor, como fica no histórico. O texto só é guardado
+/// para mensagens Text; de File e Encrypted fica apenas o tamanho.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Milissegundos desde 1970-01-01 UTC.
+    pub timestamp: i64,
+    pub from: u16,
+    pub to: u16,
+    pub message_type: u8,
+    pub bytes: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Sala da mensagem; `to` é então o id da sala.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<u16>,
+}
+
+impl HistoryEntry {
+    /// Entrada ainda sem id; o id é atribuído pelo armazenamento.
+    pub fn routed(from: u16, to: u16, message: &Message) -> Self {
+        let message_type = message.metadata.message_type;
+        let content = if message_type == MessageType::Text {
+            Some(String::from_utf8_lossy(&message.content).to_string())
+        } else {
+            None
+        };
+        HistoryEntry {
+            id: 0,
+            timestamp: Utc::now().timestamp_millis(),
+            from,
+            to,
+            message_type: message_type.into(),
+            bytes: message.content.len(),
+            content,
+            room: None,
+        }
+    }
+
+    /// Mensagem de sala, com o texto e `to` igual ao id da sala.
+    pub fn in_room(from: u16, room: u16, message: &Message) -> Self {
+        HistoryEntry {
+            content: Some(String::from_utf8_lossy(&message.content).to_string()),
+            room: Some(room),
+            ..HistoryEntry::routed(from, room, message)
+        }
+    }
+}
+
+/// Conteúdo (json) de uma requisição `History`. Sem conteúdo, retorna a página
+/// mais recente. `before` pagina para trás a partir do menor id já recebido.
+/// Com `room`, consulta as mensagens da sala em vez das conversas diretas.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room: Option<u16>,
+}
@@ -1,15 +1,35 @@
+use bytes::Bytes;
+
+use crate::config::{METADATA_BYTES, UDP_METADATA_BYTES};
+use crate::models::admin::AdminCommand;
+use crate::models::credentials::Credentials;
+use crate::models::error::ErrorBody;
+use crate::models::history::{HistoryEntry, HistoryRequest};
 use crate::models::metadata::MsgMetadata;
+use crate::models::room::{RoomAction, RoomInfo, RoomJoin};
 use crate::utilities::enums::MessageType;
 
 #[derive(Debug, Clone)]
 pub struct Message {
     pub metadata: MsgMetadata,
-    pub content: Vec<u8>,
+    pub content: Bytes,
+}
+
+/// Mensagem pronta para o stream TCP: o cabeçalho já serializado e o conteúdo,
+/// que é o mesmo buffer da mensagem. Clonar um `Frame` não copia nenhum dos
+/// dois, então a mesma mensagem pode ser escrita em várias sessões.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub header: Bytes,
+    pub content: Bytes,
 }
 
 impl Message {
-    pub fn new(metadata: MsgMetadata, content: Vec<u8>) -> Message {
-        Message { metadata, content }
+    pub fn new(metadata: MsgMetadata, content: impl Into<Bytes>) -> Message {
+        Message {
+            metadata,
+            content: content.into(),
+        }
     }
 
     pub fn new_text(
@@ -19,7 +39,7 @@ impl Message {
         udp_id: Option<u16>,
         udp_seq: Option<u16>,
     ) -> Message {
-        let content_bytes = content.as_bytes().to_vec();
+        let content_bytes = Bytes::from(content);
         let metadata = MsgMetadata::new(
             key,
             receiver_id,
@@ -34,8 +54,29 @@ impl Message {
         }
     }
 
-    pub fn new_connection_request(key: u16, name: String) -> Message {
-        let content_bytes = name.as_bytes().to_vec();
+    pub fn new_file(
+        key: u16,
+        receiver_id: u16,
+        content: Vec<u8>,
+        udp_id: Option<u16>,
+        udp_seq: Option<u16>,
+    ) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            receiver_id,
+            MessageType::File,
+            content.len() as u64,
+            udp_id,
+            udp_seq,
+        );
+        Message {
+            metadata,
+            content: content.into(),
+        }
+    }
+
+    pub fn new_connection_request(key: u16, credentials: &Credentials) -> Message {
+        let content_bytes = credentials.encode();
         let metadata = MsgMetadata::new(
             key,
             0,
@@ -46,7 +87,7 @@ impl Message {
         );
         Message {
             metadata,
-            content: content_bytes,
+            content: content_bytes.into(),
         }
     }
 
@@ -54,7 +95,7 @@ impl Message {
         let metadata = MsgMetadata::new(key, 0, MessageType::ListClients, 0, None, None);
         Message {
             metadata,
-            content: Vec::new(),
+            content: Bytes::new(),
         }
     }
 
@@ -77,7 +118,7 @@ impl Message {
         );
         Message {
             metadata,
-            content: content_bytes,
+            content: content_bytes.into(),
         }
     }
 
@@ -98,7 +139,7 @@ impl Message {
         );
         Message {
             metadata,
-            content: content_bytes,
+            content: content_bytes.into(),
         }
     }
 
@@ -117,7 +158,289 @@ impl Message {
         );
         Message {
             metadata,
-            content: Vec::new(),
+            content: Bytes::new(),
+        }
+    }
+
+    pub fn new_error_response(key: u16, receiver_id: u16, reason: String) -> Message {
+        let content_bytes = reason.as_bytes().to_vec();
+        let metadata = MsgMetadata::new(
+            key,
+            receiver_id,
+            MessageType::Error,
+            content_bytes.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: content_bytes.into(),
+        }
+    }
+
+    pub fn new_coded_error(key: u16, receiver_id: u16, error: &ErrorBody) -> Message {
+        let content_bytes = serde_json::to_vec(error).unwrap();
+        Message::new_with_content(key, receiver_id, MessageType::Error, content_bytes)
+    }
+
+    /// Texto de um `Error`, com ou sem código.
+    pub fn error_reason(&self) -> String {
+        match ErrorBody::decode(&self.content) {
+            Some(error) => error.message,
+            None => String::from_utf8_lossy(&self.content).to_string(),
+        }
+    }
+
+    /// Texto para todos os clients conectados.
+    pub fn new_broadcast(key: u16, text: String) -> Message {
+        Message::new_with_content(key, 0, MessageType::Broadcast, text.into_bytes())
+    }
+
+    pub fn new_forward(key: u16, sender_id: u16, message: &Message) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            sender_id,
+            message.metadata.message_type,
+            message.content.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: message.content.clone(),
+        }
+    }
+
+    /// Confirma ao remetente que a mensagem `key`, guardada enquanto o destinatário
+    /// estava offline, foi entregue a ele.
+    pub fn new_delivered(key: u16, recipient_id: u16) -> Message {
+        let metadata = MsgMetadata::new(key, recipient_id, MessageType::Delivered, 0, None, None);
+        Message {
+            metadata,
+            content: Bytes::new(),
+        }
+    }
+
+    pub fn new_queued_response(key: u16, receiver_id: u16) -> Message {
+        let content_bytes = "Destinatário offline: mensagem guardada para entrega"
+            .as_bytes()
+            .to_vec();
+        let metadata = MsgMetadata::new(
+            key,
+            receiver_id,
+            MessageType::Success,
+            content_bytes.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: content_bytes.into(),
+        }
+    }
+
+    pub fn new_history_request(key: u16, peer_id: u16, request: &HistoryRequest) -> Message {
+        let content_bytes = serde_json::to_vec(request).unwrap();
+        let metadata = MsgMetadata::new(
+            key,
+            peer_id,
+            MessageType::History,
+            content_bytes.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: content_bytes.into(),
+        }
+    }
+
+    pub fn new_history_response(key: u16, peer_id: u16, entries: &[HistoryEntry]) -> Message {
+        let content_bytes = serde_json::to_vec(entries).unwrap();
+        let metadata = MsgMetadata::new(
+            key,
+            peer_id,
+            MessageType::Success,
+            content_bytes.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: content_bytes.into(),
+        }
+    }
+
+    pub fn new_create_room_request(key: u16, name: &str) -> Message {
+        Message::new_with_content(key, 0, MessageType::CreateRoom, name.as_bytes().to_vec())
+    }
+
+    /// `room_id` 0 procura a sala pelo nome.
+    pub fn new_join_room_request(key: u16, room_id: u16, join: &RoomJoin) -> Message {
+        Message::new_with_content(key, room_id, MessageType::JoinRoom, join.encode())
+    }
+
+    /// `room_id` 0 procura a sala pelo nome.
+    pub fn new_leave_room_request(key: u16, room_id: u16, name: &str) -> Message {
+        Message::new_with_content(
+            key,
+            room_id,
+            MessageType::LeaveRoom,
+            name.as_bytes().to_vec(),
+        )
+    }
+
+    /// Aviso do servidor ao membro tirado da sala (expulso ou banido).
+    pub fn new_room_removed(room_id: u16, reason: &str) -> Message {
+        Message::new_with_content(
+            0,
+            room_id,
+            MessageType::LeaveRoom,
+            reason.as_bytes().to_vec(),
+        )
+    }
+
+    pub fn new_moderate_room_request(key: u16, room_id: u16, action: &RoomAction) -> Message {
+        let content_bytes = serde_json::to_vec(action).unwrap();
+        Message::new_with_content(key, room_id, MessageType::ModerateRoom, content_bytes)
+    }
+
+    pub fn new_list_rooms_request(key: u16) -> Message {
+        Message::new_with_content(key, 0, MessageType::ListRooms, Vec::new())
+    }
+
+    pub fn new_room_message(key: u16, room_id: u16, text: String) -> Message {
+        Message::new_with_content(key, room_id, MessageType::RoomMessage, text.into_bytes())
+    }
+
+    /// Mensagem de sala entregue aos membros: o conteúdo começa com o id do
+    /// remetente (u16 little endian), 0 para avisos do servidor.
+    pub fn new_room_forward(key: u16, room_id: u16, sender_id: u16, content: &[u8]) -> Message {
+        let mut content_bytes = sender_id.to_le_bytes().to_vec();
+        content_bytes.extend_from_slice(content);
+        Message::new_with_content(key, room_id, MessageType::RoomMessage, content_bytes)
+    }
+
+    pub fn new_room_response(key: u16, room: &RoomInfo) -> Message {
+        let content_bytes = serde_json::to_vec(room).unwrap();
+        Message::new_with_content(key, room.id, MessageType::Success, content_bytes)
+    }
+
+    pub fn new_list_rooms_response(key: u16, rooms: &[RoomInfo]) -> Message {
+        let content_bytes = serde_json::to_vec(rooms).unwrap();
+        Message::new_with_content(key, 0, MessageType::Success, content_bytes)
+    }
+
+    pub fn new_admin_request(key: u16, command: &AdminCommand) -> Message {
+        let content_bytes = serde_json::to_vec(command).unwrap();
+        Message::new_with_content(key, 0, MessageType::Admin, content_bytes)
+    }
+
+    /// Success com um texto para o usuário.
+    pub fn new_text_response(key: u16, receiver_id: u16, text: &str) -> Message {
+        Message::new_with_content(
+            key,
+            receiver_id,
+            MessageType::Success,
+            text.as_bytes().to_vec(),
+        )
+    }
+
+    fn new_with_content(
+        key: u16,
+        receiver_id: u16,
+        message_type: MessageType,
+        content_bytes: Vec<u8>,
+    ) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            receiver_id,
+            message_type,
+            content_bytes.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: content_bytes.into(),
+        }
+    }
+
+    pub fn new_presence(receiver_id: u16, client_id: u16, name: String, online: bool) -> Message {
+        let content_json = serde_json::to_string(&(client_id, name, online)).unwrap();
+        let content_bytes = content_json.as_bytes().to_vec();
+        let metadata = MsgMetadata::new(
+            0,
+            receiver_id,
+            MessageType::Presence,
+            content_bytes.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: content_bytes.into(),
+        }
+    }
+
+    pub fn new_disconnect_request(key: u16) -> Message {
+        let metadata = MsgMetadata::new(key, 0, MessageType::Disconnect, 0, None, None);
+        Message {
+            metadata,
+            content: Bytes::new(),
+        }
+    }
+
+    pub fn new_public_key(key: u16, public_key: Vec<u8>) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            0,
+            MessageType::PublicKey,
+            public_key.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: public_key.into(),
+        }
+    }
+
+    pub fn new_public_key_request(key: u16, owner_id: u16) -> Message {
+        let metadata = MsgMetadata::new(key, owner_id, MessageType::PublicKey, 0, None, None);
+        Message {
+            metadata,
+            content: Bytes::new(),
+        }
+    }
+
+    pub fn new_public_key_response(key: u16, owner_id: u16, public_key: Vec<u8>) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            owner_id,
+            MessageType::Success,
+            public_key.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: public_key.into(),
+        }
+    }
+
+    pub fn new_encrypted(key: u16, receiver_id: u16, envelope: Vec<u8>) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            receiver_id,
+            MessageType::Encrypted,
+            envelope.len() as u64,
+            None,
+            None,
+        );
+        Message {
+            metadata,
+            content: envelope.into(),
         }
     }
 
@@ -130,14 +453,21 @@ impl Message {
         };
         Ok(Message {
             metadata,
-            content: data,
+            content: Bytes::from(data).slice(METADATA_BYTES + UDP_METADATA_BYTES..),
         })
     }
 
-    pub async fn serialize(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        bytes.extend(self.metadata.serialize().await.unwrap());
-        bytes.extend(&self.content);
+    pub fn frame(&self) -> Frame {
+        Frame {
+            header: self.metadata.serialize().into(),
+            content: self.content.clone(),
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.metadata.size() + self.content.len());
+        self.metadata.write_to(&mut bytes);
+        bytes.extend_from_slice(&self.content);
         bytes
     }
 
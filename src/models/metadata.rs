@@ -1,9 +1,9 @@
 use std::io::{Error, ErrorKind};
 
+use bytes::BufMut;
 use chrono::{DateTime, Utc};
-use tokio::io;
-use tokio::io::AsyncWriteExt;
 
+use crate::config::{METADATA_BYTES, UDP_METADATA_BYTES};
 use crate::utilities::enums::MessageType;
 
 #[derive(Debug, Clone)]
@@ -37,34 +37,52 @@ impl MsgMetadata {
         }
     }
 
-    pub async fn serialize(&self) -> io::Result<Vec<u8>> {
-        let mut bytes = Vec::new();
-        let key = self.key.to_le_bytes();
-        bytes.write_all(&key).await?;
-        let receiver_id_bytes = self.receiver_id.to_le_bytes();
-        bytes.write_all(&receiver_id_bytes).await?;
-
-        bytes.push(self.message_type as u8);
-
-        let message_length_bytes = self.message_length.to_le_bytes();
-        bytes.write_all(&message_length_bytes).await?;
+    /// Tamanho do cabeçalho serializado: 13 bytes, mais 4 em pacotes UDP.
+    pub fn size(&self) -> usize {
+        let udp_fields = [self.udp_id, self.udp_seq].iter().flatten().count();
+        METADATA_BYTES + 2 * udp_fields
+    }
 
+    /// Escreve o cabeçalho em `buf`.
+    pub fn write_to<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u16_le(self.key);
+        buf.put_u16_le(self.receiver_id);
+        buf.put_u8(self.message_type as u8);
+        buf.put_u64_le(self.message_length);
         if let Some(udp_id) = self.udp_id {
-            let udp_id_bytes = udp_id.to_le_bytes();
-            bytes.write_all(&udp_id_bytes).await?;
+            buf.put_u16_le(udp_id);
         }
         if let Some(udp_seq) = self.udp_seq {
-            let udp_seq_bytes = udp_seq.to_le_bytes();
-            bytes.write_all(&udp_seq_bytes).await?;
+            buf.put_u16_le(udp_seq);
         }
+    }
 
-        Ok(bytes)
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size());
+        self.write_to(&mut bytes);
+        bytes
     }
 
     pub fn deserialize(data: &[u8], is_udp: bool) -> Result<MsgMetadata, Error> {
-        let key = u16::from_le_bytes(data[0..2].try_into().unwrap());
+        let header_size = if is_udp {
+            METADATA_BYTES + UDP_METADATA_BYTES
+        } else {
+            METADATA_BYTES
+        };
+        if data.len() < header_size {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Cabeçalho incompleto: {0} de {1} bytes",
+                    data.len(),
+                    header_size
+                ),
+            ));
+        }
+
+        let key = read_u16(data, 0);
 
-        let receiver_id = u16::from_le_bytes(data[2..4].try_into().unwrap());
+        let receiver_id = read_u16(data, 2);
 
         let message_type = match MessageType::try_from(data[4]) {
             Ok(message_type) => message_type,
@@ -76,31 +94,13 @@ impl MsgMetadata {
             }
         };
 
-        let message_length = match data[5..13].try_into() {
-            Ok(bytes) => u64::from_le_bytes(bytes),
-            Err(_) => {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
-                    "Tamanho da mensagem inválido",
-                ))
-            }
-        };
+        let message_length = read_u64(data, 5);
 
-        let udp_id;
-        let udp_seq;
-        if is_udp {
-            udp_id = match data[13..15].try_into() {
-                Ok(bytes) => Some(u16::from_le_bytes(bytes)),
-                Err(_) => return Err(Error::new(ErrorKind::InvalidData, "ID UDP inválido")),
-            };
-            udp_seq = match data[15..17].try_into() {
-                Ok(bytes) => Some(u16::from_le_bytes(bytes)),
-                Err(_) => return Err(Error::new(ErrorKind::InvalidData, "Sequência UDP inválida")),
-            };
+        let (udp_id, udp_seq) = if is_udp {
+            (Some(read_u16(data, 13)), Some(read_u16(data, 15)))
         } else {
-            udp_id = None;
-            udp_seq = None;
-        }
+            (None, None)
+        };
 
         Ok(MsgMetadata {
             key,
@@ -113,6 +113,16 @@ impl MsgMetadata {
         })
     }
 
+    /// Key e tamanho do conteúdo de um cabeçalho TCP, lidos mesmo quando o
+    /// restante é inválido, para que o quadro possa ser descartado e respondido.
+    pub fn peek_frame(data: &[u8]) -> Option<(u16, u64)> {
+        if data.len() < METADATA_BYTES {
+            return None;
+        }
+        let message_length = read_u64(data, 5);
+        Some((read_u16(data, 0), message_length))
+    }
+
     pub fn string(&self) -> String {
         format!(
             "ID: {0}\nDestinatário: {1}\nTimestamp: {2}\nTipo de Mensagem: {3:?}\nTamanho da Mensagem: {4}",
@@ -124,3 +134,13 @@ impl MsgMetadata {
         self.message_length == content_size
     }
 }
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
@@ -1,2 +1,9 @@
+pub mod admin;
+pub mod credentials;
+pub mod error;
+pub mod event;
+pub mod history;
 pub mod message;
 pub mod metadata;
+pub mod room;
+pub mod validation;
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// Sala como os clients a enxergam: resposta de `CreateRoom` e `JoinRoom`, e
+/// cada item da lista de `ListRooms`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: u16,
+    pub name: String,
+    pub members: Vec<u16>,
+    #[serde(default)]
+    pub owner: u16,
+    #[serde(default)]
+    pub operators: Vec<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub invite_only: bool,
+    /// Se a entrada exige senha.
+    #[serde(default)]
+    pub protected: bool,
+}
+
+/// Conteúdo de um `JoinRoom`: só o nome, ou json quando a sala tem senha.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomJoin {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl RoomJoin {
+    pub fn encode(&self) -> Vec<u8> {
+        match &self.password {
+            None => self.name.as_bytes().to_vec(),
+            Some(_) => serde_json::to_vec(self).unwrap(),
+        }
+    }
+
+    pub fn decode(content: &[u8]) -> Self {
+        let mut join = serde_json::from_slice(content).unwrap_or_else(|_| RoomJoin {
+            name: String::from_utf8_lossy(content).to_string(),
+            password: None,
+        });
+        join.name = join.name.trim().to_string();
+        join
+    }
+}
+
+/// Conteúdo (json) de um `ModerateRoom`. Ações sobre membros usam o id do
+/// usuário; `Unban` usa a identidade informada no aviso do banimento.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RoomAction {
+    Kick { user: u16 },
+    Ban { user: u16 },
+    Unban { identity: String },
+    Op { user: u16 },
+    Deop { user: u16 },
+    Mute { user: u16 },
+    Unmute { user: u16 },
+    Invite { user: u16 },
+    Topic { topic: Option<String> },
+    InviteOnly { enabled: bool },
+    Password { password: Option<String> },
+}
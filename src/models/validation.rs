@@ -0,0 +1,44 @@
+use std::str;
+
+use crate::models::credentials::Credentials;
+use crate::models::error::{ErrorBody, ErrorCode};
+use crate::models::message::Message;
+use crate::utilities::enums::MessageType;
+
+/// Tamanho máximo, em caracteres, do nome de um client.
+pub const MAX_NAME_CHARS: usize = 32;
+
+/// Nomes têm de 1 a `MAX_NAME_CHARS` caracteres: letras, dígitos, `-`, `_`,
+/// `.` e espaços entre as palavras.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Nome vazio".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("Nome com mais de {0} caracteres", MAX_NAME_CHARS));
+    }
+    let allowed = |c: char| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.');
+    if name.trim() != name || !name.chars().all(allowed) {
+        return Err(format!("Nome inválido: {0}", name));
+    }
+    Ok(())
+}
+
+/// Confere o conteúdo das mensagens que o servidor interpreta como texto: Text
+/// precisa ser UTF-8 e Connection e SetName precisam de um nome válido.
+pub fn validate_payload(message: &Message) -> Result<(), ErrorBody> {
+    let invalid = |reason: String| ErrorBody::new(ErrorCode::InvalidRequest, reason);
+    let text = || {
+        str::from_utf8(&message.content)
+            .map_err(|_| invalid("Conteúdo não é UTF-8 válido".to_string()))
+    };
+    match message.metadata.message_type {
+        MessageType::Text | MessageType::Broadcast => text().map(|_| ()),
+        MessageType::SetName => validate_name(text()?.trim()).map_err(invalid),
+        MessageType::Connection => {
+            text()?;
+            validate_name(&Credentials::decode(&message.content).name).map_err(invalid)
+        }
+        _ => Ok(()),
+    }
+}
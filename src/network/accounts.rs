@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::models::credentials::Credentials;
+use crate::models::validation;
+
+const TOKEN_PREFIX: &str = "t1_";
+const TOKEN_BYTES: usize = 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    /// Id do usuário, o mesmo em todas as conexões da conta.
+    pub id: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub token_hashes: Vec<String>,
+    /// Pode usar as mensagens `Admin`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub admin: bool,
+}
+
+impl Account {
+    /// Verifica senha (argon2) ou token (SHA-256). Lento por causa do argon2:
+    /// deve ser chamado fora do runtime assíncrono.
+    pub fn verify(&self, credentials: &Credentials) -> bool {
+        if let (Some(password), Some(hash)) = (&credentials.password, &self.password_hash) {
+            return Argon2::default()
+                .verify_password(password.as_bytes(), hash.as_str())
+                .is_ok();
+        }
+        if let Some(token) = &credentials.token {
+            let token_hash = hash_token(token);
+            return self.token_hashes.contains(&token_hash);
+        }
+        false
+    }
+}
+
+/// Registro de contas gravado em um arquivo json, indexado pelo nome.
+#[derive(Debug, Clone)]
+pub struct Accounts {
+    path: PathBuf,
+    accounts: BTreeMap<String, Account>,
+}
+
+impl Accounts {
+    /// Carrega o arquivo de contas. Um arquivo inexistente é um registro vazio.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let accounts = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Arquivo de contas inválido {0}: {1}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("Falha ao ler {0}: {1}", path.display(), e)),
+        };
+        Ok(Accounts {
+            path: path.to_path_buf(),
+            accounts,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.accounts).unwrap();
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, content)
+            .and_then(|_| fs::rename(&temporary, &self.path))
+            .map_err(|e| format!("Falha ao gravar {0}: {1}", self.path.display(), e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Account> {
+        self.accounts.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.accounts.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.accounts.keys()
+    }
+
+    /// Cria a conta, se necessário, e define a senha.
+    pub fn set_password(&mut self, name: &str, password: &str) -> Result<(), String> {
+        validate_account_name(name)?;
+        if password.is_empty() {
+            return Err("Senha vazia".to_string());
+        }
+        let hash = Argon2::default()
+            .hash_password(password.as_bytes())
+            .map_err(|e| format!("Falha ao gerar hash da senha: {0}", e))?;
+        self.entry(name).password_hash = Some(hash.to_string());
+        Ok(())
+    }
+
+    /// Cria a conta, se necessário, e gera um novo token. O token só é exibido
+    /// aqui; o arquivo guarda apenas o hash.
+    pub fn add_token(&mut self, name: &str) -> Result<String, String> {
+        validate_account_name(name)?;
+        let bytes: [u8; TOKEN_BYTES] = rand::random();
+        let token = format!("{0}{1}", TOKEN_PREFIX, to_hex(&bytes));
+        self.entry(name).token_hashes.push(hash_token(&token));
+        Ok(token)
+    }
+
+    /// Concede ou retira o papel de administrador de uma conta existente.
+    pub fn set_admin(&mut self, name: &str, admin: bool) -> Result<(), String> {
+        match self.accounts.get_mut(name) {
+            Some(account) => {
+                account.admin = admin;
+                Ok(())
+            }
+            None => Err(format!("Conta {0} não encontrada", name)),
+        }
+    }
+
+    pub fn is_admin(&self, name: &str) -> bool {
+        self.accounts.get(name).is_some_and(|account| account.admin)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.accounts.remove(name).is_some()
+    }
+
+    /// Ids de contas ficam reservados mesmo com a conta offline.
+    pub fn is_reserved(&self, id: u16) -> bool {
+        self.accounts.values().any(|account| account.id == id)
+    }
+
+    fn entry(&mut self, name: &str) -> &mut Account {
+        if !self.accounts.contains_key(name) {
+            let id = loop {
+                let id = rand::random::<u16>();
+                if id != 0 && !self.is_reserved(id) {
+                    break id;
+                }
+            };
+            let account = Account {
+                id,
+                password_hash: None,
+                token_hashes: Vec::new(),
+                admin: false,
+            };
+            self.accounts.insert(name.to_string(), account);
+        }
+        self.accounts.get_mut(name).unwrap()
+    }
+}
+
+fn validate_account_name(name: &str) -> Result<(), String> {
+    validation::validate_name(name)
+        .map_err(|reason| format!("Nome de conta inválido: \"{0}\" ({1})", name, reason))
+}
+
+fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{0:02x}", byte)).collect()
+}
@@ -1,20 +1,183 @@
 use std::error::Error;
+use std::future::Future;
 
+use crate::models::admin::AdminCommand;
+use crate::models::credentials::Credentials;
 use crate::models::message::Message;
-use crate::utilities::enums::MessageType;
+use crate::models::room::RoomAction;
+use crate::utilities::enums::{MessageType, Protocol};
+
+pub type ClientResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
 pub trait Client {
     fn get_id(&self) -> u16;
     fn get_name(&self) -> String;
-    async fn get_log(&self) -> String;
-    async fn set_name(&mut self, name: String) -> Result<u16, Box<dyn Error>>;
-    async fn send_text(
-        &mut self,
+    fn get_protocol(&self) -> Protocol;
+    fn get_log(&self) -> impl Future<Output = String> + Send;
+    fn set_name(&self, name: String) -> impl Future<Output = ClientResult<u16>> + Send;
+    fn send_text(
+        &self,
         content: String,
         destination_id: u16,
-    ) -> Result<u16, Box<dyn Error>>;
-    async fn send_connection_request(&mut self, name: String) -> Result<(), Box<dyn Error>>;
-    async fn send(&mut self, message_bytes: Vec<u8>) -> Result<(), Box<dyn Error>>;
-    async fn listen(&mut self) -> Result<Message, Box<dyn Error>>;
+    ) -> impl Future<Output = ClientResult<u16>> + Send;
+    fn send_connection_request(
+        &self,
+        credentials: Credentials,
+    ) -> impl Future<Output = ClientResult<u16>> + Send;
+    fn list_clients(&self) -> impl Future<Output = ClientResult<u16>> + Send;
+    fn disconnect(&self) -> impl Future<Output = ClientResult<u16>> + Send;
+    fn send(&self, message: Message) -> impl Future<Output = ClientResult<()>> + Send;
+    fn listen(&self) -> impl Future<Output = ClientResult<Message>> + Send;
     fn create_command(&self, command: String) -> (MessageType, u16, String);
 }
+
+pub fn parse_command(input: &str) -> (MessageType, u16, String) {
+    let input = input.trim();
+    let (command, rest) = match input.split_once(' ') {
+        Some((command, rest)) => (command, rest.trim()),
+        None => (input, ""),
+    };
+    match command {
+        "msg" => match rest.split_once(' ') {
+            Some((dest, content)) => match dest.parse::<u16>() {
+                Ok(destination_id) => (MessageType::Text, destination_id, content.to_string()),
+                Err(_) => (MessageType::Error, 0, format!("ID inválido: {0}", dest)),
+            },
+            None => (
+                MessageType::Error,
+                0,
+                "Uso: msg <id destino> <conteúdo>".to_string(),
+            ),
+        },
+        "nome" if !rest.is_empty() => (MessageType::SetName, 0, rest.to_string()),
+        "historico" => {
+            let (peer, search) = rest.split_once(' ').unwrap_or((rest, ""));
+            match peer.parse::<u16>() {
+                Ok(peer_id) => (MessageType::History, peer_id, search.trim().to_string()),
+                Err(_) => (
+                    MessageType::Error,
+                    0,
+                    "Uso: historico <id> [termo]".to_string(),
+                ),
+            }
+        }
+        "busca" if !rest.is_empty() => (MessageType::History, 0, rest.to_string()),
+        "criar" if !rest.is_empty() => (MessageType::CreateRoom, 0, rest.to_string()),
+        "entrar" if !rest.is_empty() => (MessageType::JoinRoom, 0, rest.to_string()),
+        "deixar" if !rest.is_empty() => (MessageType::LeaveRoom, 0, rest.to_string()),
+        "salas" => (MessageType::ListRooms, 0, String::new()),
+        "lista" => (MessageType::ListClients, 0, String::new()),
+        "sair" => (MessageType::Disconnect, 0, String::new()),
+        _ => (
+            MessageType::Error,
+            0,
+            format!("Comando desconhecido: {0}", input),
+        ),
+    }
+}
+
+/// Comandos de moderação, usados na aba de uma sala. Retorna `None` se o
+/// comando não for de moderação, ou o uso correto se faltar argumento.
+pub fn parse_room_action(input: &str) -> Option<Result<RoomAction, String>> {
+    let input = input.trim();
+    let (command, rest) = match input.split_once(' ') {
+        Some((command, rest)) => (command, rest.trim()),
+        None => (input, ""),
+    };
+    let user = |usage: &str| {
+        rest.parse::<u16>()
+            .map_err(|_| format!("Uso: {0} <id>", usage))
+    };
+    let optional = (!rest.is_empty()).then(|| rest.to_string());
+    let action = match command {
+        "expulsar" => user("expulsar").map(|user| RoomAction::Kick { user }),
+        "banir" => user("banir").map(|user| RoomAction::Ban { user }),
+        "desbanir" if !rest.is_empty() => Ok(RoomAction::Unban {
+            identity: rest.to_string(),
+        }),
+        "desbanir" => Err("Uso: desbanir <identidade>".to_string()),
+        "op" => user("op").map(|user| RoomAction::Op { user }),
+        "deop" => user("deop").map(|user| RoomAction::Deop { user }),
+        "silenciar" => user("silenciar").map(|user| RoomAction::Mute { user }),
+        "dessilenciar" => user("dessilenciar").map(|user| RoomAction::Unmute { user }),
+        "convidar" => user("convidar").map(|user| RoomAction::Invite { user }),
+        "topico" => Ok(RoomAction::Topic { topic: optional }),
+        "convite" => match rest {
+            "sim" => Ok(RoomAction::InviteOnly { enabled: true }),
+            "nao" | "não" => Ok(RoomAction::InviteOnly { enabled: false }),
+            _ => Err("Uso: convite sim|nao".to_string()),
+        },
+        "senha" => Ok(RoomAction::Password { password: optional }),
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Comandos de `/admin`. Retorna o uso correto se o comando for desconhecido ou
+/// faltar argumento.
+pub fn parse_admin_command(input: &str) -> Result<AdminCommand, String> {
+    let input = input.trim();
+    let (command, rest) = match input.split_once(' ') {
+        Some((command, rest)) => (command, rest.trim()),
+        None => (input, ""),
+    };
+    let (target, text) = match rest.split_once(' ') {
+        Some((target, text)) => (target, text.trim()),
+        None => (rest, ""),
+    };
+    match command {
+        "expulsar" => match rest.parse::<u16>() {
+            Ok(user) => Ok(AdminCommand::Kick { user }),
+            Err(_) => Err("Uso: /admin expulsar <id>".to_string()),
+        },
+        "renomear" => match target.parse::<u16>() {
+            Ok(user) if !text.is_empty() => Ok(AdminCommand::Rename {
+                user,
+                name: text.to_string(),
+            }),
+            _ => Err("Uso: /admin renomear <id> <nome>".to_string()),
+        },
+        "aviso" if !text.is_empty() => match target {
+            "todos" => Ok(AdminCommand::Notice {
+                user: None,
+                text: text.to_string(),
+            }),
+            _ => match target.parse::<u16>() {
+                Ok(user) => Ok(AdminCommand::Notice {
+                    user: Some(user),
+                    text: text.to_string(),
+                }),
+                Err(_) => Err("Uso: /admin aviso <id|todos> <texto>".to_string()),
+            },
+        },
+        "aviso" => Err("Uso: /admin aviso <id|todos> <texto>".to_string()),
+        "stats" => Ok(AdminCommand::Stats),
+        "recarregar" => Ok(AdminCommand::ReloadConfig),
+        _ => Err("Uso: /admin expulsar|renomear|aviso|stats|recarregar".to_string()),
+    }
+}
+
+pub(crate) fn expect_response(message: &Message, key: u16) -> Option<ClientResult<u16>> {
+    if message.metadata.key != key {
+        return None;
+    }
+    match message.metadata.message_type {
+        MessageType::Success => Some(Ok(message.metadata.receiver_id)),
+        MessageType::Error => Some(Err(message.error_reason().into())),
+        _ => None,
+    }
+}
+
+pub async fn wait_response<C: Client>(client: &C, key: u16) -> ClientResult<Message> {
+    loop {
+        let message = client.listen().await?;
+        if message.metadata.key != key {
+            continue;
+        }
+        match message.metadata.message_type {
+            MessageType::Success => return Ok(message),
+            MessageType::Error => return Err(message.error_reason().into()),
+            _ => {}
+        }
+    }
+}
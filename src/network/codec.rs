@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::config::{MAX_MESSAGE_SIZE, METADATA_BYTES};
+use crate::models::error::{ErrorBody, ErrorCode};
+use crate::models::message::{Frame, Message};
+use crate::models::metadata::MsgMetadata;
+
+/// Maior reserva de uma vez para o conteúdo que ainda não chegou. O buffer
+/// cresce conforme os bytes chegam, e não pelo tamanho declarado no cabeçalho.
+const RESERVE_CHUNK: usize = 64 * 1024;
+
+/// Mensagem recusada antes de ser processada; o remetente pode ser respondido
+/// com `error` usando a `key` dela.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejected {
+    pub key: u16,
+    pub error: ErrorBody,
+}
+
+impl Rejected {
+    /// Recusa de uma mensagem de `size` bytes acima do limite.
+    pub fn too_large(key: u16, size: u64, max_size: u64) -> Self {
+        Rejected {
+            key,
+            error: ErrorBody::new(
+                ErrorCode::MessageTooLarge,
+                format!(
+                    "Mensagem de {0} bytes excede o limite de {1} bytes",
+                    size, max_size
+                ),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{0}", self.error)
+    }
+}
+
+impl Error for Rejected {}
+
+/// Codec das mensagens no stream TCP: cabeçalho de 13 bytes seguido do
+/// conteúdo. Mensagens com conteúdo acima de `max_size` viram um `Rejected` e
+/// o conteúdo delas é descartado conforme chega, sem ser guardado; o stream
+/// segue alinhado no cabeçalho seguinte. Um cabeçalho inválido é um erro
+/// `InvalidData`, que encerra a conexão.
+#[derive(Debug, Clone)]
+pub struct MessageCodec {
+    max_size: u64,
+    skipping: u64,
+}
+
+impl MessageCodec {
+    pub fn new(max_size: u64) -> Self {
+        MessageCodec {
+            max_size,
+            skipping: 0,
+        }
+    }
+
+    pub fn set_max_size(&mut self, max_size: u64) {
+        self.max_size = max_size;
+    }
+
+    fn skip(&mut self, src: &mut BytesMut) {
+        let skipped = self.skipping.min(src.len() as u64);
+        src.advance(skipped as usize);
+        self.skipping -= skipped;
+    }
+}
+
+impl Default for MessageCodec {
+    fn default() -> Self {
+        MessageCodec::new(MAX_MESSAGE_SIZE)
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Result<Message, Rejected>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        self.skip(src);
+        if self.skipping > 0 {
+            return Ok(None);
+        }
+        let Some((key, message_length)) = MsgMetadata::peek_frame(src) else {
+            src.reserve(METADATA_BYTES - src.len());
+            return Ok(None);
+        };
+        // Com o cabeçalho inválido, o tamanho declarado não merece confiança e
+        // não há como achar o próximo cabeçalho: o stream é um erro.
+        let metadata = MsgMetadata::deserialize(&src[..METADATA_BYTES], false).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Metadata inválida: {0}", e),
+            )
+        })?;
+        let frame_size = usize::try_from(message_length)
+            .ok()
+            .and_then(|length| length.checked_add(METADATA_BYTES))
+            .filter(|_| message_length <= self.max_size);
+        let Some(frame_size) = frame_size else {
+            let rejected = Rejected::too_large(key, message_length, self.max_size);
+            src.advance(METADATA_BYTES);
+            self.skipping = message_length;
+            self.skip(src);
+            return Ok(Some(Err(rejected)));
+        };
+        if src.len() < frame_size {
+            src.reserve((frame_size - src.len()).min(RESERVE_CHUNK));
+            return Ok(None);
+        }
+        src.advance(METADATA_BYTES);
+        let content = src.split_to(message_length as usize).freeze();
+        Ok(Some(Ok(Message::new(metadata, content))))
+    }
+}
+
+impl Encoder<Frame> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> io::Result<()> {
+        dst.reserve(frame.header.len() + frame.content.len());
+        dst.extend_from_slice(&frame.header);
+        dst.extend_from_slice(&frame.content);
+        Ok(())
+    }
+}
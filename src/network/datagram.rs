@@ -0,0 +1,43 @@
+use std::io;
+use std::net::SocketAddr;
+
+use futures::future::BoxFuture;
+use tokio::net::UdpSocket;
+
+/// Transporte de datagramas do servidor e do client UDP. Fora dos testes é o
+/// próprio `UdpSocket`; `network::simulated` fornece uma rede em memória com
+/// perdas, duplicação, atraso e MTU configuráveis.
+pub trait DatagramTransport: Send + Sync {
+    /// Envia um datagrama. Como no UDP, um datagrama perdido no caminho não é
+    /// um erro.
+    fn send_to<'a>(
+        &'a self,
+        datagram: &'a [u8],
+        target: SocketAddr,
+    ) -> BoxFuture<'a, io::Result<usize>>;
+    /// Aguarda o próximo datagrama, de qualquer origem.
+    fn recv_from<'a>(&'a self, buf: &'a mut [u8])
+        -> BoxFuture<'a, io::Result<(usize, SocketAddr)>>;
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+impl DatagramTransport for UdpSocket {
+    fn send_to<'a>(
+        &'a self,
+        datagram: &'a [u8],
+        target: SocketAddr,
+    ) -> BoxFuture<'a, io::Result<usize>> {
+        Box::pin(UdpSocket::send_to(self, datagram, target))
+    }
+
+    fn recv_from<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> BoxFuture<'a, io::Result<(usize, SocketAddr)>> {
+        Box::pin(UdpSocket::recv_from(self, buf))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+}
@@ -0,0 +1,123 @@
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::utilities::enums::MessageType;
+
+pub const PUBLIC_KEY_BYTES: usize = 32;
+const NONCE_BYTES: usize = 24;
+const KEY_INFO: &[u8] = b"t1-lab-redes e2e";
+
+/// Par de chaves X25519 do client. Mensagens `Encrypted` carregam o envelope
+/// `chave pública do remetente (32) | nonce (24) | texto cifrado | tag (16)`;
+/// o texto cifrado contém o tipo original (Text ou File) seguido do conteúdo.
+pub struct E2eKeys {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+pub struct Opened {
+    pub message_type: MessageType,
+    pub content: Vec<u8>,
+    pub sender_key: [u8; PUBLIC_KEY_BYTES],
+}
+
+impl E2eKeys {
+    pub fn generate() -> Self {
+        let secret = StaticSecret::from(rand::random::<[u8; 32]>());
+        let public = PublicKey::from(&secret);
+        E2eKeys { secret, public }
+    }
+
+    pub fn public_key(&self) -> [u8; PUBLIC_KEY_BYTES] {
+        self.public.to_bytes()
+    }
+
+    pub fn seal(
+        &self,
+        recipient_key: &[u8; PUBLIC_KEY_BYTES],
+        message_type: MessageType,
+        content: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        let recipient = PublicKey::from(*recipient_key);
+        let cipher = self.cipher(&self.public, &recipient)?;
+        let nonce: [u8; NONCE_BYTES] = rand::random();
+        let mut plaintext = Vec::with_capacity(content.len() + 1);
+        plaintext.push(message_type.into());
+        plaintext.extend_from_slice(content);
+        let aad = [self.public.as_bytes().as_slice(), recipient_key].concat();
+        let ciphertext = cipher
+            .encrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "Falha ao cifrar mensagem".to_string())?;
+        let mut envelope = Vec::with_capacity(PUBLIC_KEY_BYTES + NONCE_BYTES + ciphertext.len());
+        envelope.extend_from_slice(self.public.as_bytes());
+        envelope.extend_from_slice(&nonce);
+        envelope.extend(ciphertext);
+        Ok(envelope)
+    }
+
+    pub fn open(&self, envelope: &[u8]) -> Result<Opened, String> {
+        if envelope.len() < PUBLIC_KEY_BYTES + NONCE_BYTES {
+            return Err("Envelope cifrado incompleto".to_string());
+        }
+        let (sender_key, rest) = envelope.split_at(PUBLIC_KEY_BYTES);
+        let (nonce, ciphertext) = rest.split_at(NONCE_BYTES);
+        let sender_key: [u8; PUBLIC_KEY_BYTES] = sender_key.try_into().unwrap();
+        let sender = PublicKey::from(sender_key);
+        let cipher = self.cipher(&sender, &self.public)?;
+        let aad = [sender_key.as_slice(), self.public.as_bytes()].concat();
+        let nonce: [u8; NONCE_BYTES] = nonce.try_into().unwrap();
+        let plaintext = cipher
+            .decrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "Mensagem cifrada inválida".to_string())?;
+        let (message_type, content) = plaintext
+            .split_first()
+            .ok_or("Mensagem cifrada vazia".to_string())?;
+        let message_type = match MessageType::try_from(*message_type)? {
+            message_type @ (MessageType::Text | MessageType::File) => message_type,
+            _ => return Err("Tipo de mensagem cifrada inválido".to_string()),
+        };
+        Ok(Opened {
+            message_type,
+            content: content.to_vec(),
+            sender_key,
+        })
+    }
+
+    /// A chave simétrica depende do segredo compartilhado e das duas chaves
+    /// públicas, na ordem remetente e destinatário.
+    fn cipher(
+        &self,
+        sender: &PublicKey,
+        recipient: &PublicKey,
+    ) -> Result<XChaCha20Poly1305, String> {
+        let peer = if sender == &self.public {
+            recipient
+        } else {
+            sender
+        };
+        let shared = self.secret.diffie_hellman(peer);
+        if !shared.was_contributory() {
+            return Err("Chave pública inválida".to_string());
+        }
+        let hkdf = Hkdf::<Sha256>::new(None, shared.as_bytes());
+        let mut key = [0u8; 32];
+        let info = [KEY_INFO, sender.as_bytes(), recipient.as_bytes()].concat();
+        hkdf.expand(&info, &mut key).unwrap();
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+}
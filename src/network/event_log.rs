@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, warn};
+
+use crate::models::event::{ServerEvent, ServerEventKind, Severity};
+
+#[derive(Debug)]
+pub struct EventLog {
+    events: Mutex<VecDeque<ServerEvent>>,
+    capacity: usize,
+    sender: broadcast::Sender<ServerEvent>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        EventLog {
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            sender,
+        }
+    }
+
+    pub fn emit(&self, severity: Severity, kind: ServerEventKind) {
+        let event = ServerEvent::new(severity, kind);
+        match severity {
+            Severity::Debug => debug!(event = ?event.kind, "{0}", event.kind),
+            Severity::Info => info!(event = ?event.kind, "{0}", event.kind),
+            Severity::Warning => warn!(event = ?event.kind, "{0}", event.kind),
+            Severity::Error => error!(event = ?event.kind, "{0}", event.kind),
+        }
+        let mut events = self.events.lock().unwrap();
+        while !events.is_empty() && events.len() >= self.capacity {
+            events.pop_front();
+        }
+        // Com capacidade 0 nada fica guardado; os eventos só vão aos inscritos.
+        if self.capacity > 0 {
+            events.push_back(event.clone());
+        }
+        let _ = self.sender.send(event);
+    }
+
+    pub fn recent(&self) -> Vec<ServerEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn subscribe(&self) -> (Vec<ServerEvent>, broadcast::Receiver<ServerEvent>) {
+        let events = self.events.lock().unwrap();
+        (events.iter().cloned().collect(), self.sender.subscribe())
+    }
+}
@@ -0,0 +1,300 @@
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::task;
+
+use crate::config::{
+    ServerConfig, HISTORY_PAGE_SIZE, MAX_HISTORY_PAGE_SIZE, MEMORY_HISTORY_CAPACITY,
+};
+use crate::models::history::{HistoryEntry, HistoryRequest};
+
+const READ_CHUNK: u64 = 64 * 1024;
+
+/// Armazenamento do histórico de mensagens. As chamadas são síncronas e podem
+/// ir ao disco, então o servidor as faz por `append_all` e `query`, fora do
+/// runtime e depois de liberar as tabelas.
+pub trait HistoryStore: Send + Sync {
+    /// Registra a entrada com o próximo id e a retorna.
+    fn append(&self, entry: HistoryEntry) -> Result<HistoryEntry, String>;
+    /// As `limit` entradas mais recentes que casam com o filtro, em ordem de id.
+    fn query(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, String>;
+}
+
+/// Registra `entries`, em ordem, numa thread de bloqueio.
+pub async fn append_all(
+    store: Arc<dyn HistoryStore>,
+    entries: Vec<HistoryEntry>,
+) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        entries
+            .into_iter()
+            .try_for_each(|entry| store.append(entry).map(|_| ()))
+    })
+    .await
+    .map_err(|e| format!("Falha ao gravar o histórico: {0}", e))?
+}
+
+/// Consulta `store` numa thread de bloqueio.
+pub async fn query(
+    store: Arc<dyn HistoryStore>,
+    filter: HistoryFilter,
+) -> Result<Vec<HistoryEntry>, String> {
+    task::spawn_blocking(move || store.query(&filter))
+        .await
+        .map_err(|e| format!("Falha ao ler o histórico: {0}", e))?
+}
+
+/// Escolhe o armazenamento da configuração: arquivo de log com `history_file`,
+/// memória sem ele.
+pub fn open(config: &ServerConfig) -> Result<Arc<dyn HistoryStore>, String> {
+    match &config.history_file {
+        Some(path) => Ok(Arc::new(LogHistory::open(path)?)),
+        None => Ok(Arc::new(MemoryHistory::default())),
+    }
+}
+
+/// Filtro de uma consulta: conversas de `user`, com `peer` se informado, ou as
+/// mensagens de `room` a partir de `since`.
+#[derive(Debug, Clone)]
+pub struct HistoryFilter {
+    pub user: u16,
+    pub peer: Option<u16>,
+    pub room: Option<u16>,
+    pub since: Option<i64>,
+    pub before: Option<u64>,
+    pub limit: usize,
+    pub search: Option<String>,
+}
+
+impl HistoryFilter {
+    /// `peer` 0 consulta todas as conversas do usuário.
+    pub fn new(user: u16, peer: u16, request: HistoryRequest) -> Self {
+        HistoryFilter {
+            user,
+            peer: (peer != 0).then_some(peer),
+            room: request.room,
+            since: None,
+            before: request.before,
+            limit: request
+                .limit
+                .unwrap_or(HISTORY_PAGE_SIZE)
+                .clamp(1, MAX_HISTORY_PAGE_SIZE),
+            search: request
+                .search
+                .map(|search| search.trim().to_lowercase())
+                .filter(|search| !search.is_empty()),
+        }
+    }
+
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let selected = match self.room {
+            Some(room) => {
+                entry.room == Some(room) && self.since.is_none_or(|since| entry.timestamp >= since)
+            }
+            None => entry.room.is_none() && self.in_conversation(entry),
+        };
+        if !selected {
+            return false;
+        }
+        if self.before.is_some_and(|before| entry.id >= before) {
+            return false;
+        }
+        match &self.search {
+            Some(search) => entry
+                .content
+                .as_ref()
+                .is_some_and(|content| content.to_lowercase().contains(search)),
+            None => true,
+        }
+    }
+
+    fn in_conversation(&self, entry: &HistoryEntry) -> bool {
+        let other = if entry.from == self.user {
+            entry.to
+        } else if entry.to == self.user {
+            entry.from
+        } else {
+            return false;
+        };
+        self.peer.is_none_or(|peer| peer == other)
+    }
+
+    /// Recebe as entradas da mais nova para a mais antiga e para de lê-las ao
+    /// completar a página.
+    fn latest<E: Borrow<HistoryEntry>>(
+        &self,
+        newest_first: impl Iterator<Item = E>,
+    ) -> Vec<HistoryEntry> {
+        let mut page: Vec<HistoryEntry> = newest_first
+            .filter(|entry| self.matches(entry.borrow()))
+            .take(self.limit)
+            .map(|entry| entry.borrow().clone())
+            .collect();
+        page.reverse();
+        page
+    }
+}
+
+/// Histórico só em memória, perdido ao reiniciar o servidor. Guarda as
+/// `MEMORY_HISTORY_CAPACITY` entradas mais recentes.
+#[derive(Default)]
+pub struct MemoryHistory {
+    entries: Mutex<(VecDeque<HistoryEntry>, u64)>,
+}
+
+impl HistoryStore for MemoryHistory {
+    fn append(&self, mut entry: HistoryEntry) -> Result<HistoryEntry, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let (entries, last_id) = &mut *entries;
+        *last_id += 1;
+        entry.id = *last_id;
+        if entries.len() == MEMORY_HISTORY_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry.clone());
+        Ok(entry)
+    }
+
+    fn query(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, String> {
+        let entries = self.entries.lock().unwrap();
+        Ok(filter.latest(entries.0.iter().rev()))
+    }
+}
+
+/// Histórico num arquivo só de acréscimo, uma entrada json por linha. Consultas
+/// leem o arquivo de trás para frente, em blocos, até completar a página;
+/// linhas inválidas (ex.: cortadas numa queda) são ignoradas.
+pub struct LogHistory {
+    path: PathBuf,
+    writer: Mutex<(File, u64)>,
+}
+
+impl LogHistory {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let last_id = match File::open(path) {
+            Ok(file) => newest_first(file).next().map_or(0, |entry| entry.id),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(format!("Falha ao ler {0}: {1}", path.display(), e)),
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Falha ao criar {0}: {1}", parent.display(), e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Falha ao abrir {0}: {1}", path.display(), e))?;
+        // Termina uma última linha incompleta para não emendá-la na próxima entrada.
+        let complete = match file.seek(SeekFrom::End(-1)) {
+            Ok(_) => {
+                let mut last = [0u8];
+                file.read_exact(&mut last).is_ok_and(|_| last[0] == b'\n')
+            }
+            Err(_) => true,
+        };
+        if !complete {
+            file.write_all(b"\n")
+                .map_err(|e| format!("Falha ao gravar {0}: {1}", path.display(), e))?;
+        }
+        Ok(LogHistory {
+            path: path.to_path_buf(),
+            writer: Mutex::new((file, last_id)),
+        })
+    }
+}
+
+impl HistoryStore for LogHistory {
+    fn append(&self, mut entry: HistoryEntry) -> Result<HistoryEntry, String> {
+        let mut writer = self.writer.lock().unwrap();
+        let (file, last_id) = &mut *writer;
+        entry.id = *last_id + 1;
+        let mut line = serde_json::to_vec(&entry).unwrap();
+        line.push(b'\n');
+        file.write_all(&line)
+            .map_err(|e| format!("Falha ao gravar {0}: {1}", self.path.display(), e))?;
+        *last_id = entry.id;
+        Ok(entry)
+    }
+
+    fn query(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, String> {
+        let file = File::open(&self.path)
+            .map_err(|e| format!("Falha ao ler {0}: {1}", self.path.display(), e))?;
+        Ok(filter.latest(newest_first(file)))
+    }
+}
+
+/// Entradas do arquivo da última linha para a primeira. Os ids crescem com a
+/// posição no arquivo, então a primeira é a de maior id.
+fn newest_first(file: File) -> impl Iterator<Item = HistoryEntry> {
+    ReverseLines::new(file).filter_map(|line| serde_json::from_slice(&line).ok())
+}
+
+/// Linhas de um arquivo, da última para a primeira, lidas em blocos de
+/// `READ_CHUNK` bytes a partir do fim. Para na primeira falha de leitura.
+struct ReverseLines {
+    file: File,
+    position: u64,
+    partial: Vec<u8>,
+    lines: Vec<Vec<u8>>,
+}
+
+impl ReverseLines {
+    fn new(mut file: File) -> Self {
+        let position = file.seek(SeekFrom::End(0)).unwrap_or(0);
+        ReverseLines {
+            file,
+            position,
+            partial: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Lê o bloco anterior a `position` e separa as linhas completas dele.
+    fn read_chunk(&mut self) -> std::io::Result<()> {
+        let size = READ_CHUNK.min(self.position);
+        self.position -= size;
+        self.file.seek(SeekFrom::Start(self.position))?;
+        let mut chunk = vec![0u8; size as usize];
+        self.file.read_exact(&mut chunk)?;
+        chunk.append(&mut self.partial);
+        let mut parts = chunk.split(|byte| *byte == b'\n');
+        // O início do bloco pode ser o fim de uma linha do bloco anterior.
+        if self.position > 0 {
+            self.partial = parts.next().unwrap_or_default().to_vec();
+        }
+        self.lines.extend(
+            parts
+                .filter(|line| !line.is_empty())
+                .map(|line| line.to_vec()),
+        );
+        Ok(())
+    }
+}
+
+impl Iterator for ReverseLines {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if let Some(line) = self.lines.pop() {
+                return Some(line);
+            }
+            if self.position == 0 {
+                return None;
+            }
+            if self.read_chunk().is_err() {
+                self.position = 0;
+                return None;
+            }
+        }
+    }
+}
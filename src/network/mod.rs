@@ -1,5 +1,20 @@
+pub mod accounts;
 pub mod client;
+pub mod codec;
+pub mod datagram;
+pub mod e2e;
+pub mod event_log;
+pub mod history;
+pub mod offline;
+pub mod rate_limit;
+pub mod rooms;
 pub mod server;
+pub mod sessions;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulated;
 mod tcp;
 pub mod tcp_client;
-mod udp;
+pub mod tls;
+pub mod udp;
+pub mod udp_client;
+pub mod udp_crypto;
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::config::ServerConfig;
+use crate::models::message::Message;
+use crate::models::metadata::MsgMetadata;
+use crate::utilities::enums::MessageType;
+
+/// Mensagem guardada para um usuário offline. `from` é o remetente, ou o
+/// destinatário original no caso de uma confirmação `Delivered`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedMessage {
+    pub key: u16,
+    pub from: u16,
+    pub message_type: u8,
+    #[serde(with = "base64_content")]
+    pub content: Vec<u8>,
+    pub queued_at: i64,
+}
+
+impl QueuedMessage {
+    pub fn new(key: u16, from: u16, message_type: MessageType, content: Vec<u8>) -> Self {
+        QueuedMessage {
+            key,
+            from,
+            message_type: message_type.into(),
+            content,
+            queued_at: Utc::now().timestamp(),
+        }
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        MessageType::try_from(self.message_type).unwrap_or(MessageType::Text)
+    }
+
+    /// Reconstrói a mensagem como o destinatário a receberia se estivesse online.
+    pub fn to_message(&self) -> Message {
+        match self.message_type() {
+            MessageType::Delivered => Message::new_delivered(self.key, self.from),
+            message_type => {
+                let metadata = MsgMetadata::new(
+                    self.key,
+                    self.from,
+                    message_type,
+                    self.content.len() as u64,
+                    None,
+                    None,
+                );
+                Message::new(metadata, self.content.clone())
+            }
+        }
+    }
+}
+
+/// O conteúdo vai para o arquivo em base64. Arquivos gravados antes disso,
+/// com o conteúdo como lista de bytes, continuam sendo lidos.
+mod base64_content {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Content {
+        Encoded(String),
+        Bytes(Vec<u8>),
+    }
+
+    pub fn serialize<S: Serializer>(content: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(content))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match Content::deserialize(deserializer)? {
+            Content::Encoded(encoded) => STANDARD.decode(encoded).map_err(serde::de::Error::custom),
+            Content::Bytes(bytes) => Ok(bytes),
+        }
+    }
+}
+
+/// Filas de mensagens por usuário, em ordem de chegada. Com `offline_queue_file`
+/// as filas sobrevivem a reinícios: o servidor pega uma `Snapshot` das filas
+/// alteradas de tempos em tempos e a grava fora do runtime.
+#[derive(Debug)]
+pub struct OfflineQueue {
+    path: Option<PathBuf>,
+    ttl: i64,
+    max_messages: usize,
+    max_bytes: usize,
+    max_per_sender: usize,
+    queues: BTreeMap<u16, VecDeque<QueuedMessage>>,
+    dirty: bool,
+}
+
+impl OfflineQueue {
+    pub fn new(config: &ServerConfig) -> Self {
+        OfflineQueue {
+            path: config.offline_queue_file.clone(),
+            ttl: config.offline_ttl as i64,
+            max_messages: config.offline_max_messages,
+            max_bytes: config.offline_max_bytes,
+            max_per_sender: config.offline_max_per_sender,
+            queues: BTreeMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Lê as filas gravadas, se houver arquivo, descartando as mensagens vencidas.
+    pub fn load(&mut self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.queues = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Fila offline inválida {0}: {1}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("Falha ao ler {0}: {1}", path.display(), e)),
+        };
+        self.prune();
+        Ok(())
+    }
+
+    /// Guarda a mensagem no fim da fila do usuário, respeitando os limites de
+    /// quantidade e de bytes e a cota de cada remetente dentro deles, para que
+    /// um só remetente não ocupe a fila inteira.
+    pub fn push(&mut self, recipient: u16, message: QueuedMessage) -> Result<(), String> {
+        self.prune();
+        let queue = self.queues.get(&recipient);
+        let queued = queue.map_or(0, VecDeque::len);
+        let bytes: usize = queue
+            .into_iter()
+            .flatten()
+            .map(|queued| queued.content.len())
+            .sum();
+        if queued >= self.max_messages || bytes + message.content.len() > self.max_bytes {
+            return Err(format!("Fila offline do ID {0} cheia", recipient));
+        }
+        let from_sender = queue
+            .into_iter()
+            .flatten()
+            .filter(|queued| queued.from == message.from)
+            .count();
+        if from_sender >= self.max_per_sender {
+            return Err(format!(
+                "Cota do remetente na fila offline do ID {0} esgotada",
+                recipient
+            ));
+        }
+        self.queues.entry(recipient).or_default().push_back(message);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Retira todas as mensagens ainda válidas do usuário, na ordem em que chegaram.
+    pub fn take(&mut self, recipient: u16) -> Vec<QueuedMessage> {
+        self.prune();
+        let taken = self.queues.remove(&recipient);
+        self.dirty |= taken.is_some();
+        taken.map(Vec::from).unwrap_or_default()
+    }
+
+    pub fn queued(&self, recipient: u16) -> usize {
+        self.queues.get(&recipient).map_or(0, VecDeque::len)
+    }
+
+    /// Total de mensagens guardadas, de todos os usuários.
+    pub fn len(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// Aplica os limites de uma configuração recarregada. O arquivo das filas
+    /// só muda com um reinício.
+    pub fn set_limits(&mut self, config: &ServerConfig) {
+        self.ttl = config.offline_ttl as i64;
+        self.max_messages = config.offline_max_messages;
+        self.max_bytes = config.offline_max_bytes;
+        self.max_per_sender = config.offline_max_per_sender;
+    }
+
+    fn prune(&mut self) {
+        let oldest = Utc::now().timestamp() - self.ttl;
+        let before = self.len();
+        self.queues.retain(|_, queue| {
+            queue.retain(|queued| queued.queued_at >= oldest);
+            !queue.is_empty()
+        });
+        self.dirty |= self.len() != before;
+    }
+
+    /// As filas serializadas, se mudaram desde a última snapshot e há arquivo;
+    /// sem arquivo, elas ficam só em memória.
+    pub fn snapshot(&mut self) -> Option<Snapshot> {
+        let path = self.path.clone().filter(|_| self.dirty)?;
+        self.dirty = false;
+        Some(Snapshot {
+            path,
+            content: serde_json::to_vec(&self.queues).unwrap(),
+        })
+    }
+}
+
+/// Filas serializadas por `OfflineQueue::snapshot`, prontas para ir ao disco.
+pub struct Snapshot {
+    path: PathBuf,
+    content: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Grava num arquivo temporário e o renomeia, para que uma queda no meio
+    /// não deixe o arquivo das filas pela metade. Bloqueia.
+    pub fn write(&self) -> Result<(), String> {
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, &self.content)
+            .and_then(|_| fs::rename(&temporary, &self.path))
+            .map_err(|e| format!("Falha ao gravar {0}: {1}", self.path.display(), e))
+    }
+}
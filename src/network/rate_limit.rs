@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use crate::config::ServerConfig;
+use crate::utilities::enums::RateLimitAction;
+
+/// Acima disso, os baldes de IPs cheios (sem uso recente) são descartados.
+const MAX_IDLE_IPS: usize = 1024;
+
+/// Balde de fichas: enche `rate` fichas por segundo até `capacity`. Uma cobrança
+/// maior que a capacidade passa com o balde cheio e o deixa negativo, para que
+/// mensagens grandes não fiquem bloqueadas para sempre.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, burst: u64) -> Self {
+        let capacity = (rate * burst.max(1)) as f64;
+        TokenBucket {
+            rate: rate as f64,
+            capacity,
+            tokens: capacity,
+        }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.tokens = (self.tokens + self.rate * elapsed.as_secs_f64()).min(self.capacity);
+    }
+
+    /// Sem limite quando `rate` é 0.
+    fn allows(&self, amount: f64) -> bool {
+        self.rate == 0.0 || self.tokens >= amount.min(self.capacity)
+    }
+
+    fn is_full(&self) -> bool {
+        self.rate == 0.0 || self.tokens >= self.capacity
+    }
+}
+
+/// Limites de quantidade e de bytes de uma sessão ou de um IP.
+#[derive(Debug, Clone)]
+struct Budget {
+    messages: TokenBucket,
+    bytes: TokenBucket,
+    updated: Instant,
+}
+
+impl Budget {
+    fn new(messages: u64, bytes: u64, burst: u64) -> Self {
+        Budget {
+            messages: TokenBucket::new(messages, burst),
+            bytes: TokenBucket::new(bytes, burst),
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.messages.refill(elapsed);
+        self.bytes.refill(elapsed);
+        self.updated = now;
+    }
+
+    /// O limite que `messages` mensagens com `bytes` bytes excederiam, sem
+    /// cobrar nada.
+    fn exceeded(&self, messages: u64, bytes: usize) -> Option<&'static str> {
+        if messages > 0 && !self.messages.allows(messages as f64) {
+            return Some("mensagens");
+        }
+        if !self.bytes.allows(bytes as f64) {
+            return Some("bytes");
+        }
+        None
+    }
+
+    fn charge(&mut self, messages: u64, bytes: usize) {
+        self.messages.tokens -= messages as f64;
+        self.bytes.tokens -= bytes as f64;
+    }
+}
+
+/// Mensagem recusada pelo limitador e a ação configurada para o caso.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limited {
+    pub action: RateLimitAction,
+    pub reason: String,
+}
+
+/// Limites de envio por sessão e por IP de origem, em mensagens e em bytes por
+/// segundo, com rajadas de até `rate_burst` segundos.
+#[derive(Debug)]
+pub struct RateLimiter {
+    session_messages: u64,
+    session_bytes: u64,
+    ip_messages: u64,
+    ip_bytes: u64,
+    burst: u64,
+    action: RateLimitAction,
+    mute: Duration,
+    sessions: HashMap<String, Budget>,
+    ips: HashMap<IpAddr, Budget>,
+    muted: HashMap<String, Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &ServerConfig) -> Self {
+        let mut limiter = RateLimiter {
+            session_messages: 0,
+            session_bytes: 0,
+            ip_messages: 0,
+            ip_bytes: 0,
+            burst: 0,
+            action: RateLimitAction::Drop,
+            mute: Duration::ZERO,
+            sessions: HashMap::new(),
+            ips: HashMap::new(),
+            muted: HashMap::new(),
+        };
+        limiter.configure(config);
+        limiter
+    }
+
+    /// Aplica os limites da configuração. Os baldes recomeçam cheios; sessões
+    /// silenciadas continuam silenciadas.
+    pub fn configure(&mut self, config: &ServerConfig) {
+        self.session_messages = config.rate_messages;
+        self.session_bytes = config.rate_bytes;
+        self.ip_messages = config.ip_rate_messages;
+        self.ip_bytes = config.ip_rate_bytes;
+        self.burst = config.rate_burst;
+        self.action = config.rate_action;
+        self.mute = Duration::from_secs(config.rate_mute_secs);
+        self.sessions.clear();
+        self.ips.clear();
+    }
+
+    /// Cobra `messages` mensagens e `bytes` bytes da sessão `addr` e do IP dela.
+    /// Ao exceder um limite, nada é cobrado e a ação configurada é retornada;
+    /// uma sessão silenciada tem tudo descartado até o fim do silêncio.
+    pub fn check(
+        &mut self,
+        addr: &str,
+        ip: Option<IpAddr>,
+        messages: u64,
+        bytes: usize,
+    ) -> Result<(), Limited> {
+        let now = Instant::now();
+        if let Some(until) = self.muted.get(addr).copied() {
+            if until > now {
+                return Err(Limited {
+                    action: RateLimitAction::Drop,
+                    reason: format!(
+                        "Sessão silenciada por excesso de mensagens por mais {0}s",
+                        until.duration_since(now).as_secs() + 1
+                    ),
+                });
+            }
+            self.muted.remove(addr);
+        }
+        let session = self
+            .sessions
+            .entry(addr.to_string())
+            .or_insert_with(|| Budget::new(self.session_messages, self.session_bytes, self.burst));
+        session.refill(now);
+        let mut exceeded = session
+            .exceeded(messages, bytes)
+            .map(|limit| format!("Limite de {0} da sessão excedido", limit));
+        if let Some(ip) = ip {
+            let budget = self.ip_budget(ip, now);
+            if exceeded.is_none() {
+                exceeded = budget
+                    .exceeded(messages, bytes)
+                    .map(|limit| format!("Limite de {0} do IP {1} excedido", limit, ip));
+            }
+        }
+        let Some(reason) = exceeded else {
+            self.sessions.get_mut(addr).unwrap().charge(messages, bytes);
+            if let Some(ip) = ip {
+                self.ips.get_mut(&ip).unwrap().charge(messages, bytes);
+            }
+            return Ok(());
+        };
+        let reason = match self.action {
+            RateLimitAction::Mute => {
+                self.muted.insert(addr.to_string(), now + self.mute);
+                format!("{0}: silenciado por {1}s", reason, self.mute.as_secs())
+            }
+            _ => reason,
+        };
+        Err(Limited {
+            action: self.action,
+            reason,
+        })
+    }
+
+    /// Cobra uma nova sessão (conexão TCP ou primeiro datagrama UDP de um
+    /// endereço) do IP de origem, antes que ela receba um id: cada sessão
+    /// aberta custa uma mensagem do limite do IP.
+    pub fn admit(&mut self, ip: IpAddr) -> bool {
+        let budget = self.ip_budget(ip, Instant::now());
+        if budget.exceeded(1, 0).is_some() {
+            return false;
+        }
+        budget.charge(1, 0);
+        true
+    }
+
+    /// Descarta o estado de uma sessão encerrada.
+    pub fn forget(&mut self, addr: &str) {
+        self.sessions.remove(addr);
+        self.muted.remove(addr);
+    }
+
+    fn ip_budget(&mut self, ip: IpAddr, now: Instant) -> &mut Budget {
+        if self.ips.len() >= MAX_IDLE_IPS && !self.ips.contains_key(&ip) {
+            self.ips.retain(|_, budget| {
+                budget.refill(now);
+                !(budget.messages.is_full() && budget.bytes.is_full())
+            });
+        }
+        let budget = self
+            .ips
+            .entry(ip)
+            .or_insert_with(|| Budget::new(self.ip_messages, self.ip_bytes, self.burst));
+        budget.refill(now);
+        budget
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::Utc;
+
+use crate::models::error::{ErrorBody, ErrorCode};
+use crate::models::message::Message;
+use crate::models::room::{RoomAction, RoomInfo};
+
+/// Sala aberta. O dono e os operadores moderam a sala; o dono é o único que
+/// nomeia operadores e não pode ser expulso. Banimentos valem para identidades
+/// (`conta:<nome>` ou `ip:<endereço>`), não para ids, que mudam a cada conexão
+/// de usuários sem conta.
+#[derive(Debug, Clone)]
+pub struct Room {
+    pub id: u16,
+    pub name: String,
+    pub members: BTreeSet<u16>,
+    pub owner: u16,
+    pub operators: BTreeSet<u16>,
+    pub muted: BTreeSet<u16>,
+    pub invited: BTreeSet<u16>,
+    pub banned: BTreeSet<String>,
+    pub topic: Option<String>,
+    pub invite_only: bool,
+    /// Milissegundos desde 1970-01-01 UTC. Ids de salas são reaproveitados, então
+    /// o histórico da sala começa aqui.
+    pub created: i64,
+    password: Option<String>,
+}
+
+/// Resultado da saída de um membro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Departure {
+    /// A sala ficou vazia e foi fechada.
+    pub closed: bool,
+    /// O dono saiu e a sala passou para este membro.
+    pub new_owner: Option<u16>,
+}
+
+impl Room {
+    pub fn info(&self) -> RoomInfo {
+        RoomInfo {
+            id: self.id,
+            name: self.name.clone(),
+            members: self.members.iter().copied().collect(),
+            owner: self.owner,
+            operators: self.operators.iter().copied().collect(),
+            topic: self.topic.clone(),
+            invite_only: self.invite_only,
+            protected: self.password.is_some(),
+        }
+    }
+
+    pub fn is_operator(&self, user: u16) -> bool {
+        user == self.owner || self.operators.contains(&user)
+    }
+
+    /// A mensagem para cada membro, menos `except`.
+    pub fn fan_out(&self, except: u16, message: &Message) -> Vec<(u16, Message)> {
+        self.members
+            .iter()
+            .filter(|member| **member != except)
+            .map(|member| (*member, message.clone()))
+            .collect()
+    }
+
+    /// Aviso do servidor (remetente 0) para os membros, menos `except`.
+    pub fn notice(&self, except: u16, text: &str) -> Vec<(u16, Message)> {
+        let notice =
+            Message::new_room_forward(Message::generate_key(), self.id, 0, text.as_bytes());
+        self.fan_out(except, &notice)
+    }
+
+    /// Recusa mensagens de quem não é membro ou está silenciado.
+    pub fn check_speaker(&self, user: u16) -> Result<(), ErrorBody> {
+        self.check_member(user)?;
+        if self.muted.contains(&user) {
+            return Err(ErrorBody::new(
+                ErrorCode::Muted,
+                format!("Você está silenciado na sala {0}", self.name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Aplica uma ação de moderação de `actor`. `identities` são as identidades
+    /// do alvo de um `Ban`.
+    pub fn apply(
+        &mut self,
+        actor: u16,
+        action: &RoomAction,
+        identities: &[String],
+    ) -> Result<(), ErrorBody> {
+        self.check_member(actor)?;
+        if !self.is_operator(actor) {
+            return Err(ErrorBody::new(
+                ErrorCode::NotOperator,
+                format!("Apenas operadores da sala {0} podem fazer isso", self.name),
+            ));
+        }
+        match action {
+            RoomAction::Kick { user } => {
+                self.check_target(actor, *user)?;
+                self.remove(*user);
+            }
+            RoomAction::Ban { user } => {
+                if identities.is_empty() {
+                    return Err(ErrorBody::new(
+                        ErrorCode::InvalidRequest,
+                        format!("ID {0} não encontrado", user),
+                    ));
+                }
+                if self.members.contains(user) {
+                    self.check_target(actor, *user)?;
+                    self.remove(*user);
+                }
+                self.banned.extend(identities.iter().cloned());
+                self.invited.remove(user);
+            }
+            RoomAction::Unban { identity } => {
+                if !self.banned.remove(identity) {
+                    return Err(ErrorBody::new(
+                        ErrorCode::InvalidRequest,
+                        format!("{0} não está banido da sala {1}", identity, self.name),
+                    ));
+                }
+            }
+            RoomAction::Op { user } | RoomAction::Deop { user } => {
+                if actor != self.owner {
+                    return Err(ErrorBody::new(
+                        ErrorCode::NotOperator,
+                        format!("Apenas o dono da sala {0} pode fazer isso", self.name),
+                    ));
+                }
+                self.check_target(actor, *user)?;
+                if matches!(action, RoomAction::Op { .. }) {
+                    self.operators.insert(*user);
+                } else {
+                    self.operators.remove(user);
+                }
+            }
+            RoomAction::Mute { user } => {
+                self.check_target(actor, *user)?;
+                self.muted.insert(*user);
+            }
+            RoomAction::Unmute { user } => {
+                self.check_target(actor, *user)?;
+                self.muted.remove(user);
+            }
+            RoomAction::Invite { user } => {
+                self.invited.insert(*user);
+            }
+            RoomAction::Topic { topic } => {
+                self.topic = normalize(topic);
+            }
+            RoomAction::InviteOnly { enabled } => {
+                self.invite_only = *enabled;
+            }
+            RoomAction::Password { password } => {
+                self.password = normalize(password);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_member(&self, user: u16) -> Result<(), ErrorBody> {
+        if !self.members.contains(&user) {
+            return Err(ErrorBody::new(
+                ErrorCode::NotInRoom,
+                format!("Você não está na sala {0}", self.name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// O alvo precisa ser membro; o dono não pode ser alvo, e operadores só
+    /// podem ser alvo do dono.
+    fn check_target(&self, actor: u16, user: u16) -> Result<(), ErrorBody> {
+        if !self.members.contains(&user) {
+            return Err(ErrorBody::new(
+                ErrorCode::NotInRoom,
+                format!("ID {0} não está na sala {1}", user, self.name),
+            ));
+        }
+        if user == self.owner || (self.operators.contains(&user) && actor != self.owner) {
+            return Err(ErrorBody::new(
+                ErrorCode::NotOperator,
+                format!("Sem permissão sobre o ID {0} na sala {1}", user, self.name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Tira o membro; se era o dono, a sala passa para o primeiro operador, ou
+    /// para o primeiro membro.
+    fn remove(&mut self, user: u16) -> Departure {
+        self.members.remove(&user);
+        self.operators.remove(&user);
+        self.muted.remove(&user);
+        let mut new_owner = None;
+        if user == self.owner {
+            let next = self
+                .operators
+                .iter()
+                .next()
+                .or_else(|| self.members.iter().next())
+                .copied();
+            if let Some(next) = next {
+                self.owner = next;
+                self.operators.remove(&next);
+                new_owner = Some(next);
+            }
+        }
+        Departure {
+            closed: self.members.is_empty(),
+            new_owner,
+        }
+    }
+}
+
+fn normalize(value: &Option<String>) -> Option<String> {
+    value
+        .as_ref()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Salas abertas e seus membros. Uma sala existe enquanto tiver membros; os ids
+/// são próprios das salas e não se confundem com os ids de usuários.
+#[derive(Debug, Default)]
+pub struct RoomTable {
+    rooms: BTreeMap<u16, Room>,
+    last_id: u16,
+}
+
+impl RoomTable {
+    /// Cria a sala com `owner` como primeiro membro e dono.
+    pub fn create(&mut self, name: &str, owner: u16) -> Result<&Room, ErrorBody> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(ErrorBody::new(
+                ErrorCode::InvalidRequest,
+                format!("Nome de sala inválido: {0}", name),
+            ));
+        }
+        if self.find(name).is_some() {
+            return Err(ErrorBody::new(
+                ErrorCode::RoomExists,
+                format!("Sala já existente: {0}", name),
+            ));
+        }
+        let id = self.next_id()?;
+        let room = Room {
+            id,
+            name: name.to_string(),
+            members: BTreeSet::from([owner]),
+            owner,
+            operators: BTreeSet::new(),
+            muted: BTreeSet::new(),
+            invited: BTreeSet::new(),
+            banned: BTreeSet::new(),
+            topic: None,
+            invite_only: false,
+            created: Utc::now().timestamp_millis(),
+            password: None,
+        };
+        Ok(self.rooms.entry(id).or_insert(room))
+    }
+
+    /// Procura a sala pelo id ou, com id 0, pelo nome.
+    pub fn resolve(&self, id: u16, name: &str) -> Result<u16, ErrorBody> {
+        let room = if id != 0 {
+            self.rooms.get(&id)
+        } else {
+            self.find(name)
+        };
+        match room {
+            Some(room) => Ok(room.id),
+            None if id != 0 => Err(not_found(id)),
+            None => Err(ErrorBody::new(
+                ErrorCode::RoomNotFound,
+                format!("Sala {0} não encontrada", name),
+            )),
+        }
+    }
+
+    pub fn get(&self, id: &u16) -> Option<&Room> {
+        self.rooms.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &u16) -> Result<&mut Room, ErrorBody> {
+        self.rooms.get_mut(id).ok_or(not_found(*id))
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Room> {
+        self.rooms.values().find(|room| room.name == name)
+    }
+
+    /// Adiciona o usuário à sala, conferindo banimento, convite e senha.
+    /// `identities` são as identidades do usuário. Retorna se ele ainda não era
+    /// membro.
+    pub fn join(
+        &mut self,
+        id: u16,
+        user: u16,
+        identities: &[String],
+        password: Option<&str>,
+    ) -> Result<bool, ErrorBody> {
+        let room = self.get_mut(&id)?;
+        if room.members.contains(&user) {
+            return Ok(false);
+        }
+        if identities
+            .iter()
+            .any(|identity| room.banned.contains(identity))
+        {
+            return Err(ErrorBody::new(
+                ErrorCode::Banned,
+                format!("Você está banido da sala {0}", room.name),
+            ));
+        }
+        if !room.invited.contains(&user) {
+            if room.invite_only {
+                return Err(ErrorBody::new(
+                    ErrorCode::InviteOnly,
+                    format!("Sala {0} exige convite", room.name),
+                ));
+            }
+            if room.password.is_some() && room.password.as_deref() != password {
+                return Err(ErrorBody::new(
+                    ErrorCode::WrongPassword,
+                    format!("Senha da sala {0} incorreta", room.name),
+                ));
+            }
+        }
+        room.invited.remove(&user);
+        Ok(room.members.insert(user))
+    }
+
+    /// Tira o usuário da sala, que é fechada se ficar vazia.
+    pub fn leave(&mut self, id: u16, user: u16) -> Result<Departure, ErrorBody> {
+        let room = self.get_mut(&id)?;
+        room.check_member(user)?;
+        let departure = room.remove(user);
+        if departure.closed {
+            self.rooms.remove(&id);
+        }
+        Ok(departure)
+    }
+
+    /// Tira o usuário de todas as salas. Retorna as salas de que ele saiu.
+    pub fn leave_all(&mut self, user: u16) -> Vec<(u16, Departure)> {
+        let ids: Vec<u16> = self
+            .rooms
+            .values()
+            .filter(|room| room.members.contains(&user))
+            .map(|room| room.id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| self.leave(id, user).ok().map(|departure| (id, departure)))
+            .collect()
+    }
+
+    pub fn list(&self) -> Vec<RoomInfo> {
+        self.rooms.values().map(Room::info).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.rooms.clear();
+    }
+
+    fn next_id(&mut self) -> Result<u16, ErrorBody> {
+        if self.rooms.len() >= u16::MAX as usize {
+            return Err(ErrorBody::new(
+                ErrorCode::InvalidRequest,
+                "Limite de salas atingido",
+            ));
+        }
+        loop {
+            self.last_id = self.last_id.wrapping_add(1);
+            if self.last_id != 0 && !self.rooms.contains_key(&self.last_id) {
+                return Ok(self.last_id);
+            }
+        }
+    }
+}
+
+fn not_found(id: u16) -> ErrorBody {
+    ErrorBody::new(
+        ErrorCode::RoomNotFound,
+        format!("Sala {0} não encontrada", id),
+    )
+}
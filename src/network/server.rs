@@ -1,342 +1,1868 @@
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use bimap::BiMap;
-use tokio::io::AsyncWriteExt;
-use tokio::net::{TcpListener, TcpStream, UdpSocket};
-use tokio::sync::{Mutex, RwLock};
-use tokio::task;
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::{watch, Mutex, OnceCell, RwLock};
+use tokio::{task, time};
+use tokio_rustls::TlsAcceptor;
 
-use crate::config::{HOST_ADDRESS, TCP_PORT, UDP_PORT};
-use crate::models::message::Message;
-use crate::network::{tcp, udp};
-use crate::utilities::enums::MessageType;
+use crate::config::{ServerArgs, ServerConfig, MAX_DATAGRAM_SIZE, OFFLINE_SAVE_INTERVAL_MS};
+use crate::models::admin::{AdminCommand, ServerStats};
+use crate::models::credentials::Credentials;
+use crate::models::error::{ErrorBody, ErrorCode};
+use crate::models::event::{ServerEventKind, Severity};
+use crate::models::history::{HistoryEntry, HistoryRequest};
+use crate::models::message::{Frame, Message};
+use crate::models::metadata::MsgMetadata;
+use crate::models::room::{RoomAction, RoomJoin};
+use crate::models::validation;
+use crate::network::accounts::Accounts;
+use crate::network::codec::Rejected;
+use crate::network::datagram::DatagramTransport;
+use crate::network::e2e::PUBLIC_KEY_BYTES;
+use crate::network::event_log::EventLog;
+use crate::network::history::{self, HistoryFilter, HistoryStore, MemoryHistory};
+use crate::network::offline::{OfflineQueue, QueuedMessage};
+use crate::network::rate_limit::{Limited, RateLimiter};
+use crate::network::rooms::{Departure, Room, RoomTable};
+use crate::network::sessions::SessionTable;
+use crate::network::tcp::MessageWriter;
+use crate::network::udp::Reassembly;
+use crate::network::udp_crypto::{UdpSession, UsedSessions, SEAL_OVERHEAD};
+use crate::network::{tcp, tls, udp};
+use crate::utilities::enums::{MessageType, Protocol, RateLimitAction};
 
-#[derive(Debug, Clone)]
+const UDP_PREFIX: &str = "udp://";
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resultado do processamento de uma mensagem. `from` é o usuário dono da
+/// sessão, que muda quando ela se autentica.
+struct Routed {
+    from: u16,
+    replies: Vec<Message>,
+    deliveries: Vec<(u16, Message)>,
+    logged_in: bool,
+}
+
+impl Routed {
+    fn reply(from: u16, reply: Message) -> Self {
+        Routed {
+            from,
+            replies: vec![reply],
+            deliveries: Vec::new(),
+            logged_in: false,
+        }
+    }
+}
+
+/// Tabelas travadas enquanto `process_message` roteia uma mensagem.
+struct Tables<'a> {
+    sessions: &'a SessionTable,
+    name_table: &'a mut HashMap<u16, String>,
+    public_keys: &'a mut HashMap<u16, Vec<u8>>,
+    rooms: &'a mut RoomTable,
+}
+
+#[derive(Clone)]
 pub struct Server {
-    pub tcp_clients: Arc<RwLock<HashMap<String, Arc<Mutex<TcpStream>>>>>,
-    pub id_table: Arc<RwLock<BiMap<u16, String>>>,
-    polling_table: Arc<RwLock<HashMap<u16, Vec<u16>>>>,
+    config: Arc<RwLock<Arc<ServerConfig>>>,
+    source: Arc<Option<ServerArgs>>,
+    started: Instant,
+    pub tcp_clients: Arc<RwLock<HashMap<String, Arc<Mutex<MessageWriter>>>>>,
+    pub sessions: Arc<RwLock<SessionTable>>,
     pub name_table: Arc<RwLock<HashMap<u16, String>>>,
-    pub log: Arc<RwLock<String>>,
-    udp_id_map: Arc<RwLock<HashMap<u16, Vec<u16>>>>,
-    udp_data_map: Arc<RwLock<HashMap<u16, Vec<Message>>>>,
+    pub public_keys: Arc<RwLock<HashMap<u16, Vec<u8>>>>,
+    pub accounts: Arc<RwLock<Option<Accounts>>>,
+    pub authenticated: Arc<RwLock<HashMap<u16, String>>>,
+    pub offline: Arc<Mutex<OfflineQueue>>,
+    offline_saving: Arc<Mutex<()>>,
+    pub history: Arc<RwLock<Arc<dyn HistoryStore>>>,
+    pub rooms: Arc<RwLock<RoomTable>>,
+    pub events: Arc<EventLog>,
+    limiter: Arc<Mutex<RateLimiter>>,
+    udp_socket: Arc<OnceCell<Box<dyn DatagramTransport>>>,
+    udp_ids: Arc<AtomicU16>,
+    udp_data_map: Arc<RwLock<HashMap<String, Reassembly>>>,
+    udp_sessions: Arc<RwLock<HashMap<String, Arc<UdpSession>>>>,
+    used_udp_sessions: Arc<Mutex<UsedSessions>>,
+    shutdown_tx: Arc<watch::Sender<bool>>,
 }
 
 impl Server {
-    pub fn new() -> Self {
+    pub fn new(config: ServerConfig) -> Self {
         Server {
+            events: Arc::new(EventLog::new(config.event_log_capacity)),
+            offline: Arc::new(Mutex::new(OfflineQueue::new(&config))),
+            offline_saving: Arc::new(Mutex::new(())),
+            limiter: Arc::new(Mutex::new(RateLimiter::new(&config))),
+            history: Arc::new(RwLock::new(Arc::new(MemoryHistory::default()))),
+            rooms: Arc::new(RwLock::new(RoomTable::default())),
+            config: Arc::new(RwLock::new(Arc::new(config))),
+            source: Arc::new(None),
+            started: Instant::now(),
             tcp_clients: Arc::new(RwLock::new(HashMap::new())),
-            id_table: Arc::new(RwLock::new(BiMap::new())),
-            polling_table: Arc::new(RwLock::new(HashMap::new())),
+            sessions: Arc::new(RwLock::new(SessionTable::default())),
             name_table: Arc::new(RwLock::new(HashMap::new())),
-            udp_id_map: Arc::new(RwLock::new(HashMap::new())),
+            public_keys: Arc::new(RwLock::new(HashMap::new())),
+            accounts: Arc::new(RwLock::new(None)),
+            authenticated: Arc::new(RwLock::new(HashMap::new())),
+            udp_socket: Arc::new(OnceCell::new()),
+            udp_ids: Arc::new(AtomicU16::new(rand::random())),
             udp_data_map: Arc::new(RwLock::new(HashMap::new())),
-            log: Arc::new(RwLock::new(String::new())),
+            udp_sessions: Arc::new(RwLock::new(HashMap::new())),
+            used_udp_sessions: Arc::new(Mutex::new(UsedSessions::default())),
+            shutdown_tx: Arc::new(watch::channel(false).0),
         }
     }
 
-    pub async fn start(&self) {
-        let tcp_listener = TcpListener::bind(format!("{0}:{1}", HOST_ADDRESS, TCP_PORT))
-            .await
-            .unwrap();
-        let udp_socket = UdpSocket::bind(format!("{0}:{1}", HOST_ADDRESS, UDP_PORT))
-            .await
-            .unwrap();
+    /// Servidor cuja configuração foi lida de `args`; `ReloadConfig` volta a
+    /// lê-la da mesma origem.
+    pub fn from_args(args: ServerArgs) -> Result<Self, String> {
+        let config = ServerConfig::load(&args)?;
+        Ok(Server {
+            source: Arc::new(Some(args)),
+            ..Server::new(config)
+        })
+    }
 
-        self.log
-            .write()
-            .await
-            .push_str("\nServidor executando TCP na porta 8080 e UDP porta 8081");
+    /// Servidor que recebe e envia datagramas por `transport` em vez de abrir um
+    /// socket UDP em `udp_address`.
+    pub fn with_udp_transport(self, transport: Box<dyn DatagramTransport>) -> Self {
+        Server {
+            udp_socket: Arc::new(OnceCell::new_with(Some(transport))),
+            ..self
+        }
+    }
+
+    /// Configuração em uso.
+    pub async fn config(&self) -> Arc<ServerConfig> {
+        self.config.read().await.clone()
+    }
+
+    /// Abre as portas e atende até o `shutdown`. Falhas ao iniciar (TLS,
+    /// contas, fila offline, histórico ou portas em uso) são emitidas como
+    /// evento e retornadas.
+    pub async fn start(&self) -> Result<(), String> {
+        let config = self.config().await;
+        let tls = match (&config.tls_cert, &config.tls_key) {
+            (Some(cert), Some(key)) => match tls::acceptor(cert, key) {
+                Ok(acceptor) => Some(acceptor),
+                Err(e) => return Err(self.startup_failed(e)),
+            },
+            _ => None,
+        };
+        if let Some(path) = &config.accounts_file {
+            match Accounts::load(path) {
+                Ok(accounts) => *self.accounts.write().await = Some(accounts),
+                Err(e) => return Err(self.startup_failed(e)),
+            }
+        }
+        if let Err(e) = self.offline.lock().await.load() {
+            return Err(self.startup_failed(e));
+        }
+        match history::open(&config) {
+            Ok(store) => *self.history.write().await = store,
+            Err(e) => return Err(self.startup_failed(e)),
+        }
+        let tcp_listener = match TcpListener::bind(config.tcp_address()).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                let message = format!(
+                    "Falha ao abrir a porta TCP {0}: {1}",
+                    config.tcp_address(),
+                    e
+                );
+                return Err(self.startup_failed(message));
+            }
+        };
+        if !self.udp_socket.initialized() {
+            match UdpSocket::bind(config.udp_address()).await {
+                Ok(udp_socket) => {
+                    let _ = self.udp_socket.set(Box::new(udp_socket));
+                }
+                Err(e) => {
+                    let message = format!(
+                        "Falha ao abrir a porta UDP {0}: {1}",
+                        config.udp_address(),
+                        e
+                    );
+                    return Err(self.startup_failed(message));
+                }
+            }
+        }
+        let Some(udp_socket) = self.udp_socket.get() else {
+            return Err(self.startup_failed("Socket UDP indisponível".to_string()));
+        };
+        let ports = tcp_listener
+            .local_addr()
+            .and_then(|tcp_addr| Ok((tcp_addr.port(), udp_socket.local_addr()?.port())));
+        let (tcp_port, udp_port) = match ports {
+            Ok(ports) => ports,
+            Err(e) => {
+                let message = format!("Falha ao ler as portas abertas: {0}", e);
+                return Err(self.startup_failed(message));
+            }
+        };
 
-        let tcp_clients = self.tcp_clients.clone();
-        let id_table = self.id_table.clone();
-        let name_table = self.name_table.clone();
-        let log = self.log.clone();
+        self.events.emit(
+            Severity::Info,
+            ServerEventKind::Started { tcp_port, udp_port },
+        );
 
+        let server = self.clone();
         let tcp_task = task::spawn(async move {
-            Self::listen_tcp(
-                log,
-                tcp_listener,
-                tcp_clients,
-                id_table.clone(),
-                name_table.clone(),
-            )
-            .await;
+            server.listen_tcp(tcp_listener, tls).await;
         });
 
-        let id_table = self.id_table.clone();
-        let udp_id_map = self.udp_id_map.clone();
-        let udp_data_map = self.udp_data_map.clone();
-
+        let server = self.clone();
         let udp_task = task::spawn(async move {
-            Self::listen_udp(udp_socket, id_table.clone(), udp_id_map, udp_data_map).await;
+            server.listen_udp().await;
         });
 
-        let _ = tokio::join!(tcp_task, udp_task);
+        let server = self.clone();
+        let offline_task = task::spawn(async move {
+            server.save_offline_periodically().await;
+        });
+
+        let _ = tokio::join!(tcp_task, udp_task, offline_task);
+        Ok(())
     }
 
-    async fn listen_tcp(
-        log: Arc<RwLock<String>>,
-        listener: TcpListener,
-        tcp_clients: Arc<RwLock<HashMap<String, Arc<Mutex<TcpStream>>>>>,
-        id_table: Arc<RwLock<BiMap<u16, String>>>,
-        name_table: Arc<RwLock<HashMap<u16, String>>>,
-    ) {
+    fn startup_failed(&self, message: String) -> String {
+        self.events.emit(
+            Severity::Error,
+            ServerEventKind::Error {
+                message: message.clone(),
+            },
+        );
+        message
+    }
+
+    async fn listen_tcp(&self, listener: TcpListener, tls: Option<TlsAcceptor>) {
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
         loop {
-            let log_clone = log.clone();
-            let tcp_clients_clone = tcp_clients.clone();
-            let name_table_clone = name_table.clone();
-            let id_table_clone = id_table.clone();
-            let (stream, addr) = match listener.accept().await {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = shutdown_rx.changed() => break,
+            };
+            let (stream, addr) = match accepted {
                 Ok((stream, addr)) => (stream, addr),
                 Err(e) => {
-                    log_clone
-                        .write()
-                        .await
-                        .push_str(&format!("\nFalha ao aceitar conexão: {0}", e));
+                    self.events.emit(
+                        Severity::Error,
+                        ServerEventKind::Error {
+                            message: format!("Falha ao aceitar conexão: {0}", e),
+                        },
+                    );
                     continue;
                 }
             };
+            let _ = stream.set_nodelay(true);
             let addr = addr.to_string();
-            let id = Self::assign_id(addr.clone(), id_table.clone()).await;
+            let server = self.clone();
+            let tls = tls.clone();
+            tokio::spawn(async move {
+                let Some(acceptor) = tls else {
+                    server.serve_tcp(stream, addr).await;
+                    return;
+                };
+                match time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => server.serve_tcp(stream, addr).await,
+                    Ok(Err(e)) => server.events.emit(
+                        Severity::Warning,
+                        ServerEventKind::Error {
+                            message: format!("Falha no handshake TLS com {0}: {1}", addr, e),
+                        },
+                    ),
+                    Err(_) => server.events.emit(
+                        Severity::Warning,
+                        ServerEventKind::Error {
+                            message: format!("Tempo esgotado no handshake TLS com {0}", addr),
+                        },
+                    ),
+                }
+            });
+        }
+    }
+
+    async fn serve_tcp<S: AsyncRead + AsyncWrite + Send + 'static>(&self, stream: S, addr: String) {
+        if let Some(ip) = source_ip(&addr) {
+            if !self.limiter.lock().await.admit(ip) {
+                self.events.emit(
+                    Severity::Warning,
+                    ServerEventKind::Error {
+                        message: format!("Conexão de {0} recusada: limite do IP excedido", addr),
+                    },
+                );
+                return;
+            }
+        }
+        let id = self.assign_id(addr.clone()).await;
+        let max_size = self.config().await.max_message_size;
+        let (mut reader, writer) = tcp::framed(stream, max_size);
+        self.tcp_clients
+            .write()
+            .await
+            .insert(addr.clone(), Arc::new(Mutex::new(writer)));
+        self.events.emit(
+            Severity::Info,
+            ServerEventKind::Connected {
+                id,
+                addr: addr.clone(),
+                protocol: Protocol::TCP,
+            },
+        );
+
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let mut reason = None;
+        loop {
+            let max_size = self.config().await.max_message_size;
+            reader.decoder_mut().set_max_size(max_size);
+            let received = tokio::select! {
+                received = reader.next() => received,
+                _ = shutdown_rx.changed() => return,
+            };
+            let Some(id) = self.sessions.read().await.user(&addr) else {
+                break;
+            };
+            let mut message = match received {
+                Some(Ok(Ok(msg))) => msg,
+                Some(Ok(Err(rejected))) => {
+                    self.reject(&addr, id, rejected).await;
+                    continue;
+                }
+                Some(Err(e)) => {
+                    reason = Some(format!("Mensagem corrompida: {0}", e));
+                    break;
+                }
+                None => {
+                    reason = Some("Conexão encerrada".to_string());
+                    break;
+                }
+            };
+            let is_disconnect = message.metadata.message_type == MessageType::Disconnect;
+            self.handle_message(&mut message, id, &addr).await;
+            if is_disconnect {
+                break;
+            }
+        }
+        self.disconnect_session(&addr, reason).await;
+    }
+
+    async fn listen_udp(&self) {
+        let socket = self.udp_socket.get().unwrap();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        loop {
+            let received = tokio::select! {
+                received = socket.recv_from(&mut buf) => received,
+                _ = shutdown_rx.changed() => break,
+            };
+            let (len, addr) = match received {
+                Ok((len, addr)) => (len, addr),
+                Err(e) => {
+                    self.events.emit(
+                        Severity::Error,
+                        ServerEventKind::Error {
+                            message: format!("Falha ao receber dados UDP: {0}", e),
+                        },
+                    );
+                    continue;
+                }
+            };
+            let addr_str = format!("{0}{1}", UDP_PREFIX, addr);
+            let packet = match &self.config().await.udp_psk {
+                Some(psk) => match self.open_udp(psk, &addr_str, &buf[..len]).await {
+                    Some(packet) => packet,
+                    None => {
+                        self.events.emit(
+                            Severity::Debug,
+                            ServerEventKind::Error {
+                                message: format!(
+                                    "Datagrama UDP de {0} descartado: falha de autenticação",
+                                    addr
+                                ),
+                            },
+                        );
+                        continue;
+                    }
+                },
+                None => buf[..len].to_vec(),
+            };
+            let known_id = self.sessions.read().await.user(&addr_str);
+            let id = match known_id {
+                Some(id) => id,
+                None => {
+                    if !self.limiter.lock().await.admit(addr.ip()) {
+                        self.events.emit(
+                            Severity::Debug,
+                            ServerEventKind::Error {
+                                message: format!(
+                                    "Datagrama UDP de {0} descartado: limite do IP excedido",
+                                    addr
+                                ),
+                            },
+                        );
+                        continue;
+                    }
+                    let id = self.assign_id(addr_str.clone()).await;
+                    self.events.emit(
+                        Severity::Info,
+                        ServerEventKind::Connected {
+                            id,
+                            addr: addr.to_string(),
+                            protocol: Protocol::UDP,
+                        },
+                    );
+                    id
+                }
+            };
+            if !self
+                .datagram_within_rate_limit(&packet, id, &addr_str)
+                .await
+            {
+                continue;
+            }
+            let max_size = self.config().await.max_message_size;
+            let message = {
+                let mut udp_data_map = self.udp_data_map.write().await;
+                let reassembly = udp_data_map.entry(addr_str.clone()).or_default();
+                udp::build_udp_message(packet, reassembly, max_size)
+            };
+            let message = match message {
+                Ok(message) => message,
+                Err(rejected) => {
+                    self.reject(&addr_str, id, rejected).await;
+                    None
+                }
+            };
+            if let Some(mut message) = message {
+                let is_disconnect = message.metadata.message_type == MessageType::Disconnect;
+                self.handle_message(&mut message, id, &addr_str).await;
+                if is_disconnect {
+                    self.disconnect_session(&addr_str, None).await;
+                }
+            }
+        }
+    }
+
+    /// Autentica um datagrama cifrado. Um id de sessão desconhecido que autentique
+    /// com a chave pré-compartilhada substitui a sessão anterior do endereço,
+    /// desde que seja recente e nunca tenha sido usado.
+    async fn open_udp(&self, psk: &str, addr: &str, datagram: &[u8]) -> Option<Vec<u8>> {
+        let session_id = UdpSession::peek_id(datagram)?;
+        let current = self.udp_sessions.read().await.get(addr).cloned();
+        if let Some(session) = &current {
+            if session.id() == session_id {
+                return session.open(datagram);
+            }
+        }
+        let session = UdpSession::server(psk, session_id);
+        let packet = session.open(datagram)?;
+        let now = Utc::now().timestamp_millis();
+        if !self.used_udp_sessions.lock().await.admit(session_id, now) {
+            return None;
+        }
+        if current.is_some() {
+            self.disconnect_session(addr, Some("Nova sessão UDP".to_string()))
+                .await;
+        }
+        self.udp_sessions
+            .write()
+            .await
+            .insert(addr.to_string(), Arc::new(session));
+        Some(packet)
+    }
+
+    /// Trata uma mensagem recebida na sessão `addr` do usuário `from`. Respostas
+    /// (Success e Error) voltam só para essa sessão; o restante é entregue em
+    /// todas as sessões do destinatário.
+    async fn handle_message(&self, message: &mut Message, from: u16, addr: &str) {
+        if !self.within_rate_limit(message, from, addr).await {
+            return;
+        }
+        let routed = self.route(message, from, addr).await;
+        for reply in &routed.replies {
+            self.reply(addr, routed.from, reply).await;
+        }
+        self.deliver(routed).await;
+    }
+
+    /// Responde uma mensagem recusada antes de ser processada.
+    async fn reject(&self, addr: &str, id: u16, rejected: Rejected) {
+        self.events.emit(
+            Severity::Warning,
+            ServerEventKind::Rejected {
+                id,
+                reason: rejected.error.message.clone(),
+            },
+        );
+        let error = Message::new_coded_error(rejected.key, id, &rejected.error);
+        self.reply(addr, id, &error).await;
+    }
+
+    /// Cobra a mensagem dos limites da sessão e do IP. Acima do limite, a sessão
+    /// recebe um `Error` com código `rate_limited` e a ação configurada é
+    /// aplicada; retorna se a mensagem ainda deve ser processada. `Disconnect`
+    /// nunca é limitado.
+    async fn within_rate_limit(&self, message: &Message, from: u16, addr: &str) -> bool {
+        if message.metadata.message_type == MessageType::Disconnect {
+            return true;
+        }
+        // Os bytes de uma mensagem UDP já foram cobrados datagrama a datagrama.
+        let bytes = match addr.starts_with(UDP_PREFIX) {
+            true => 0,
+            false => message.content.len(),
+        };
+        let checked = self
+            .limiter
+            .lock()
+            .await
+            .check(addr, source_ip(addr), 1, bytes);
+        let Err(limited) = checked else {
+            return true;
+        };
+        self.rate_limited(limited, Some(message.metadata.key), from, addr)
+            .await
+    }
+
+    /// Cobra os bytes de um datagrama antes da remontagem. Acima do limite, as
+    /// mensagens incompletas do endereço são descartadas junto com ele.
+    async fn datagram_within_rate_limit(&self, packet: &[u8], from: u16, addr: &str) -> bool {
+        let metadata = MsgMetadata::deserialize(packet, true).ok();
+        if metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.message_type == MessageType::Disconnect)
+        {
+            return true;
+        }
+        let checked = self
+            .limiter
+            .lock()
+            .await
+            .check(addr, source_ip(addr), 0, packet.len());
+        let Err(limited) = checked else {
+            return true;
+        };
+        if let Some(reassembly) = self.udp_data_map.write().await.get_mut(addr) {
+            reassembly.discard_pending();
+        }
+        // Responde uma vez por mensagem, no primeiro pacote dela.
+        let key = metadata
+            .filter(|metadata| metadata.udp_seq == Some(0))
+            .map(|metadata| metadata.key);
+        self.rate_limited(limited, key, from, addr).await
+    }
+
+    /// Aplica a ação de um limite excedido e diz se a mensagem segue. Com
+    /// `key`, a sessão recebe um Error com código rate_limited.
+    async fn rate_limited(
+        &self,
+        limited: Limited,
+        key: Option<u16>,
+        from: u16,
+        addr: &str,
+    ) -> bool {
+        self.events.emit(
+            Severity::Warning,
+            ServerEventKind::RateLimited {
+                id: from,
+                addr: addr.to_string(),
+                reason: limited.reason.clone(),
+                action: limited.action,
+            },
+        );
+        if let Some(key) = key {
+            // O aviso não responde a mensagem, que segue o fluxo normal.
+            let key = match limited.action {
+                RateLimitAction::Warn => 0,
+                _ => key,
+            };
+            let error = ErrorBody::new(ErrorCode::RateLimited, limited.reason.clone());
+            self.reply(addr, from, &Message::new_coded_error(key, from, &error))
+                .await;
+        }
+        match limited.action {
+            RateLimitAction::Warn => true,
+            RateLimitAction::Drop | RateLimitAction::Mute => false,
+            RateLimitAction::Disconnect => {
+                self.disconnect_session(addr, Some(limited.reason)).await;
+                false
+            }
+        }
+    }
+
+    /// Executa um comando administrativo da interface do servidor, pelo mesmo
+    /// caminho das mensagens `Admin` dos clients. Retorna a resposta.
+    pub async fn admin(&self, command: &AdminCommand) -> Message {
+        let key = Message::generate_key();
+        let mut message = Message::new_admin_request(key, command);
+        let routed = self.route(&mut message, 0, "").await;
+        let response = routed
+            .replies
+            .last()
+            .cloned()
+            .unwrap_or_else(|| Message::new_generic_response(key, 0, true));
+        self.deliver(routed).await;
+        response
+    }
+
+    /// Processa a mensagem e separa as respostas para a sessão do que deve ser
+    /// entregue a outros usuários.
+    async fn route(&self, message: &mut Message, from: u16, addr: &str) -> Routed {
+        if let Err(error) = validation::validate_payload(message) {
+            self.events.emit(
+                Severity::Warning,
+                ServerEventKind::Rejected {
+                    id: from,
+                    reason: error.message.clone(),
+                },
+            );
+            let error = Message::new_coded_error(message.metadata.key, from, &error);
+            return Routed::reply(from, error);
+        }
+        let account = match self.authorize(message, from).await {
+            Ok(account) => account,
+            Err(reason) => {
+                self.events.emit(
+                    Severity::Warning,
+                    ServerEventKind::Rejected {
+                        id: from,
+                        reason: reason.clone(),
+                    },
+                );
+                let error = Message::new_error_response(message.metadata.key, from, reason);
+                return Routed::reply(from, error);
+            }
+        };
+        let from = match &account {
+            Some((id, _)) if *id != from => self.move_session(addr, from, Some(*id)).await,
+            None if message.metadata.message_type == MessageType::Connection
+                && self.authenticated.read().await.contains_key(&from) =>
             {
-                let mut tcp_clients_write = tcp_clients.write().await;
-                tcp_clients_write.insert(addr.clone(), Arc::new(Mutex::new(stream)));
+                self.move_session(addr, from, None).await
             }
-            log_clone
+            _ => from,
+        };
+        if let Some(response) = self.store_offline(message, from).await {
+            return Routed::reply(from, response);
+        }
+        if let Some(response) = self.reload_requested(message, from).await {
+            return Routed::reply(from, response);
+        }
+        if message.metadata.message_type == MessageType::History {
+            return Routed::reply(from, self.history_request(message, from).await);
+        }
+        let mut logged_in = false;
+        let mut appended = Vec::new();
+        let messages = {
+            let sessions = self.sessions.read().await;
+            let mut name_table = self.name_table.write().await;
+            let mut public_keys = self.public_keys.write().await;
+            let mut rooms = self.rooms.write().await;
+            let tables = Tables {
+                sessions: &sessions,
+                name_table: &mut name_table,
+                public_keys: &mut public_keys,
+                rooms: &mut rooms,
+            };
+            let messages = self
+                .process_message(message, from, tables, &mut appended)
+                .await;
+            if let Some((_, account)) = account {
+                if name_table.get(&from) == Some(&account) {
+                    self.authenticated.write().await.insert(from, account);
+                    logged_in = true;
+                }
+            }
+            messages
+        };
+        if !appended.is_empty() {
+            self.append_history(appended).await;
+        }
+        let (replies, deliveries): (Vec<_>, Vec<_>) =
+            messages.into_iter().partition(|(id, message)| {
+                *id == from
+                    && matches!(
+                        message.metadata.message_type,
+                        MessageType::Success | MessageType::Error
+                    )
+            });
+        Routed {
+            from,
+            replies: replies.into_iter().map(|(_, reply)| reply).collect(),
+            deliveries,
+            logged_in,
+        }
+    }
+
+    /// Entrega as mensagens para outros usuários, encerra quem recebeu um
+    /// `Disconnect` do servidor e, depois de um login, a fila offline.
+    async fn deliver(&self, routed: Routed) {
+        let kicked: Vec<u16> = routed
+            .deliveries
+            .iter()
+            .filter(|(_, message)| message.metadata.message_type == MessageType::Disconnect)
+            .map(|(id, _)| *id)
+            .collect();
+        self.dispatch(routed.deliveries).await;
+        for id in kicked {
+            self.disconnect(id, None).await;
+        }
+        if routed.logged_in {
+            self.flush_offline(routed.from).await;
+        }
+    }
+
+    /// Grava as entradas no histórico sem segurar as tabelas.
+    async fn append_history(&self, entries: Vec<HistoryEntry>) {
+        let store = self.history.read().await.clone();
+        if let Err(e) = history::append_all(store, entries).await {
+            self.events
+                .emit(Severity::Error, ServerEventKind::Error { message: e });
+        }
+    }
+
+    /// Responde a um `History`. A tabela de salas só é lida para conferir a
+    /// participação; a consulta roda depois, numa thread de bloqueio.
+    async fn history_request(&self, message: &Message, from: u16) -> Message {
+        let key = message.metadata.key;
+        let peer = message.metadata.receiver_id;
+        let request = if message.content.is_empty() {
+            Ok(HistoryRequest::default())
+        } else {
+            serde_json::from_slice::<HistoryRequest>(&message.content)
+        };
+        let Ok(request) = request else {
+            return Message::new_error_response(
+                key,
+                from,
+                "Requisição de histórico inválida".to_string(),
+            );
+        };
+        let mut filter = HistoryFilter::new(from, peer, request);
+        // Só membros leem o histórico da sala, e só o da sala atual.
+        if let Some(room_id) = filter.room {
+            match self
+                .rooms
                 .write()
                 .await
-                .push_str(&format!("\nNova conexão TCP: {0} - ID {1}", addr, id));
+                .get_mut(&room_id)
+                .and_then(|room| room.check_member(from).map(|_| room.created))
+            {
+                Ok(created) => filter.since = Some(created),
+                Err(error) => return Message::new_coded_error(key, from, &error),
+            }
+        }
+        let store = self.history.read().await.clone();
+        match history::query(store, filter).await {
+            Ok(entries) => Message::new_history_response(key, peer, &entries),
+            Err(e) => {
+                self.events
+                    .emit(Severity::Error, ServerEventKind::Error { message: e });
+                Message::new_error_response(key, from, "Histórico indisponível".to_string())
+            }
+        }
+    }
 
-            tokio::spawn(async move {
-                loop {
-                    let sender_id;
-                    let messages = {
-                        let client_stream = {
-                            let tcp_clients_read = tcp_clients_clone.read().await;
-                            tcp_clients_read.get(&addr).unwrap().clone()
-                        };
-                        let mut stream = client_stream.lock().await;
-                        log_clone.write().await.push_str(&format!(
-                            "\nRecebendo dados do Client: {} - ID {}, Nome: {}",
-                            stream.peer_addr().unwrap(),
-                            &id,
-                            name_table_clone
-                                .read()
-                                .await
-                                .get(&id)
-                                .unwrap_or(&String::from("Sem nome"))
-                        ));
-                        let mut message = match tcp::receive(&mut stream).await {
-                            Ok(msg) => msg,
-                            Err(e) => {
-                                eprintln!("{}", e);
-                                return;
-                            }
-                        };
-                        let sender_addr = stream.peer_addr().unwrap().to_string();
-                        sender_id = *id_table_clone
-                            .read()
-                            .await
-                            .get_by_right(&sender_addr)
-                            .unwrap();
-                        let mut id_table = id_table_clone.write().await;
-                        let mut name_table = name_table_clone.write().await;
-                        Self::process_message(
-                            &mut message,
-                            sender_id,
-                            &mut id_table,
-                            &mut name_table,
-                            &log_clone,
-                        )
-                        .await
-                    };
+    /// `ReloadConfig` troca as contas e o histórico, que não podem ser travados
+    /// junto com o registro de sessões; por isso é tratado antes do
+    /// `process_message`.
+    async fn reload_requested(&self, message: &Message, from: u16) -> Option<Message> {
+        if message.metadata.message_type != MessageType::Admin {
+            return None;
+        }
+        let Ok(AdminCommand::ReloadConfig) = serde_json::from_slice(&message.content) else {
+            return None;
+        };
+        let key = message.metadata.key;
+        let response = match self.reload_config(from).await {
+            Ok(restart) if restart.is_empty() => {
+                Message::new_text_response(key, from, "Configuração recarregada")
+            }
+            Ok(restart) => Message::new_text_response(
+                key,
+                from,
+                &format!(
+                    "Configuração recarregada; exigem reinício: {0}",
+                    restart.join(", ")
+                ),
+            ),
+            Err(e) => {
+                self.events.emit(
+                    Severity::Error,
+                    ServerEventKind::Error { message: e.clone() },
+                );
+                Message::new_error_response(key, from, e)
+            }
+        };
+        Some(response)
+    }
+
+    /// Relê a configuração da mesma origem usada ao iniciar, o arquivo de contas
+    /// e, se mudou, o arquivo de histórico. Retorna as opções alteradas que só
+    /// valem depois de um reinício (veja `ServerConfig::reloaded`).
+    async fn reload_config(&self, by: u16) -> Result<Vec<String>, String> {
+        let current = self.config().await;
+        let next = match self.source.as_ref() {
+            Some(args) => ServerConfig::load(args)?,
+            None => (*current).clone(),
+        };
+        let (next, restart) = current.reloaded(next);
+        let accounts = match &next.accounts_file {
+            Some(path) => Some(Accounts::load(path)?),
+            None => None,
+        };
+        let history = match next.history_file != current.history_file {
+            true => Some(history::open(&next)?),
+            false => None,
+        };
+        *self.accounts.write().await = accounts;
+        if let Some(history) = history {
+            *self.history.write().await = history;
+        }
+        self.offline.lock().await.set_limits(&next);
+        self.limiter.lock().await.configure(&next);
+        *self.config.write().await = Arc::new(next);
+        self.events.emit(
+            Severity::Info,
+            ServerEventKind::ConfigReloaded {
+                by,
+                restart: restart.clone(),
+            },
+        );
+        Ok(restart)
+    }
+
+    /// Guarda mensagens diretas para contas offline. Retorna a resposta ao
+    /// remetente, ou `None` se a mensagem segue o fluxo normal.
+    async fn store_offline(&self, message: &Message, from: u16) -> Option<Message> {
+        let message_type = message.metadata.message_type;
+        if !matches!(
+            message_type,
+            MessageType::Text | MessageType::File | MessageType::Encrypted
+        ) {
+            return None;
+        }
+        let key = message.metadata.key;
+        let receiver_id = message.metadata.receiver_id;
+        let queued = QueuedMessage::new(key, from, message_type, message.content.to_vec());
+        match self.store_if_offline(receiver_id, queued).await? {
+            Ok(()) => {
+                let entry = HistoryEntry::routed(from, receiver_id, message);
+                self.append_history(vec![entry]).await;
+                self.events.emit(
+                    Severity::Info,
+                    ServerEventKind::Queued {
+                        from,
+                        to: receiver_id,
+                        bytes: message.content.len(),
+                    },
+                );
+                Some(Message::new_queued_response(key, receiver_id))
+            }
+            Err(reason) => {
+                self.events.emit(
+                    Severity::Warning,
+                    ServerEventKind::Rejected {
+                        id: from,
+                        reason: reason.clone(),
+                    },
+                );
+                Some(Message::new_error_response(key, from, reason))
+            }
+        }
+    }
+
+    /// Enfileira a mensagem se o destinatário for uma conta sem sessões. O
+    /// registro de sessões fica travado até a mensagem entrar na fila, para que
+    /// ela não escape da entrega feita no login.
+    async fn store_if_offline(
+        &self,
+        recipient: u16,
+        queued: QueuedMessage,
+    ) -> Option<Result<(), String>> {
+        let sessions = self.sessions.read().await;
+        if sessions.contains_user(&recipient) {
+            return None;
+        }
+        let registered = self
+            .accounts
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|accounts| accounts.is_reserved(recipient));
+        if !registered {
+            return None;
+        }
+        Some(self.offline.lock().await.push(recipient, queued))
+    }
+
+    /// Grava as filas offline alteradas a cada `OFFLINE_SAVE_INTERVAL_MS`, até
+    /// o `shutdown`, que faz a última gravação.
+    async fn save_offline_periodically(&self) {
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let mut interval = time::interval(Duration::from_millis(OFFLINE_SAVE_INTERVAL_MS));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.save_offline().await,
+                _ = shutdown_rx.changed() => break,
+            }
+        }
+    }
+
+    /// Grava as filas offline, se mudaram, numa thread de bloqueio. A fila só
+    /// fica travada enquanto é serializada; `offline_saving` impede que uma
+    /// gravação mais antiga termine depois de uma mais nova.
+    async fn save_offline(&self) {
+        let _saving = self.offline_saving.lock().await;
+        let Some(snapshot) = self.offline.lock().await.snapshot() else {
+            return;
+        };
+        let written = task::spawn_blocking(move || snapshot.write())
+            .await
+            .map_err(|e| format!("Falha ao gravar a fila offline: {0}", e))
+            .and_then(|written| written);
+        if let Err(e) = written {
+            self.events
+                .emit(Severity::Error, ServerEventKind::Error { message: e });
+        }
+    }
+
+    /// Entrega, em ordem, a fila offline do usuário que acabou de se autenticar e
+    /// confirma cada mensagem ao remetente com um `Delivered`.
+    async fn flush_offline(&self, id: u16) {
+        let queued = self.offline.lock().await.take(id);
+        if queued.is_empty() {
+            return;
+        }
+        let messages = queued
+            .iter()
+            .map(|message| (id, message.to_message()))
+            .collect();
+        self.dispatch(messages).await;
+        for message in &queued {
+            if message.message_type() == MessageType::Delivered {
+                continue;
+            }
+            let notice = QueuedMessage::new(message.key, id, MessageType::Delivered, Vec::new());
+            let stored = self.store_if_offline(message.from, notice).await;
+            if stored.is_none() && self.sessions.read().await.contains_user(&message.from) {
+                let delivered = Message::new_delivered(message.key, id);
+                self.dispatch(vec![(message.from, delivered)]).await;
+            }
+        }
+        self.events.emit(
+            Severity::Info,
+            ServerEventKind::Flushed {
+                id,
+                count: queued.len(),
+            },
+        );
+    }
+
+    /// Move a sessão para o usuário `to`, ou para um id novo. O usuário anterior
+    /// deixa de existir se ficar sem sessões.
+    async fn move_session(&self, addr: &str, from: u16, to: Option<u16>) -> u16 {
+        let id = match to {
+            Some(id) => {
+                self.sessions.write().await.insert(id, addr.to_string());
+                id
+            }
+            None => self.assign_id(addr.to_string()).await,
+        };
+        if !self.sessions.read().await.contains_user(&from) {
+            if self.name_table.read().await.contains_key(&from) {
+                self.forget_user(from, None).await;
+            } else {
+                self.public_keys.write().await.remove(&from);
+                self.authenticated.write().await.remove(&from);
+                self.leave_rooms(from, &format!("ID {0}", from)).await;
+            }
+        }
+        id
+    }
 
-                    for (dest_id, message) in messages {
-                        log_clone
-                            .write()
+    /// Confere as credenciais de um `Connection` e se o nome pedido num `SetName`
+    /// pertence a outra conta. Retorna o id e o nome da conta autenticada pelo
+    /// `Connection`; a sessão passa a pertencer ao usuário com esse id.
+    async fn authorize(
+        &self,
+        message: &Message,
+        from: u16,
+    ) -> Result<Option<(u16, String)>, String> {
+        match message.metadata.message_type {
+            MessageType::Connection => {
+                let credentials = Credentials::decode(&message.content);
+                let account = match self.accounts.read().await.as_ref() {
+                    Some(accounts) => accounts.get(&credentials.name).cloned(),
+                    None => None,
+                };
+                match account {
+                    Some(account) => {
+                        let id = account.id;
+                        let name = credentials.name.clone();
+                        let verified = task::spawn_blocking(move || account.verify(&credentials))
                             .await
-                            .push_str(&format!("\nEnviando mensagem para ID {0}", dest_id));
-                        let dest_client_addr = {
-                            let id_table_read = id_table_clone.read().await;
-                            match id_table_read.get_by_left(&dest_id) {
-                                Some(addr) => addr.clone(),
-                                None => {
-                                    log_clone
-                                        .write()
-                                        .await
-                                        .push_str("\nFalha ao encontrar destinatário.");
-                                    continue;
-                                }
-                            }
-                        };
-                        if dest_client_addr.starts_with("udp") {
-                            continue;
-                        } else {
-                            let stream = {
-                                let tcp_clients_read = tcp_clients_clone.read().await;
-                                match tcp_clients_read.get(&dest_client_addr) {
-                                    Some(client) => client.clone(),
-                                    None => {
-                                        log_clone
-                                            .write()
-                                            .await
-                                            .push_str("\nFalha ao encontrar destinatário.");
-                                        continue;
-                                    }
-                                }
-                            };
-                            let mut stream = stream.lock().await;
-                            Self::send_tcp(&mut stream, message, log_clone.clone())
-                                .await
-                                .unwrap();
+                            .unwrap_or(false);
+                        if !verified {
+                            return Err("Credenciais inválidas".to_string());
                         }
+                        Ok(Some((id, name)))
+                    }
+                    None if !credentials.is_anonymous() => Err("Credenciais inválidas".to_string()),
+                    None if self.config().await.require_auth => {
+                        Err("Autenticação obrigatória".to_string())
                     }
+                    None => Ok(None),
                 }
-            });
+            }
+            MessageType::Disconnect => Ok(None),
+            MessageType::Admin => {
+                self.check_admin(from).await?;
+                if let Ok(AdminCommand::Rename { user, name }) =
+                    serde_json::from_slice(&message.content)
+                {
+                    self.check_registered_name(user, name.trim()).await?;
+                }
+                Ok(None)
+            }
+            _ if self.config().await.require_auth
+                && !self.authenticated.read().await.contains_key(&from) =>
+            {
+                Err("Autenticação obrigatória".to_string())
+            }
+            MessageType::History if !self.authenticated.read().await.contains_key(&from) => {
+                Err("Histórico disponível apenas para usuários autenticados".to_string())
+            }
+            MessageType::SetName => {
+                let name = String::from_utf8_lossy(&message.content).trim().to_string();
+                self.check_registered_name(from, &name).await?;
+                Ok(None)
+            }
+            _ => Ok(None),
         }
     }
 
+    /// Mensagens `Admin` exigem uma conta administradora; o id 0 é a interface
+    /// do próprio servidor.
+    async fn check_admin(&self, id: u16) -> Result<(), String> {
+        if id == 0 {
+            return Ok(());
+        }
+        let account = self.authenticated.read().await.get(&id).cloned();
+        let admin = match (account, self.accounts.read().await.as_ref()) {
+            (Some(account), Some(accounts)) => accounts.is_admin(&account),
+            _ => false,
+        };
+        if !admin {
+            return Err("Comando disponível apenas para administradores".to_string());
+        }
+        Ok(())
+    }
+
+    /// Um nome registrado só pode ser usado pelo client autenticado nessa conta.
+    async fn check_registered_name(&self, id: u16, name: &str) -> Result<(), String> {
+        let registered = match self.accounts.read().await.as_ref() {
+            Some(accounts) => accounts.contains(name),
+            None => false,
+        };
+        let owner = self.authenticated.read().await.get(&id).cloned();
+        if registered && owner.as_deref() != Some(name) {
+            return Err(format!("Nome registrado por outro usuário: {0}", name));
+        }
+        Ok(())
+    }
+
     async fn process_message(
+        &self,
         message: &mut Message,
         from: u16,
-        id_table: &mut BiMap<u16, String>,
-        name_table: &mut HashMap<u16, String>,
-        log: &Arc<RwLock<String>>,
+        tables: Tables<'_>,
+        appended: &mut Vec<HistoryEntry>,
     ) -> Vec<(u16, Message)> {
+        let Tables {
+            sessions,
+            name_table,
+            public_keys,
+            rooms,
+        } = tables;
         let mut messages = Vec::new();
+        let key = message.metadata.key;
         match message.metadata.message_type {
-            MessageType::File | MessageType::Text => {
-                let content = String::from_utf8_lossy(&message.content);
-                let dest_message = Message::new_text(
-                    message.metadata.key,
-                    message.metadata.receiver_id,
-                    content.to_string(),
-                    None,
-                    None,
+            MessageType::File | MessageType::Text | MessageType::Encrypted => {
+                let receiver_id = message.metadata.receiver_id;
+                if !sessions.contains_user(&receiver_id) {
+                    messages.push((
+                        from,
+                        Message::new_error_response(
+                            key,
+                            from,
+                            format!("Destinatário {0} offline", receiver_id),
+                        ),
+                    ));
+                    return messages;
+                }
+                messages.push((receiver_id, Message::new_forward(key, from, message)));
+                messages.push((from, Message::new_generic_response(key, receiver_id, true)));
+                appended.push(HistoryEntry::routed(from, receiver_id, message));
+                let content = if message.metadata.message_type == MessageType::Text {
+                    Some(String::from_utf8_lossy(&message.content).to_string())
+                } else {
+                    None
+                };
+                self.events.emit(
+                    Severity::Info,
+                    ServerEventKind::MessageRouted {
+                        from,
+                        to: receiver_id,
+                        message_type: message.metadata.message_type,
+                        bytes: message.content.len(),
+                        content,
+                    },
                 );
-                let response_message = Message::new_generic_response(
-                    message.metadata.key,
-                    message.metadata.receiver_id,
-                    true,
+            }
+            MessageType::Broadcast => {
+                for id in sessions.ids().filter(|id| **id != from) {
+                    messages.push((*id, Message::new_forward(key, from, message)));
+                }
+                messages.push((from, Message::new_generic_response(key, from, true)));
+                self.events.emit(
+                    Severity::Info,
+                    ServerEventKind::MessageRouted {
+                        from,
+                        to: 0,
+                        message_type: MessageType::Broadcast,
+                        bytes: message.content.len(),
+                        content: Some(String::from_utf8_lossy(&message.content).to_string()),
+                    },
                 );
-                messages.push((message.metadata.receiver_id, dest_message));
-                messages.push((from, response_message));
-                log.write().await.push_str(&format!(
-                    "\nMensagem de {0} para {1}:\n{2}\n",
-                    from.to_string(),
-                    message.metadata.receiver_id,
-                    content
-                ));
             }
-            MessageType::Connection => {
-                let client_name = String::from_utf8_lossy(&message.content).trim().to_string();
-                let success = !name_table.values().any(|name| name == &client_name);
-                if success {
-                    name_table.insert(from, client_name.clone());
+            MessageType::Connection | MessageType::SetName => {
+                let client_name = if message.metadata.message_type == MessageType::Connection {
+                    Credentials::decode(&message.content).name
+                } else {
+                    String::from_utf8_lossy(&message.content).trim().to_string()
+                };
+                let taken = name_table
+                    .iter()
+                    .any(|(id, name)| *id != from && name == &client_name);
+                if client_name.is_empty() || taken {
+                    let reason = if taken {
+                        format!("Nome já existente: {0}", client_name)
+                    } else {
+                        "Nome vazio".to_string()
+                    };
+                    self.events.emit(
+                        Severity::Warning,
+                        ServerEventKind::Rejected {
+                            id: from,
+                            reason: reason.clone(),
+                        },
+                    );
+                    messages.push((from, Message::new_error_response(key, from, reason)));
+                    return messages;
                 }
-                let response_message =
-                    Message::new_generic_response(message.metadata.key, from, success);
-                messages.push((from, response_message));
-                log.write().await.push_str(&format!(
-                    "\nClient ID {0} - Nome: {1}\nConectado\n",
-                    from, client_name
-                ));
+                name_table.insert(from, client_name.clone());
+                messages.push((from, Message::new_generic_response(key, from, true)));
+                for id in sessions.ids().filter(|id| **id != from) {
+                    messages.push((
+                        *id,
+                        Message::new_presence(*id, from, client_name.clone(), true),
+                    ));
+                }
+                let kind = if message.metadata.message_type == MessageType::Connection {
+                    ServerEventKind::Registered {
+                        id: from,
+                        name: client_name,
+                    }
+                } else {
+                    ServerEventKind::Renamed {
+                        id: from,
+                        name: client_name,
+                        by_server: false,
+                    }
+                };
+                self.events.emit(Severity::Info, kind);
             }
             MessageType::ListClients => {
-                println!("Listando clientes conectados...");
                 let mut clients = Vec::<(u16, String)>::new();
-                for client in id_table.iter() {
-                    let id = *client.0;
+                for id in sessions.ids() {
+                    let id = *id;
                     let name = match name_table.get(&id) {
                         Some(name) => name.clone(),
                         None => String::from("Sem nome"),
                     };
                     clients.push((id, name));
                 }
-                //Message::new_list_clients(message.metadata.sender_id, clients, udp_id)
+                messages.push((
+                    from,
+                    Message::new_list_clients_response(key, from, clients, None, None),
+                ));
             }
-            MessageType::SetName => {
-                let client_name = String::from_utf8_lossy(&message.content).trim().to_string();
-                let success = !name_table.values().any(|name| name == &client_name);
-                if success {
-                    name_table.insert(from, client_name.clone());
+            MessageType::PublicKey if message.metadata.receiver_id == 0 => {
+                if message.content.len() != PUBLIC_KEY_BYTES {
+                    messages.push((
+                        from,
+                        Message::new_error_response(
+                            key,
+                            from,
+                            "Chave pública inválida".to_string(),
+                        ),
+                    ));
+                    return messages;
+                }
+                public_keys.insert(from, message.content.to_vec());
+                messages.push((from, Message::new_generic_response(key, from, true)));
+            }
+            MessageType::PublicKey => {
+                let owner_id = message.metadata.receiver_id;
+                let response = match public_keys.get(&owner_id) {
+                    Some(public_key) => {
+                        Message::new_public_key_response(key, owner_id, public_key.clone())
+                    }
+                    None => Message::new_error_response(
+                        key,
+                        from,
+                        format!("ID {0} sem chave pública", owner_id),
+                    ),
+                };
+                messages.push((from, response));
+            }
+            MessageType::CreateRoom => {
+                let name = String::from_utf8_lossy(&message.content).trim().to_string();
+                match rooms.create(&name, from) {
+                    Ok(room) => {
+                        messages.push((from, Message::new_room_response(key, &room.info())));
+                        self.events.emit(
+                            Severity::Info,
+                            ServerEventKind::RoomCreated {
+                                room: room.id,
+                                name,
+                                by: from,
+                            },
+                        );
+                    }
+                    Err(error) => {
+                        messages.push((from, Message::new_coded_error(key, from, &error)))
+                    }
+                }
+            }
+            MessageType::JoinRoom => {
+                let join = RoomJoin::decode(&message.content);
+                let (account, addresses) = self.identities(sessions, from).await;
+                let identities: Vec<String> = account.into_iter().chain(addresses).collect();
+                let joined = rooms
+                    .resolve(message.metadata.receiver_id, &join.name)
+                    .and_then(|id| {
+                        rooms
+                            .join(id, from, &identities, join.password.as_deref())
+                            .map(|new| (id, new))
+                    });
+                match joined {
+                    Ok((id, new)) => {
+                        let room = rooms.get(&id).unwrap();
+                        messages.push((from, Message::new_room_response(key, &room.info())));
+                        if new {
+                            let user = Self::display_name(name_table, from);
+                            messages
+                                .extend(room.notice(from, &format!("{0} entrou na sala", user)));
+                            self.events.emit(
+                                Severity::Info,
+                                ServerEventKind::RoomJoined { room: id, id: from },
+                            );
+                        }
+                    }
+                    Err(error) => {
+                        messages.push((from, Message::new_coded_error(key, from, &error)))
+                    }
+                }
+            }
+            MessageType::LeaveRoom => {
+                let name = String::from_utf8_lossy(&message.content).trim().to_string();
+                let left = rooms
+                    .resolve(message.metadata.receiver_id, &name)
+                    .and_then(|id| rooms.leave(id, from).map(|departure| (id, departure)));
+                match left {
+                    Ok((id, departure)) => {
+                        messages.push((from, Message::new_generic_response(key, id, true)));
+                        if let Some(room) = rooms.get(&id) {
+                            let user = Self::display_name(name_table, from);
+                            messages.extend(Self::departure_notice(
+                                name_table,
+                                room,
+                                &format!("{0} saiu da sala", user),
+                                departure,
+                            ));
+                        }
+                        self.events.emit(
+                            Severity::Info,
+                            ServerEventKind::RoomLeft {
+                                room: id,
+                                id: from,
+                                closed: departure.closed,
+                            },
+                        );
+                    }
+                    Err(error) => {
+                        messages.push((from, Message::new_coded_error(key, from, &error)))
+                    }
+                }
+            }
+            MessageType::ListRooms => {
+                messages.push((from, Message::new_list_rooms_response(key, &rooms.list())));
+            }
+            MessageType::RoomMessage => {
+                let room_id = message.metadata.receiver_id;
+                let room = match rooms
+                    .get_mut(&room_id)
+                    .and_then(|room| room.check_speaker(from).map(|_| room))
+                {
+                    Ok(room) => room,
+                    Err(error) => {
+                        messages.push((from, Message::new_coded_error(key, from, &error)));
+                        return messages;
+                    }
+                };
+                let forward = Message::new_room_forward(key, room_id, from, &message.content);
+                messages.extend(room.fan_out(from, &forward));
+                messages.push((from, Message::new_generic_response(key, room_id, true)));
+                appended.push(HistoryEntry::in_room(from, room_id, message));
+                self.events.emit(
+                    Severity::Info,
+                    ServerEventKind::RoomMessage {
+                        from,
+                        room: room_id,
+                        content: String::from_utf8_lossy(&message.content).to_string(),
+                    },
+                );
+            }
+            MessageType::ModerateRoom => {
+                let room_id = message.metadata.receiver_id;
+                let Ok(action) = serde_json::from_slice::<RoomAction>(&message.content) else {
+                    let error =
+                        ErrorBody::new(ErrorCode::InvalidRequest, "Ação de moderação inválida");
+                    messages.push((from, Message::new_coded_error(key, from, &error)));
+                    return messages;
+                };
+                let identities = match &action {
+                    RoomAction::Ban { user } => match self.identities(sessions, *user).await {
+                        (Some(account), _) => vec![account],
+                        (None, addresses) => addresses,
+                    },
+                    _ => Vec::new(),
+                };
+                let removed = match (&action, rooms.get(&room_id)) {
+                    (RoomAction::Kick { user } | RoomAction::Ban { user }, Some(room)) => {
+                        room.members.contains(user).then_some(*user)
+                    }
+                    _ => None,
+                };
+                let applied = match &action {
+                    RoomAction::Invite { user } if !sessions.contains_user(user) => {
+                        Err(ErrorBody::new(
+                            ErrorCode::InvalidRequest,
+                            format!("Destinatário {0} offline", user),
+                        ))
+                    }
+                    _ => rooms
+                        .get_mut(&room_id)
+                        .and_then(|room| room.apply(from, &action, &identities).map(|_| room)),
+                };
+                let room = match applied {
+                    Ok(room) => room,
+                    Err(error) => {
+                        messages.push((from, Message::new_coded_error(key, from, &error)));
+                        return messages;
+                    }
+                };
+                let actor = Self::display_name(name_table, from);
+                let target = |user: &u16| Self::display_name(name_table, *user);
+                let notice = match &action {
+                    RoomAction::Kick { user } => {
+                        format!("{0} foi expulso por {1}", target(user), actor)
+                    }
+                    RoomAction::Ban { user } => format!(
+                        "{0} foi banido por {1} ({2})",
+                        target(user),
+                        actor,
+                        identities.join(", ")
+                    ),
+                    RoomAction::Unban { identity } => {
+                        format!("{0} removeu o banimento de {1}", actor, identity)
+                    }
+                    RoomAction::Op { user } => format!("{0} agora é operador", target(user)),
+                    RoomAction::Deop { user } => {
+                        format!("{0} deixou de ser operador", target(user))
+                    }
+                    RoomAction::Mute { user } => {
+                        format!("{0} foi silenciado por {1}", target(user), actor)
+                    }
+                    RoomAction::Unmute { user } => {
+                        format!("{0} pode falar novamente", target(user))
+                    }
+                    RoomAction::Invite { user } => format!("{0} convidou {1}", actor, target(user)),
+                    RoomAction::Topic { .. } => match &room.topic {
+                        Some(topic) => format!("{0} mudou o tópico: {1}", actor, topic),
+                        None => format!("{0} removeu o tópico", actor),
+                    },
+                    RoomAction::InviteOnly { enabled: true } => {
+                        format!("{0} restringiu a sala a convidados", actor)
+                    }
+                    RoomAction::InviteOnly { enabled: false } => {
+                        format!("{0} abriu a sala a todos", actor)
+                    }
+                    RoomAction::Password { .. } if room.info().protected => {
+                        format!("{0} definiu uma senha para a sala", actor)
+                    }
+                    RoomAction::Password { .. } => format!("{0} removeu a senha da sala", actor),
+                };
+                match &action {
+                    RoomAction::Kick { .. } | RoomAction::Ban { .. } => {
+                        if let Some(user) = removed {
+                            let reason = format!("{0} na sala {1}", notice, room.name);
+                            messages.push((user, Message::new_room_removed(room_id, &reason)));
+                        }
+                    }
+                    RoomAction::Invite { user } => {
+                        let text = format!("{0} convidou você para a sala {1}", actor, room.name);
+                        messages.push((
+                            *user,
+                            Message::new_text(Message::generate_key(), 0, text, None, None),
+                        ));
+                    }
+                    _ => {}
+                }
+                messages.extend(room.notice(0, &notice));
+                messages.push((from, Message::new_room_response(key, &room.info())));
+                self.events.emit(
+                    Severity::Info,
+                    ServerEventKind::RoomModerated {
+                        room: room_id,
+                        by: from,
+                        action: notice,
+                    },
+                );
+            }
+            MessageType::Admin => {
+                let Ok(command) = serde_json::from_slice::<AdminCommand>(&message.content) else {
+                    let error = ErrorBody::new(
+                        ErrorCode::InvalidRequest,
+                        "Comando administrativo inválido",
+                    );
+                    messages.push((from, Message::new_coded_error(key, from, &error)));
+                    return messages;
+                };
+                let offline = |user: u16| {
+                    let error = ErrorBody::new(
+                        ErrorCode::InvalidRequest,
+                        format!("ID {0} não encontrado", user),
+                    );
+                    Message::new_coded_error(key, from, &error)
+                };
+                match command {
+                    AdminCommand::Kick { user } => {
+                        if !sessions.contains_user(&user) {
+                            messages.push((from, offline(user)));
+                            return messages;
+                        }
+                        let notice = Message::new_text(
+                            Message::generate_key(),
+                            0,
+                            "Você foi desconectado pelo servidor".to_string(),
+                            None,
+                            None,
+                        );
+                        messages.push((user, notice));
+                        messages.push((user, Message::new_disconnect_request(0)));
+                        let text = format!("Client ID {0} expulso", user);
+                        messages.push((from, Message::new_text_response(key, from, &text)));
+                        self.events
+                            .emit(Severity::Warning, ServerEventKind::Kicked { id: user });
+                    }
+                    AdminCommand::Rename { user, name } => {
+                        let name = name.trim().to_string();
+                        let reason = if !sessions.contains_user(&user) {
+                            Some(format!("ID {0} não encontrado", user))
+                        } else if let Err(reason) = validation::validate_name(&name) {
+                            Some(reason)
+                        } else if name_table
+                            .iter()
+                            .any(|(other, other_name)| *other != user && other_name == &name)
+                        {
+                            Some(format!("Nome já existente: {0}", name))
+                        } else {
+                            None
+                        };
+                        if let Some(reason) = reason {
+                            let error = ErrorBody::new(ErrorCode::InvalidRequest, reason);
+                            messages.push((from, Message::new_coded_error(key, from, &error)));
+                            return messages;
+                        }
+                        name_table.insert(user, name.clone());
+                        for id in sessions.ids() {
+                            messages
+                                .push((*id, Message::new_presence(*id, user, name.clone(), true)));
+                        }
+                        let text = format!("Client ID {0} renomeado para \"{1}\"", user, name);
+                        messages.push((from, Message::new_text_response(key, from, &text)));
+                        self.events.emit(
+                            Severity::Info,
+                            ServerEventKind::Renamed {
+                                id: user,
+                                name,
+                                by_server: true,
+                            },
+                        );
+                    }
+                    AdminCommand::Notice { user, text } => {
+                        let recipients: Vec<u16> = match user {
+                            Some(user) if !sessions.contains_user(&user) => {
+                                messages.push((from, offline(user)));
+                                return messages;
+                            }
+                            Some(user) => vec![user],
+                            None => sessions.ids().copied().collect(),
+                        };
+                        for id in recipients {
+                            let notice = Message::new_text(
+                                Message::generate_key(),
+                                0,
+                                text.clone(),
+                                None,
+                                None,
+                            );
+                            messages.push((id, notice));
+                        }
+                        let sent = match user {
+                            Some(user) => format!("Aviso enviado para ID {0}", user),
+                            None => "Aviso enviado para todos".to_string(),
+                        };
+                        messages.push((from, Message::new_text_response(key, from, &sent)));
+                        self.events.emit(
+                            Severity::Info,
+                            ServerEventKind::Notice {
+                                id: user.unwrap_or(0),
+                                text,
+                            },
+                        );
+                    }
+                    AdminCommand::Stats => {
+                        let stats = ServerStats {
+                            users: sessions.len(),
+                            sessions: sessions.iter().map(|(_, addrs)| addrs.len()).sum(),
+                            authenticated: self.authenticated.read().await.len(),
+                            rooms: rooms.len(),
+                            offline_messages: self.offline.lock().await.len(),
+                            uptime_secs: self.started.elapsed().as_secs(),
+                        };
+                        let content = serde_json::to_string(&stats).unwrap();
+                        messages.push((from, Message::new_text_response(key, from, &content)));
+                    }
+                    AdminCommand::ReloadConfig => {}
                 }
-                log.write().await.push_str(&format!(
-                    "\nClient ID {0} - Novo nome: {1}",
-                    from, client_name
-                ));
+            }
+            MessageType::Disconnect => {
+                messages.push((from, Message::new_generic_response(key, from, true)));
             }
             _ => {}
         }
         messages
     }
 
-    async fn send_tcp(
-        stream: &mut TcpStream,
-        message: Message,
-        log: Arc<RwLock<String>>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let message_bytes = message.serialize().await;
-        stream.write_all(&message_bytes).await?;
-        log.write().await.push_str(&format!(
-            "\nMensagem enviada para Client: {0} - ID {1}",
-            stream.peer_addr().unwrap(),
-            message.metadata.receiver_id
-        ));
-        Ok(())
+    fn display_name(name_table: &HashMap<u16, String>, id: u16) -> String {
+        match name_table.get(&id) {
+            Some(name) => name.clone(),
+            None => format!("ID {0}", id),
+        }
     }
 
-    async fn listen_udp(
-        socket: UdpSocket,
-        id_table: Arc<RwLock<BiMap<u16, String>>>,
-        udp_id_map: Arc<RwLock<HashMap<u16, Vec<u16>>>>,
-        udp_data_map: Arc<RwLock<HashMap<u16, Vec<Message>>>>,
-    ) {
-        let mut buf = [0; 1024];
-        loop {
-            let (len, addr) = match socket.recv_from(&mut buf).await {
-                Ok((len, addr)) => (len, addr),
-                Err(e) => {
-                    eprintln!("Falha ao receber dados: {}", e);
-                    continue;
+    /// Entrega cada mensagem em todas as sessões do usuário de destino.
+    async fn dispatch(&self, messages: Vec<(u16, Message)>) {
+        for (dest_id, message) in messages {
+            let addrs = self.sessions.read().await.sessions(&dest_id).to_vec();
+            if addrs.is_empty() {
+                self.events.emit(
+                    Severity::Warning,
+                    ServerEventKind::Error {
+                        message: format!("Falha ao encontrar destinatário ID {0}", dest_id),
+                    },
+                );
+                continue;
+            }
+            // O cabeçalho é serializado uma vez e o conteúdo é compartilhado
+            // entre as sessões.
+            let frame = message.frame();
+            for addr in addrs {
+                self.send_to(&addr, dest_id, &message, &frame).await;
+            }
+        }
+    }
+
+    /// Envia a mensagem para uma única sessão do usuário `id`.
+    async fn reply(&self, addr: &str, id: u16, message: &Message) {
+        self.send_to(addr, id, message, &message.frame()).await;
+    }
+
+    /// Envia a mensagem para a sessão `addr`: por TCP, escreve `frame`, que
+    /// deve ser a mensagem já serializada.
+    async fn send_to(&self, addr: &str, id: u16, message: &Message, frame: &Frame) {
+        let result = match addr.strip_prefix(UDP_PREFIX) {
+            Some(udp_addr) => self.send_udp(udp_addr, message).await,
+            None => {
+                let stream = {
+                    let tcp_clients_read = self.tcp_clients.read().await;
+                    tcp_clients_read.get(addr).cloned()
+                };
+                match stream {
+                    Some(stream) => stream
+                        .lock()
+                        .await
+                        .send(frame.clone())
+                        .await
+                        .map_err(Into::into),
+                    None => Err("Falha ao encontrar destinatário.".into()),
                 }
-            };
-            let addr_str = addr.to_string();
-            let id = {
-                let id_table_read = id_table.read().await;
-                match id_table_read.get_by_right(&addr_str) {
-                    Some(&id) => id,
-                    None => {
-                        drop(id_table_read);
-                        Self::assign_id(addr_str.clone(), id_table.clone()).await
-                    }
+            }
+        };
+        if let Err(e) = result {
+            self.events.emit(
+                Severity::Error,
+                ServerEventKind::Error {
+                    message: format!("Falha ao enviar mensagem para ID {0}: {1}", id, e),
+                },
+            );
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        self.events.emit(Severity::Info, ServerEventKind::Stopping);
+        let ids: Vec<u16> = self.sessions.read().await.ids().copied().collect();
+        let mut messages = Vec::new();
+        for id in &ids {
+            let notice = Message::new_text(
+                Message::generate_key(),
+                0,
+                "Servidor encerrando".to_string(),
+                None,
+                None,
+            );
+            messages.push((*id, notice));
+            messages.push((*id, Message::new_disconnect_request(0)));
+        }
+        self.dispatch(messages).await;
+        let _ = self.shutdown_tx.send(true);
+        self.save_offline().await;
+        for (_, stream) in self.tcp_clients.write().await.drain() {
+            let _ = stream.lock().await.close().await;
+        }
+        self.sessions.write().await.clear();
+        self.name_table.write().await.clear();
+        self.public_keys.write().await.clear();
+        self.authenticated.write().await.clear();
+        self.rooms.write().await.clear();
+        self.udp_data_map.write().await.clear();
+        self.udp_sessions.write().await.clear();
+        self.events.emit(
+            Severity::Info,
+            ServerEventKind::Stopped { clients: ids.len() },
+        );
+    }
+
+    /// Encerra todas as sessões do usuário.
+    async fn disconnect(&self, id: u16, reason: Option<String>) {
+        let addrs = self.sessions.write().await.remove_user(&id);
+        if addrs.is_empty() {
+            return;
+        }
+        for addr in &addrs {
+            self.close_session(addr).await;
+        }
+        self.forget_user(id, reason).await;
+    }
+
+    /// Encerra uma sessão. O usuário só fica offline quando fecha a última.
+    async fn disconnect_session(&self, addr: &str, reason: Option<String>) {
+        let Some((id, last)) = self.sessions.write().await.remove_session(addr) else {
+            return;
+        };
+        self.close_session(addr).await;
+        if last {
+            self.forget_user(id, reason).await;
+        } else {
+            self.events.emit(
+                Severity::Info,
+                ServerEventKind::SessionClosed {
+                    id,
+                    addr: addr.to_string(),
+                    reason,
+                },
+            );
+        }
+    }
+
+    async fn close_session(&self, addr: &str) {
+        self.udp_data_map.write().await.remove(addr);
+        if let Some(stream) = self.tcp_clients.write().await.remove(addr) {
+            let _ = stream.lock().await.close().await;
+        }
+        self.udp_sessions.write().await.remove(addr);
+        self.limiter.lock().await.forget(addr);
+    }
+
+    /// Descarta o estado de um usuário sem sessões e avisa os demais.
+    async fn forget_user(&self, id: u16, reason: Option<String>) {
+        let name = self.name_table.write().await.remove(&id);
+        self.public_keys.write().await.remove(&id);
+        self.authenticated.write().await.remove(&id);
+        let name = name.unwrap_or(String::from("Sem nome"));
+        self.leave_rooms(id, &name).await;
+        let presence: Vec<(u16, Message)> = self
+            .sessions
+            .read()
+            .await
+            .ids()
+            .map(|dest| (*dest, Message::new_presence(*dest, id, name.clone(), false)))
+            .collect();
+        self.dispatch(presence).await;
+        self.events.emit(
+            Severity::Info,
+            ServerEventKind::Disconnected { id, name, reason },
+        );
+    }
+
+    /// Tira o usuário de todas as salas e avisa os membros que ficaram.
+    async fn leave_rooms(&self, id: u16, name: &str) {
+        let mut messages = Vec::new();
+        {
+            let name_table = self.name_table.read().await;
+            let mut rooms = self.rooms.write().await;
+            for (room, departure) in rooms.leave_all(id) {
+                if let Some(room) = rooms.get(&room) {
+                    messages.extend(Self::departure_notice(
+                        &name_table,
+                        room,
+                        &format!("{0} saiu da sala", name),
+                        departure,
+                    ));
                 }
+                self.events.emit(
+                    Severity::Info,
+                    ServerEventKind::RoomLeft {
+                        room,
+                        id,
+                        closed: departure.closed,
+                    },
+                );
+            }
+        }
+        self.dispatch(messages).await;
+    }
+
+    /// Avisos aos membros que ficaram na sala depois de uma saída.
+    fn departure_notice(
+        name_table: &HashMap<u16, String>,
+        room: &Room,
+        text: &str,
+        departure: Departure,
+    ) -> Vec<(u16, Message)> {
+        let mut messages = room.notice(0, text);
+        if let Some(owner) = departure.new_owner {
+            let text = format!(
+                "{0} agora é dono da sala",
+                Self::display_name(name_table, owner)
+            );
+            messages.extend(room.notice(0, &text));
+        }
+        messages
+    }
+
+    /// Identidades do usuário usadas nos banimentos de salas: a conta, se ele
+    /// estiver autenticado, e o IP de cada sessão.
+    async fn identities(&self, sessions: &SessionTable, id: u16) -> (Option<String>, Vec<String>) {
+        let account = self
+            .authenticated
+            .read()
+            .await
+            .get(&id)
+            .map(|name| format!("conta:{0}", name));
+        let mut addresses: Vec<String> = sessions
+            .sessions(&id)
+            .iter()
+            .filter_map(|addr| source_ip(addr))
+            .map(|ip| format!("ip:{0}", ip))
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        (account, addresses)
+    }
+
+    async fn send_udp(
+        &self,
+        addr: &str,
+        message: &Message,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let socket = self.udp_socket.get().ok_or("Socket UDP indisponível")?;
+        let config = self.config().await;
+        let session = match &config.udp_psk {
+            Some(_) => Some(
+                self.udp_sessions
+                    .read()
+                    .await
+                    .get(&format!("{0}{1}", UDP_PREFIX, addr))
+                    .cloned()
+                    .ok_or("Sessão UDP inexistente")?,
+            ),
+            None => None,
+        };
+        let packet_size = match session {
+            Some(_) => config.buffer_size - SEAL_OVERHEAD,
+            None => config.buffer_size,
+        };
+        let target: SocketAddr = addr.parse()?;
+        let udp_id = self.udp_ids.fetch_add(1, Ordering::Relaxed);
+        let packets = udp::fragment(message, udp_id, packet_size)?;
+        for packet in packets {
+            match &session {
+                Some(session) => socket.send_to(&session.seal(&packet), target).await?,
+                None => socket.send_to(&packet, target).await?,
             };
-            let mut udp_id_map_read = udp_id_map.write().await;
-            let owned_ids = udp_id_map_read.entry(id).or_insert(Vec::new());
-            let mut udp_data_map_write = udp_data_map.write().await;
-            let current_packets = udp_data_map_write.entry(id).or_insert(Vec::new());
-            udp::build_udp_message(buf[..len].to_vec(), owned_ids.clone(), current_packets).await;
-            println!("Recebendo dados UDP de {0} - ID {1}", addr, id);
         }
+        Ok(())
     }
 
-    async fn assign_id(addr: String, id_table: Arc<RwLock<BiMap<u16, String>>>) -> u16 {
+    /// Cria um usuário anônimo para a sessão, com um id que não pertence a
+    /// nenhum usuário online nem a uma conta registrada.
+    async fn assign_id(&self, addr: String) -> u16 {
+        let accounts = self.accounts.read().await;
+        let mut sessions = self.sessions.write().await;
         loop {
             let id = rand::random::<u16>();
-            let mut id_table_write = id_table.write().await;
-            if !id_table_write.contains_left(&id) {
-                id_table_write.insert(id, addr.clone());
+            let reserved = accounts
+                .as_ref()
+                .is_some_and(|accounts| accounts.is_reserved(id));
+            if id != 0 && !reserved && !sessions.contains_user(&id) {
+                sessions.insert(id, addr);
                 return id;
             }
         }
     }
 }
 
+/// IP de origem de uma sessão TCP ou UDP.
+fn source_ip(addr: &str) -> Option<IpAddr> {
+    let addr = addr.strip_prefix(UDP_PREFIX).unwrap_or(addr);
+    addr.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
+
 impl Default for Server {
     fn default() -> Self {
-        Self::new()
+        Self::new(ServerConfig::default())
     }
 }
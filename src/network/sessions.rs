@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+/// Sessões de transporte de cada usuário. O id do usuário é o que os clients
+/// enxergam; cada conexão TCP (`ip:porta`) ou endereço UDP (`udp://ip:porta`)
+/// é uma sessão, e um mesmo usuário pode ter várias, de qualquer transporte.
+#[derive(Debug, Default)]
+pub struct SessionTable {
+    users: HashMap<u16, Vec<String>>,
+    sessions: HashMap<String, u16>,
+}
+
+impl SessionTable {
+    /// Associa a sessão ao usuário, tirando-a do usuário anterior, se houver.
+    pub fn insert(&mut self, id: u16, addr: String) {
+        if let Some(previous) = self.sessions.insert(addr.clone(), id) {
+            self.detach(previous, &addr);
+        }
+        self.users.entry(id).or_default().push(addr);
+    }
+
+    pub fn user(&self, addr: &str) -> Option<u16> {
+        self.sessions.get(addr).copied()
+    }
+
+    pub fn contains_user(&self, id: &u16) -> bool {
+        self.users.contains_key(id)
+    }
+
+    pub fn sessions(&self, id: &u16) -> &[String] {
+        self.users.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &u16> {
+        self.users.keys()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&u16, &Vec<String>)> {
+        self.users.iter()
+    }
+
+    /// Remove a sessão. Retorna o usuário dono dela e se era a última sessão dele.
+    pub fn remove_session(&mut self, addr: &str) -> Option<(u16, bool)> {
+        let id = self.sessions.remove(addr)?;
+        Some((id, self.detach(id, addr)))
+    }
+
+    /// Remove o usuário e retorna as sessões que ele tinha.
+    pub fn remove_user(&mut self, id: &u16) -> Vec<String> {
+        let addrs = self.users.remove(id).unwrap_or_default();
+        for addr in &addrs {
+            self.sessions.remove(addr);
+        }
+        addrs
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.users.clear();
+        self.sessions.clear();
+    }
+
+    fn detach(&mut self, id: u16, addr: &str) -> bool {
+        let Some(addrs) = self.users.get_mut(&id) else {
+            return false;
+        };
+        addrs.retain(|other| other != addr);
+        if addrs.is_empty() {
+            self.users.remove(&id);
+            return true;
+        }
+        false
+    }
+}
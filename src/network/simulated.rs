@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time;
+
+use crate::network::datagram::DatagramTransport;
+
+/// Condições da rede simulada, aplicadas a cada datagrama enviado.
+#[derive(Debug, Clone, Default)]
+pub struct Impairment {
+    /// Probabilidade, entre 0 e 1, de o datagrama se perder.
+    pub drop_rate: f64,
+    /// Probabilidade, entre 0 e 1, de o datagrama ser entregue duas vezes.
+    pub duplicate_rate: f64,
+    /// Atraso máximo de cada entrega. Cada cópia sorteia o seu, então
+    /// datagramas enviados em sequência podem chegar fora de ordem.
+    pub jitter: Duration,
+    /// Maior datagrama que passa pela rede; os maiores são descartados.
+    pub mtu: Option<usize>,
+}
+
+/// Contagem dos datagramas que passaram pela rede simulada.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    pub sent: usize,
+    pub dropped: usize,
+    pub duplicated: usize,
+    pub oversized: usize,
+}
+
+type Datagram = (Vec<u8>, SocketAddr);
+
+struct Hub {
+    sockets: HashMap<SocketAddr, UnboundedSender<Datagram>>,
+    impairment: Impairment,
+    rng: StdRng,
+    stats: NetworkStats,
+    next_port: u16,
+}
+
+/// Rede de datagramas em memória, sem sockets. Os sorteios de perda,
+/// duplicação e atraso vêm de um gerador com semente, para que um teste que
+/// falhe possa ser repetido.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+    hub: Arc<Mutex<Hub>>,
+}
+
+impl SimulatedNetwork {
+    pub fn new(impairment: Impairment, seed: u64) -> Self {
+        SimulatedNetwork {
+            hub: Arc::new(Mutex::new(Hub {
+                sockets: HashMap::new(),
+                impairment,
+                rng: StdRng::seed_from_u64(seed),
+                stats: NetworkStats::default(),
+                next_port: 1024,
+            })),
+        }
+    }
+
+    /// Troca as condições da rede; vale para os próximos envios.
+    pub fn set_impairment(&self, impairment: Impairment) {
+        self.hub.lock().unwrap().impairment = impairment;
+    }
+
+    pub fn stats(&self) -> NetworkStats {
+        self.hub.lock().unwrap().stats
+    }
+
+    /// Abre um socket em `addr`; a porta 0 escolhe uma porta livre.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<SimulatedSocket> {
+        let mut hub = self.hub.lock().unwrap();
+        let mut addr = addr;
+        if addr.port() == 0 {
+            loop {
+                addr.set_port(hub.next_port);
+                hub.next_port = hub.next_port.checked_add(1).unwrap_or(1024);
+                if !hub.sockets.contains_key(&addr) {
+                    break;
+                }
+            }
+        }
+        if hub.sockets.contains_key(&addr) {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("Endereço em uso: {0}", addr),
+            ));
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        hub.sockets.insert(addr, sender);
+        Ok(SimulatedSocket {
+            addr,
+            hub: self.hub.clone(),
+            incoming: tokio::sync::Mutex::new(receiver),
+        })
+    }
+
+    /// Socket numa porta livre de 127.0.0.1.
+    pub fn bind_any(&self) -> io::Result<SimulatedSocket> {
+        self.bind((Ipv4Addr::LOCALHOST, 0).into())
+    }
+}
+
+/// Socket da rede simulada. A porta é liberada quando ele é descartado.
+pub struct SimulatedSocket {
+    addr: SocketAddr,
+    hub: Arc<Mutex<Hub>>,
+    incoming: tokio::sync::Mutex<UnboundedReceiver<Datagram>>,
+}
+
+impl SimulatedSocket {
+    /// Aplica as condições da rede e agenda a entrega de cada cópia. Um
+    /// destino sem socket perde o datagrama em silêncio, como no UDP.
+    fn transmit(&self, datagram: &[u8], target: SocketAddr) {
+        let mut hub = self.hub.lock().unwrap();
+        let hub = &mut *hub;
+        hub.stats.sent += 1;
+        if hub.impairment.mtu.is_some_and(|mtu| datagram.len() > mtu) {
+            hub.stats.oversized += 1;
+            return;
+        }
+        if hub.rng.random_bool(hub.impairment.drop_rate) {
+            hub.stats.dropped += 1;
+            return;
+        }
+        let copies = if hub.rng.random_bool(hub.impairment.duplicate_rate) {
+            hub.stats.duplicated += 1;
+            2
+        } else {
+            1
+        };
+        let Some(receiver) = hub.sockets.get(&target).cloned() else {
+            return;
+        };
+        for _ in 0..copies {
+            let delay = hub.rng.random_range(Duration::ZERO..=hub.impairment.jitter);
+            let packet = (datagram.to_vec(), self.addr);
+            if delay.is_zero() {
+                let _ = receiver.send(packet);
+                continue;
+            }
+            let receiver = receiver.clone();
+            tokio::spawn(async move {
+                time::sleep(delay).await;
+                let _ = receiver.send(packet);
+            });
+        }
+    }
+}
+
+impl Drop for SimulatedSocket {
+    fn drop(&mut self) {
+        if let Ok(mut hub) = self.hub.lock() {
+            hub.sockets.remove(&self.addr);
+        }
+    }
+}
+
+impl DatagramTransport for SimulatedSocket {
+    fn send_to<'a>(
+        &'a self,
+        datagram: &'a [u8],
+        target: SocketAddr,
+    ) -> BoxFuture<'a, io::Result<usize>> {
+        Box::pin(async move {
+            self.transmit(datagram, target);
+            Ok(datagram.len())
+        })
+    }
+
+    fn recv_from<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> BoxFuture<'a, io::Result<(usize, SocketAddr)>> {
+        Box::pin(async move {
+            let (datagram, source) = self
+                .incoming
+                .lock()
+                .await
+                .recv()
+                .await
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+            let len = datagram.len().min(buf.len());
+            buf[..len].copy_from_slice(&datagram[..len]);
+            Ok((len, source))
+        })
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+}
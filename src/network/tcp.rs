@@ -1,29 +1,28 @@
-use tokio::io::AsyncReadExt;
-use tokio::net::TcpStream;
+use tokio::io::{self, AsyncRead, AsyncWrite};
+use tokio_util::codec::{FramedRead, FramedWrite};
 
-use crate::config::METADATA_BYTES;
-use crate::models::message::Message;
-use crate::models::metadata::MsgMetadata;
+use crate::network::codec::MessageCodec;
 
-pub async fn receive(stream: &mut TcpStream) -> Result<Message, String> {
-    let mut metadata_buffer = vec![0u8; METADATA_BYTES];
-    if stream.read_exact(&mut metadata_buffer).await.is_ok() {
-        let metadata = match MsgMetadata::deserialize(&metadata_buffer, false) {
-            Ok(meta) => meta,
-            Err(e) => {
-                return Err(format!("Metadata inválida: \n{0}", e));
-            }
-        };
+pub type TcpReader = Box<dyn AsyncRead + Send + Unpin>;
+pub type TcpWriter = Box<dyn AsyncWrite + Send + Unpin>;
+pub type MessageReader = FramedRead<TcpReader, MessageCodec>;
+pub type MessageWriter = FramedWrite<TcpWriter, MessageCodec>;
 
-        let message_length = metadata.message_length as usize;
-        let mut message_buffer = vec![0u8; message_length];
+/// Separa qualquer stream (TCP puro ou TLS) em metades de leitura e escrita.
+pub fn split<S: AsyncRead + AsyncWrite + Send + 'static>(stream: S) -> (TcpReader, TcpWriter) {
+    let (reader, writer) = io::split(stream);
+    (Box::new(reader), Box::new(writer))
+}
 
-        if stream.read_exact(&mut message_buffer).await.is_ok() {
-            Ok(Message::new(metadata, message_buffer))
-        } else {
-            Err("Mensagem corrompida".to_string())
-        }
-    } else {
-        Err("Metadata não encontrada".to_string())
-    }
+/// Separa o stream em metades que leem e escrevem mensagens, aceitando
+/// conteúdos de até `max_size` bytes.
+pub fn framed<S: AsyncRead + AsyncWrite + Send + 'static>(
+    stream: S,
+    max_size: u64,
+) -> (MessageReader, MessageWriter) {
+    let (reader, writer) = split(stream);
+    (
+        FramedRead::new(reader, MessageCodec::new(max_size)),
+        FramedWrite::new(writer, MessageCodec::new(max_size)),
+    )
 }
@@ -1,22 +1,24 @@
-use std::error::Error;
 use std::sync::Arc;
 
-use tokio::io::AsyncWriteExt;
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
-use tokio::stream;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
-use crate::config::{HOST_ADDRESS, TCP_PORT};
+use crate::config::{ClientConfig, MAX_MESSAGE_SIZE};
+use crate::models::credentials::Credentials;
 use crate::models::message::Message;
-use crate::network::client::Client;
-use crate::network::tcp;
-use crate::utilities::enums::MessageType;
+use crate::network::client::{expect_response, parse_command, Client, ClientResult};
+use crate::network::tcp::{self, MessageReader, MessageWriter};
+use crate::network::tls;
+use crate::utilities::enums::{MessageType, Protocol};
 
 pub struct TcpClient {
     pub name: String,
     pub id: u16,
     pub log: Arc<RwLock<String>>,
-    stream: Arc<RwLock<TcpStream>>,
+    reader: Mutex<MessageReader>,
+    writer: Mutex<MessageWriter>,
 }
 
 impl Client for TcpClient {
@@ -28,111 +30,112 @@ impl Client for TcpClient {
         self.name.clone()
     }
 
+    fn get_protocol(&self) -> Protocol {
+        Protocol::TCP
+    }
+
     async fn get_log(&self) -> String {
         self.log.read().await.clone()
     }
 
-    async fn set_name(&mut self, name: String) -> Result<u16, Box<dyn Error>> {
+    async fn set_name(&self, name: String) -> ClientResult<u16> {
         let key = Message::generate_key();
-        let message = Message::new_set_name_request(key, name.clone(), None, None);
-        let message_bytes = message.serialize().await;
-        self.send(message_bytes).await?;
+        let message = Message::new_set_name_request(key, name, None, None);
+        self.send(message).await?;
         Ok(key)
     }
 
-    async fn send_connection_request(&mut self, name: String) -> Result<(), Box<dyn Error>> {
+    async fn send_connection_request(&self, credentials: Credentials) -> ClientResult<u16> {
         let key = Message::generate_key();
-        let message = Message::new_connection_request(key, name);
-        self.send(message.serialize().await).await?;
-        Ok(())
+        let message = Message::new_connection_request(key, &credentials);
+        self.send(message).await?;
+        Ok(key)
     }
 
-    async fn send_text(
-        &mut self,
-        content: String,
-        destination_id: u16,
-    ) -> Result<u16, Box<dyn Error>> {
+    async fn send_text(&self, content: String, destination_id: u16) -> ClientResult<u16> {
         let key = Message::generate_key();
         let message = Message::new_text(key, destination_id, content, None, None);
-        self.send(message.serialize().await).await?;
+        self.send(message).await?;
         Ok(key)
     }
 
-    async fn send(&mut self, message_bytes: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        let mut stream = self.stream.write().await;
-        stream.write_all(&message_bytes).await?;
-        Ok(())
+    async fn list_clients(&self) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        self.send(Message::new_list_clients_request(key)).await?;
+        Ok(key)
     }
 
-    async fn listen(&mut self) -> Result<Message, Box<dyn Error>> {
-        let mut stream = self.stream.write().await;
-        let message = tcp::receive(&mut stream).await?;
-        Ok(message)
+    async fn disconnect(&self) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        self.send(Message::new_disconnect_request(key)).await?;
+        Ok(key)
     }
 
-    fn create_command(&self, input: String) -> (MessageType, u16, String) {
-        let input = input.trim();
-        let mut command = input[0..3].to_string();
-        let mut has_dest = false;
-        let message_type = match command.as_str() {
-            "msg" => {
-                command = input[4..].trim().to_string();
-                has_dest = true;
-                MessageType::Text
+    async fn send(&self, message: Message) -> ClientResult<()> {
+        self.writer.lock().await.send(message.frame()).await?;
+        Ok(())
+    }
+
+    async fn listen(&self) -> ClientResult<Message> {
+        let mut reader = self.reader.lock().await;
+        loop {
+            match reader.next().await {
+                Some(Ok(Ok(message))) => return Ok(message),
+                Some(Ok(Err(rejected))) => {
+                    tracing::warn!(error = %rejected, "Mensagem TCP descartada")
+                }
+                Some(Err(e)) => return Err(e.into()),
+                None => return Err("Conexão encerrada".into()),
             }
-            _ => MessageType::Error,
-        };
-        let mut dest_index = 0;
-        let mut destination_id = 0;
-        if !has_dest {
-            dest_index = command.find(' ').unwrap();
-            destination_id = command[0..dest_index].parse::<u16>().unwrap();
-            dest_index += 1;
         }
-        let content = command[dest_index..].to_string();
-        (message_type, destination_id, content)
+    }
+
+    fn create_command(&self, input: String) -> (MessageType, u16, String) {
+        parse_command(&input)
     }
 }
 
 impl TcpClient {
-    pub async fn new(name: String) -> Result<Self, Box<dyn Error>> {
-        let addr = format!("{}:{}", HOST_ADDRESS, TCP_PORT);
-        let stream = TcpStream::connect(addr).await?;
-        println!("Conexão encontranda!");
-        let log = Arc::new(RwLock::new(String::new()));
-        let stream = Arc::new(RwLock::new(stream));
-        let connection_request =
-            Message::new_connection_request(Message::generate_key(), name.clone());
-        let message_bytes = connection_request.serialize().await;
-        let stream_clone = stream.clone();
-        {
-            println!("Conectando...");
-            let mut stream_write = stream_clone.write().await;
-            stream_write.write_all(&message_bytes).await?;
+    pub async fn new(config: &ClientConfig) -> ClientResult<Self> {
+        let stream = TcpStream::connect(config.tcp_address()).await?;
+        stream.set_nodelay(true)?;
+        if !config.tls {
+            return Self::connect(stream, config.credentials()).await;
         }
-        let mut stream_write = stream_clone.write().await;
-        let msg = loop {
-            println!("Aguardando resposta do servidor...");
-            let msg = match tcp::receive(&mut stream_write).await {
-                Ok(msg) => msg,
-                Err(_) => {
-                    continue;
-                }
-            };
-            if msg.metadata.message_type == MessageType::Connection {
-                break msg;
+        let connector = tls::connector(config.tls_ca.as_deref())?;
+        let server_name =
+            tls::server_name(config.tls_server_name.as_ref().unwrap_or(&config.host))?;
+        let stream = connector.connect(server_name, stream).await?;
+        Self::connect(stream, config.credentials()).await
+    }
+
+    /// Registra o client sobre uma conexão já estabelecida (TCP puro ou TLS).
+    pub async fn connect<S: AsyncRead + AsyncWrite + Send + 'static>(
+        stream: S,
+        credentials: Credentials,
+    ) -> ClientResult<Self> {
+        let (reader, writer) = tcp::framed(stream, MAX_MESSAGE_SIZE);
+        let mut client = TcpClient {
+            name: credentials.name.clone(),
+            id: 0,
+            log: Arc::new(RwLock::new(String::new())),
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+        };
+        client.log.write().await.push_str("\nConectando...");
+        let key = client.send_connection_request(credentials).await?;
+        let id = loop {
+            let message = client.listen().await?;
+            if let Some(response) = expect_response(&message, key) {
+                break response?;
             }
         };
-        if msg.metadata.message_type != MessageType::Connection {
-            return Err("Erro ao conectar".into());
-        }
-        let id = msg.metadata.receiver_id;
-        println!("Conectado com sucesso!\nID: {}", 0);
-        Ok(TcpClient {
-            name,
-            log,
-            id: 0,
-            stream,
-        })
+        client.id = id;
+        client
+            .log
+            .write()
+            .await
+            .push_str(&format!("\nConectado com sucesso!\nID: {}", id));
+        Ok(client)
     }
 }
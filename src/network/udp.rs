@@ -1,49 +1,211 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use bytes::{Bytes, BytesMut};
+use tracing::warn;
+
+use crate::config::{
+    MAX_UDP_PACKETS, METADATA_BYTES, UDP_COMPLETED_WINDOW, UDP_METADATA_BYTES, UDP_PENDING_MESSAGES,
+};
 use crate::models::message::Message;
+use crate::models::metadata::MsgMetadata;
+use crate::network::codec::Rejected;
+
+/// Divide a mensagem em pacotes de até `packet_size` bytes. Recusa mensagens
+/// que precisariam de mais de `MAX_UDP_PACKETS` pacotes, que a sequência de 16
+/// bits não numera.
+pub fn fragment(
+    message: &Message,
+    udp_id: u16,
+    packet_size: usize,
+) -> Result<Vec<Vec<u8>>, String> {
+    let payload_size = packet_size - METADATA_BYTES - UDP_METADATA_BYTES;
+    let limit = payload_size as u64 * MAX_UDP_PACKETS;
+    if message.content.len() as u64 > limit {
+        return Err(format!(
+            "Mensagem de {0} bytes excede o limite de {1} bytes por UDP",
+            message.content.len(),
+            limit
+        ));
+    }
+    let mut chunks: Vec<&[u8]> = message.content.chunks(payload_size).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    let mut packets = Vec::with_capacity(chunks.len());
+    for (seq, chunk) in chunks.into_iter().enumerate() {
+        let metadata = MsgMetadata::new(
+            message.metadata.key,
+            message.metadata.receiver_id,
+            message.metadata.message_type,
+            message.content.len() as u64,
+            Some(udp_id),
+            Some(seq as u16),
+        );
+        let mut packet = Vec::with_capacity(metadata.size() + chunk.len());
+        metadata.write_to(&mut packet);
+        packet.extend_from_slice(chunk);
+        packets.push(packet);
+    }
+    Ok(packets)
+}
+
+/// Estado da remontagem das mensagens recebidas de um endereço: as mensagens
+/// incompletas, por `udp_id`, e os `udp_id` das últimas mensagens concluídas,
+/// para que cópias atrasadas de um pacote não entreguem a mesma mensagem de novo.
+#[derive(Debug, Default)]
+pub struct Reassembly {
+    pending: HashMap<u16, Pending>,
+    /// `udp_id` das mensagens incompletas, da mais antiga para a mais nova.
+    arrival: VecDeque<u16>,
+    completed: HashSet<u16>,
+    completed_order: VecDeque<u16>,
+}
+
+/// Pacotes de uma mensagem incompleta, por sequência, e quantos bytes de
+/// conteúdo já chegaram.
+#[derive(Debug)]
+struct Pending {
+    metadata: MsgMetadata,
+    packets: BTreeMap<u16, Bytes>,
+    received: u64,
+}
+
+impl Reassembly {
+    /// Quantas mensagens estão incompletas.
+    pub fn pending_messages(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Bytes de conteúdo guardados das mensagens incompletas.
+    pub fn pending_bytes(&self) -> u64 {
+        self.pending.values().map(|pending| pending.received).sum()
+    }
 
-pub async fn build_udp_message(
+    /// Descarta as mensagens incompletas. Pacotes que ainda chegarem delas são
+    /// ignorados como os de mensagens concluídas.
+    pub fn discard_pending(&mut self) {
+        while let Some(udp_id) = self.arrival.pop_front() {
+            self.complete(udp_id);
+        }
+    }
+
+    /// Marca a mensagem como concluída e retorna os pacotes dela.
+    fn complete(&mut self, udp_id: u16) -> Option<Pending> {
+        let pending = self.pending.remove(&udp_id);
+        if pending.is_some() {
+            self.arrival.retain(|id| *id != udp_id);
+        }
+        if self.completed.insert(udp_id) {
+            self.completed_order.push_back(udp_id);
+            if self.completed_order.len() > UDP_COMPLETED_WINDOW {
+                if let Some(oldest) = self.completed_order.pop_front() {
+                    self.completed.remove(&oldest);
+                }
+            }
+        }
+        pending
+    }
+
+    /// Com mais de `UDP_PENDING_MESSAGES` mensagens incompletas, descarta a
+    /// mais antiga; um pacote perdido não a completaria nunca.
+    fn evict(&mut self) {
+        if self.pending.len() <= UDP_PENDING_MESSAGES {
+            return;
+        }
+        if let Some(oldest) = self.arrival.pop_front() {
+            self.pending.remove(&oldest);
+            warn!(udp_id = oldest, "Mensagem UDP incompleta descartada");
+        }
+    }
+}
+
+/// Junta o pacote aos já recebidos e retorna a mensagem quando ela está
+/// completa. Uma mensagem acima de `max_size` bytes é recusada no primeiro
+/// pacote e os demais pacotes dela são descartados.
+pub fn build_udp_message(
     bytes: Vec<u8>,
-    owned_ids: Vec<u16>,
-    current_packets: &mut Vec<Message>,
-) -> Option<Message> {
-    let message = Message::new_udp_packet(bytes);
-    if let Err(e) = message {
-        eprintln!("Erro processando pacote UDP:\n{:?}", e);
-        return None;
-    }
-    let message = message.unwrap();
-    if owned_ids.contains(&message.metadata.udp_id.unwrap()) {
-        eprintln!("Mensagem não pertence ao remetente. Ignorando...");
-        return None;
-    }
-    if current_packets
-        .iter()
-        .any(|x| x.metadata.timestamp == message.metadata.timestamp)
+    reassembly: &mut Reassembly,
+    max_size: u64,
+) -> Result<Option<Message>, Rejected> {
+    let message = match Message::new_udp_packet(bytes) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!(error = %e, "Erro processando pacote UDP");
+            return Ok(None);
+        }
+    };
+    let (Some(udp_id), Some(udp_seq)) = (message.metadata.udp_id, message.metadata.udp_seq) else {
+        return Ok(None);
+    };
+    if reassembly.completed.contains(&udp_id) {
+        return Ok(None);
+    }
+    if message.metadata.message_length > max_size {
+        if udp_seq != 0 {
+            return Ok(None);
+        }
+        reassembly.complete(udp_id);
+        return Err(Rejected::too_large(
+            message.metadata.key,
+            message.metadata.message_length,
+            max_size,
+        ));
+    }
+    let message_length = message.metadata.message_length;
+    let pending = match reassembly.pending.entry(udp_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            reassembly.arrival.push_back(udp_id);
+            entry.insert(Pending {
+                metadata: message.metadata.clone(),
+                packets: BTreeMap::new(),
+                received: 0,
+            })
+        }
+    };
+    // Pacotes repetidos, ou cujo cabeçalho não combina com o dos já recebidos
+    // da mesma mensagem, são descartados.
+    if pending.packets.contains_key(&udp_seq)
+        || pending.metadata.message_type != message.metadata.message_type
+        || pending.metadata.message_length != message_length
     {
-        eprintln!("Pacote UDP já recebido. Ignorando...");
-        return None;
-    }
-    current_packets.push(message.clone());
-    let is_complete = message.is_complete();
-    if !is_complete {
-        eprint!("Pacote UDP incompleto. Aguardando mais pacotes...");
-        return None;
-    }
-    println!("Pacote UDP completo!");
-    let message = rebuild_message(current_packets, message.metadata.udp_id.unwrap());
-    if let Some(message) = message {
-        return Some(message);
-    }
-    println!("Erro ao reconstruir mensagem.");
-    return None;
+        return Ok(None);
+    }
+    pending.received += message.content.len() as u64;
+    pending.packets.insert(udp_seq, message.content);
+    if pending.received < message_length {
+        reassembly.evict();
+        return Ok(None);
+    }
+    let Some(pending) = reassembly.complete(udp_id) else {
+        return Ok(None);
+    };
+    if pending.received > message_length {
+        warn!(
+            udp_id,
+            "Pacotes UDP excedem o tamanho da mensagem. Descartando..."
+        );
+        return Ok(None);
+    }
+    Ok(Some(rebuild_message(pending)))
 }
 
-fn rebuild_message(current_packets: &mut Vec<Message>, udp_id: u16) -> Option<Message> {
-    current_packets.sort_by(|a, b| a.metadata.timestamp.cmp(&b.metadata.timestamp));
-    let metadata = current_packets[0].metadata.clone();
-    let mut content = Vec::new();
-    for packet in current_packets {
-        content.extend(&packet.content);
+fn rebuild_message(pending: Pending) -> Message {
+    let mut metadata = pending.metadata;
+    metadata.udp_id = None;
+    metadata.udp_seq = None;
+    // Mensagens de um único pacote mantêm o buffer do datagrama.
+    if pending.packets.len() == 1 {
+        let content = pending.packets.into_values().next().unwrap_or_default();
+        return Message { metadata, content };
+    }
+    let mut content = BytesMut::with_capacity(metadata.message_length as usize);
+    for packet in pending.packets.into_values() {
+        content.extend_from_slice(&packet);
+    }
+    Message {
+        metadata,
+        content: content.freeze(),
     }
-    let message = Message { metadata, content };
-    Some(message)
 }
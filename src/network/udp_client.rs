@@ -0,0 +1,205 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::{self, Instant};
+
+use crate::config::{
+    ClientConfig, MAX_DATAGRAM_SIZE, MAX_MESSAGE_SIZE, UDP_CONNECT_RETRY_MS, UDP_CONNECT_TIMEOUT_MS,
+};
+use crate::models::credentials::Credentials;
+use crate::models::message::Message;
+use crate::network::client::{expect_response, parse_command, Client, ClientResult};
+use crate::network::datagram::DatagramTransport;
+use crate::network::udp::{self, Reassembly};
+use crate::network::udp_crypto::{UdpSession, SEAL_OVERHEAD};
+use crate::utilities::enums::{MessageType, Protocol};
+
+pub struct UdpClient {
+    pub name: String,
+    pub id: u16,
+    pub log: Arc<RwLock<String>>,
+    socket: Box<dyn DatagramTransport>,
+    server_addr: SocketAddr,
+    packet_size: usize,
+    udp_ids: AtomicU16,
+    packets: Mutex<Reassembly>,
+    session: Option<UdpSession>,
+}
+
+impl Client for UdpClient {
+    fn get_id(&self) -> u16 {
+        self.id
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_protocol(&self) -> Protocol {
+        Protocol::UDP
+    }
+
+    async fn get_log(&self) -> String {
+        self.log.read().await.clone()
+    }
+
+    async fn set_name(&self, name: String) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        let message = Message::new_set_name_request(key, name, None, None);
+        self.send(message).await?;
+        Ok(key)
+    }
+
+    async fn send_connection_request(&self, credentials: Credentials) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        let message = Message::new_connection_request(key, &credentials);
+        self.send(message).await?;
+        Ok(key)
+    }
+
+    async fn send_text(&self, content: String, destination_id: u16) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        let message = Message::new_text(key, destination_id, content, None, None);
+        self.send(message).await?;
+        Ok(key)
+    }
+
+    async fn list_clients(&self) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        self.send(Message::new_list_clients_request(key)).await?;
+        Ok(key)
+    }
+
+    async fn disconnect(&self) -> ClientResult<u16> {
+        let key = Message::generate_key();
+        self.send(Message::new_disconnect_request(key)).await?;
+        Ok(key)
+    }
+
+    async fn send(&self, message: Message) -> ClientResult<()> {
+        let udp_id = self.udp_ids.fetch_add(1, Ordering::Relaxed);
+        let packets = udp::fragment(&message, udp_id, self.packet_size)?;
+        for packet in packets {
+            match &self.session {
+                Some(session) => {
+                    self.socket
+                        .send_to(&session.seal(&packet), self.server_addr)
+                        .await?
+                }
+                None => self.socket.send_to(&packet, self.server_addr).await?,
+            };
+        }
+        Ok(())
+    }
+
+    async fn listen(&self) -> ClientResult<Message> {
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        loop {
+            let (len, source) = self.socket.recv_from(&mut buf).await?;
+            if source != self.server_addr {
+                continue;
+            }
+            let packet = match &self.session {
+                Some(session) => match session.open(&buf[..len]) {
+                    Some(packet) => packet,
+                    None => {
+                        tracing::debug!("Datagrama UDP descartado: falha de autenticação");
+                        continue;
+                    }
+                },
+                None => buf[..len].to_vec(),
+            };
+            let mut packets = self.packets.lock().await;
+            match udp::build_udp_message(packet, &mut packets, MAX_MESSAGE_SIZE) {
+                Ok(Some(message)) => return Ok(message),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(error = %e, "Mensagem UDP descartada"),
+            }
+        }
+    }
+
+    fn create_command(&self, input: String) -> (MessageType, u16, String) {
+        parse_command(&input)
+    }
+}
+
+impl UdpClient {
+    pub async fn new(config: &ClientConfig) -> ClientResult<Self> {
+        let server_addr = lookup_host(config.udp_address())
+            .await?
+            .next()
+            .ok_or("Endereço do servidor não encontrado")?;
+        let bind_addr: SocketAddr = if server_addr.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, config.udp_bind_port).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, config.udp_bind_port).into()
+        };
+        let socket = UdpSocket::bind(bind_addr).await?;
+        Self::connect(Box::new(socket), server_addr, config).await
+    }
+
+    /// Registra o client no servidor em `server_addr` sobre um transporte já
+    /// aberto. Datagramas de outras origens são ignorados. O pedido de conexão
+    /// é reenviado, com a mesma key, enquanto não houver resposta, e desiste
+    /// depois de `UDP_CONNECT_TIMEOUT_MS`.
+    pub async fn connect(
+        socket: Box<dyn DatagramTransport>,
+        server_addr: SocketAddr,
+        config: &ClientConfig,
+    ) -> ClientResult<Self> {
+        let name = config.name.clone();
+        let session = config.udp_psk.as_deref().map(UdpSession::client);
+        let packet_size = match session {
+            Some(_) => config.buffer_size - SEAL_OVERHEAD,
+            None => config.buffer_size,
+        };
+        let mut client = UdpClient {
+            name: name.clone(),
+            id: 0,
+            log: Arc::new(RwLock::new(String::new())),
+            socket,
+            server_addr,
+            packet_size,
+            udp_ids: AtomicU16::new(rand::random()),
+            packets: Mutex::new(Reassembly::default()),
+            session,
+        };
+        client.log.write().await.push_str("\nConectando...");
+        let key = Message::generate_key();
+        let request = Message::new_connection_request(key, &config.credentials());
+        let deadline = Instant::now() + Duration::from_millis(UDP_CONNECT_TIMEOUT_MS);
+        let mut retry = Duration::from_millis(UDP_CONNECT_RETRY_MS);
+        let id = loop {
+            client.send(request.clone()).await?;
+            let wait = retry.min(deadline.saturating_duration_since(Instant::now()));
+            if let Ok(response) = time::timeout(wait, client.connection_response(key)).await {
+                break response?;
+            }
+            if Instant::now() >= deadline {
+                return Err("Tempo esgotado aguardando a resposta do servidor".into());
+            }
+            retry *= 2;
+        };
+        client.id = id;
+        client
+            .log
+            .write()
+            .await
+            .push_str(&format!("\nConectado com sucesso!\nID: {}", id));
+        Ok(client)
+    }
+
+    /// Resposta ao `Connection` de chave `key`: o id atribuído ou o erro.
+    async fn connection_response(&self, key: u16) -> ClientResult<u16> {
+        loop {
+            let message = self.listen().await?;
+            if let Some(response) = expect_response(&message, key) {
+                return response;
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+use chrono::Utc;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+pub const SESSION_ID_BYTES: usize = 16;
+const COUNTER_BYTES: usize = 8;
+const HEADER_BYTES: usize = SESSION_ID_BYTES + COUNTER_BYTES;
+const TAG_BYTES: usize = 16;
+/// Bytes acrescentados a cada datagrama: id da sessão, contador e tag.
+pub const SEAL_OVERHEAD: usize = HEADER_BYTES + TAG_BYTES;
+const REPLAY_WINDOW: u64 = 64;
+const ISSUED_AT_BYTES: usize = 8;
+/// Tempo, em milissegundos, em que um id de sessão novo é aceito pelo servidor,
+/// para cada lado do relógio dele.
+pub const SESSION_MAX_AGE: u64 = 30_000;
+
+const CLIENT_TO_SERVER: &[u8] = b"t1-lab-redes udp client->server";
+const SERVER_TO_CLIENT: &[u8] = b"t1-lab-redes udp server->client";
+
+pub type SessionId = [u8; SESSION_ID_BYTES];
+
+/// Sessão UDP autenticada com ChaCha20-Poly1305. As chaves de cada sentido são
+/// derivadas (HKDF-SHA256) da chave pré-compartilhada e de um id de sessão
+/// escolhido pelo client, que viaja em claro no início do datagrama. O id
+/// começa com o instante de criação (milissegundos LE) e termina com 8 bytes
+/// aleatórios; o servidor usa esse instante para recusar sessões antigas.
+///
+/// Formato: `id da sessão (16) | contador LE (8) | texto cifrado | tag (16)`.
+/// O cabeçalho é autenticado como dado associado e o contador forma o nonce.
+pub struct UdpSession {
+    id: SessionId,
+    sealer: ChaCha20Poly1305,
+    opener: ChaCha20Poly1305,
+    counter: AtomicU64,
+    replay: Mutex<ReplayWindow>,
+}
+
+impl UdpSession {
+    pub fn client(psk: &str) -> Self {
+        let mut id: SessionId = rand::random();
+        id[..ISSUED_AT_BYTES].copy_from_slice(&Utc::now().timestamp_millis().to_le_bytes());
+        Self::new(psk, id, CLIENT_TO_SERVER, SERVER_TO_CLIENT)
+    }
+
+    pub fn server(psk: &str, id: SessionId) -> Self {
+        Self::new(psk, id, SERVER_TO_CLIENT, CLIENT_TO_SERVER)
+    }
+
+    fn new(psk: &str, id: SessionId, seal_info: &[u8], open_info: &[u8]) -> Self {
+        let hkdf = Hkdf::<Sha256>::new(Some(&id), psk.as_bytes());
+        let derive = |info: &[u8]| {
+            let mut key = [0u8; 32];
+            hkdf.expand(info, &mut key).unwrap();
+            ChaCha20Poly1305::new(&key.into())
+        };
+        UdpSession {
+            id,
+            sealer: derive(seal_info),
+            opener: derive(open_info),
+            counter: AtomicU64::new(0),
+            replay: Mutex::new(ReplayWindow::default()),
+        }
+    }
+
+    pub fn id(&self) -> SessionId {
+        self.id
+    }
+
+    /// Lê o id da sessão de um datagrama cifrado, sem autenticá-lo.
+    pub fn peek_id(datagram: &[u8]) -> Option<SessionId> {
+        if datagram.len() < SEAL_OVERHEAD {
+            return None;
+        }
+        datagram[..SESSION_ID_BYTES].try_into().ok()
+    }
+
+    pub fn seal(&self, packet: &[u8]) -> Vec<u8> {
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let mut datagram = Vec::with_capacity(packet.len() + SEAL_OVERHEAD);
+        datagram.extend_from_slice(&self.id);
+        datagram.extend_from_slice(&counter.to_le_bytes());
+        let ciphertext = self
+            .sealer
+            .encrypt(
+                &nonce(counter),
+                Payload {
+                    msg: packet,
+                    aad: &datagram,
+                },
+            )
+            .unwrap();
+        datagram.extend(ciphertext);
+        datagram
+    }
+
+    /// Autentica e decifra um datagrama. Retorna `None` se ele pertencer a outra
+    /// sessão, tiver sido alterado ou já tiver sido recebido.
+    pub fn open(&self, datagram: &[u8]) -> Option<Vec<u8>> {
+        if Self::peek_id(datagram)? != self.id {
+            return None;
+        }
+        let (header, ciphertext) = datagram.split_at(HEADER_BYTES);
+        let counter = u64::from_le_bytes(header[SESSION_ID_BYTES..].try_into().ok()?);
+        let mut replay = self.replay.lock().unwrap();
+        if !replay.is_fresh(counter) {
+            return None;
+        }
+        let packet = self
+            .opener
+            .decrypt(
+                &nonce(counter),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .ok()?;
+        replay.accept(counter);
+        Some(packet)
+    }
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_le_bytes());
+    nonce.into()
+}
+
+/// Janela deslizante de contadores já recebidos, para descartar repetições sem
+/// exigir que os datagramas cheguem em ordem.
+#[derive(Default)]
+struct ReplayWindow {
+    highest: Option<u64>,
+    seen: u64,
+}
+
+impl ReplayWindow {
+    fn is_fresh(&self, counter: u64) -> bool {
+        match self.highest {
+            None => true,
+            Some(highest) if counter > highest => true,
+            Some(highest) => {
+                let offset = highest - counter;
+                offset < REPLAY_WINDOW && self.seen & (1 << offset) == 0
+            }
+        }
+    }
+
+    fn accept(&mut self, counter: u64) {
+        match self.highest {
+            Some(highest) if counter <= highest => self.seen |= 1 << (highest - counter),
+            Some(highest) => {
+                let shift = counter - highest;
+                self.seen = if shift < REPLAY_WINDOW {
+                    (self.seen << shift) | 1
+                } else {
+                    1
+                };
+                self.highest = Some(counter);
+            }
+            None => {
+                self.seen = 1;
+                self.highest = Some(counter);
+            }
+        }
+    }
+}
+
+/// Ids de sessão já aceitos pelo servidor. Um id só abre uma sessão uma vez e
+/// só enquanto é recente, então repetir o primeiro datagrama de uma sessão
+/// capturada, de qualquer endereço, não cria uma sessão nova nem derruba a do
+/// client. Ids mais antigos que `SESSION_MAX_AGE` são esquecidos, porque o
+/// instante deles já basta para recusá-los.
+#[derive(Default)]
+pub struct UsedSessions {
+    ids: HashSet<SessionId>,
+}
+
+impl UsedSessions {
+    /// Registra `id` se ele for recente e ainda não tiver sido usado.
+    pub fn admit(&mut self, id: SessionId, now: i64) -> bool {
+        let fresh = |id: &SessionId| now.abs_diff(issued_at(id)) <= SESSION_MAX_AGE;
+        self.ids.retain(fresh);
+        fresh(&id) && self.ids.insert(id)
+    }
+}
+
+fn issued_at(id: &SessionId) -> i64 {
+    i64::from_le_bytes(id[..ISSUED_AT_BYTES].try_into().unwrap())
+}
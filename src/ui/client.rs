@@ -0,0 +1,1101 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use tokio::sync::mpsc;
+use tokio::time;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, Paragraph, Tabs};
+use tui::{Frame, Terminal};
+
+use crate::models::admin::{AdminCommand, ServerStats};
+use crate::models::history::{HistoryEntry, HistoryRequest};
+use crate::models::message::Message;
+use crate::models::room::{RoomAction, RoomInfo, RoomJoin};
+use crate::network::client::{
+    parse_admin_command, parse_command, parse_room_action, Client, ClientResult,
+};
+use crate::network::e2e::{E2eKeys, PUBLIC_KEY_BYTES};
+use crate::ui::spawn_key_reader;
+use crate::ui::terminal::panicked;
+use crate::utilities::enums::{MessageType, Protocol};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    Disconnected(String),
+}
+
+#[derive(Debug, Clone)]
+enum PendingRequest {
+    ListClients,
+    SetName(String),
+    Text(u16),
+    Disconnect,
+    PublishKey,
+    FetchKey(u16),
+    History(u16),
+    RoomHistory(u16),
+    Room,
+    LeaveRoom,
+    ListRooms,
+    RoomMessage(u16),
+    ModerateRoom(u16),
+    Admin,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClientRequest {
+    Text(u16, String),
+    Encrypted(u16, String, Vec<u8>),
+    SetName(String),
+    ListClients,
+    Disconnect,
+    PublishKey([u8; PUBLIC_KEY_BYTES]),
+    FetchKey(u16),
+    History(u16, Option<String>),
+    RoomHistory(u16, Option<String>),
+    CreateRoom(String),
+    JoinRoom(RoomJoin),
+    LeaveRoom(u16, String),
+    ListRooms,
+    RoomMessage(u16, String),
+    ModerateRoom(u16, RoomAction),
+    Admin(AdminCommand),
+}
+
+#[derive(Debug, Clone)]
+struct Conversation {
+    peer: Option<u16>,
+    room: Option<u16>,
+    lines: Vec<String>,
+    unread: bool,
+}
+
+impl Conversation {
+    fn new(peer: Option<u16>) -> Self {
+        Conversation {
+            peer,
+            room: None,
+            lines: Vec::new(),
+            unread: false,
+        }
+    }
+
+    fn for_room(room: u16) -> Self {
+        Conversation {
+            room: Some(room),
+            ..Conversation::new(None)
+        }
+    }
+}
+
+pub struct ClientApp {
+    id: u16,
+    name: String,
+    protocol: Protocol,
+    state: ConnectionState,
+    conversations: Vec<Conversation>,
+    active: usize,
+    users: BTreeMap<u16, String>,
+    rooms: BTreeMap<u16, String>,
+    input: String,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    pending: HashMap<u16, PendingRequest>,
+    e2e: Option<E2eKeys>,
+    peer_keys: HashMap<u16, [u8; PUBLIC_KEY_BYTES]>,
+    queued: HashMap<u16, Vec<String>>,
+    quit: bool,
+}
+
+impl ClientApp {
+    pub fn new(id: u16, name: String, protocol: Protocol, e2e: Option<E2eKeys>) -> Self {
+        let mut server_tab = Conversation::new(None);
+        server_tab
+            .lines
+            .push(format!("Conectado como \"{0}\" - ID {1}", name, id));
+        server_tab.lines.push(
+            "Comandos: /msg <id> <texto>, /abrir <id>, /nome <nome>, /lista, /sair".to_string(),
+        );
+        server_tab
+            .lines
+            .push("Histórico: /historico <id> [termo], /busca <termo>; na aba da sala, /historico [termo]".to_string());
+        server_tab.lines.push(
+            "Salas: /criar <sala>, /entrar <sala> [senha], /deixar [sala], /salas".to_string(),
+        );
+        server_tab.lines.push(
+            "Moderação (na aba da sala): /expulsar, /banir, /op, /deop, /silenciar, \
+             /dessilenciar, /convidar <id>; /desbanir <identidade>; /topico [texto]; \
+             /convite sim|nao; /senha [senha]"
+                .to_string(),
+        );
+        server_tab.lines.push(
+            "Administração: /admin expulsar <id>, /admin renomear <id> <nome>, \
+             /admin aviso <id|todos> <texto>, /admin stats, /admin recarregar"
+                .to_string(),
+        );
+        ClientApp {
+            id,
+            name,
+            protocol,
+            state: ConnectionState::Connected,
+            conversations: vec![server_tab],
+            active: 0,
+            users: BTreeMap::new(),
+            rooms: BTreeMap::new(),
+            input: String::new(),
+            history: Vec::new(),
+            history_index: None,
+            pending: HashMap::new(),
+            e2e,
+            peer_keys: HashMap::new(),
+            queued: HashMap::new(),
+            quit: false,
+        }
+    }
+
+    pub fn initial_requests(&self) -> Vec<ClientRequest> {
+        let mut requests = vec![ClientRequest::ListClients];
+        if let Some(keys) = &self.e2e {
+            requests.push(ClientRequest::PublishKey(keys.public_key()));
+        }
+        requests
+    }
+
+    /// Com E2E ativo, troca mensagens de texto por `Encrypted`. Sem a chave do
+    /// destinatário, guarda o texto e pede a chave ao servidor.
+    pub fn prepare(&mut self, request: ClientRequest) -> Option<ClientRequest> {
+        let ClientRequest::Text(peer, content) = request else {
+            return Some(request);
+        };
+        let Some(keys) = &self.e2e else {
+            return Some(ClientRequest::Text(peer, content));
+        };
+        let Some(peer_key) = self.peer_keys.get(&peer) else {
+            let queue = self.queued.entry(peer).or_default();
+            queue.push(content);
+            return (queue.len() == 1).then_some(ClientRequest::FetchKey(peer));
+        };
+        match keys.seal(peer_key, MessageType::Text, content.as_bytes()) {
+            Ok(envelope) => Some(ClientRequest::Encrypted(peer, content, envelope)),
+            Err(e) => {
+                self.push_system(&format!("[erro] {0}", e));
+                None
+            }
+        }
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ClientRequest> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit = true;
+                Some(ClientRequest::Disconnect)
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.active != 0 {
+                    self.conversations.remove(self.active);
+                    self.active -= 1;
+                }
+                None
+            }
+            KeyCode::Esc => {
+                self.quit = true;
+                Some(ClientRequest::Disconnect)
+            }
+            KeyCode::Tab => {
+                self.select_conversation((self.active + 1) % self.conversations.len());
+                None
+            }
+            KeyCode::BackTab => {
+                let len = self.conversations.len();
+                self.select_conversation((self.active + len - 1) % len);
+                None
+            }
+            KeyCode::Up => {
+                if self.history.is_empty() {
+                    return None;
+                }
+                let index = match self.history_index {
+                    Some(index) => index.saturating_sub(1),
+                    None => self.history.len() - 1,
+                };
+                self.history_index = Some(index);
+                self.input = self.history[index].clone();
+                None
+            }
+            KeyCode::Down => {
+                match self.history_index {
+                    Some(index) if index + 1 < self.history.len() => {
+                        self.history_index = Some(index + 1);
+                        self.input = self.history[index + 1].clone();
+                    }
+                    _ => {
+                        self.history_index = None;
+                        self.input.clear();
+                    }
+                }
+                None
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                None
+            }
+            KeyCode::Enter => self.submit(),
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn submit(&mut self) -> Option<ClientRequest> {
+        let line = self.input.trim().to_string();
+        self.input.clear();
+        self.history_index = None;
+        if line.is_empty() {
+            return None;
+        }
+        self.history.push(line.clone());
+
+        let conversation = &self.conversations[self.active];
+        let Some(command) = line.strip_prefix('/') else {
+            return match (conversation.peer, conversation.room) {
+                (Some(peer), _) => Some(ClientRequest::Text(peer, line)),
+                (None, Some(room)) => Some(ClientRequest::RoomMessage(room, line)),
+                (None, None) => {
+                    self.push_system("Abra uma conversa com /abrir <id> ou use /msg <id> <texto>");
+                    None
+                }
+            };
+        };
+        if let Some(peer) = command.strip_prefix("abrir ") {
+            match peer.trim().parse::<u16>() {
+                Ok(peer) => {
+                    let index = self.conversation_index(peer);
+                    self.select_conversation(index);
+                }
+                Err(_) => self.push_system(&format!("ID inválido: {0}", peer.trim())),
+            }
+            return None;
+        }
+        if let Some(admin) = command
+            .strip_prefix("admin")
+            .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+        {
+            match parse_admin_command(admin) {
+                Ok(command) => return Some(ClientRequest::Admin(command)),
+                Err(usage) => {
+                    self.push_system(&usage);
+                    return None;
+                }
+            }
+        }
+        if let Some(room) = conversation.room {
+            if command.trim() == "deixar" {
+                let name = self.rooms.get(&room).cloned().unwrap_or_default();
+                return Some(ClientRequest::LeaveRoom(room, name));
+            }
+            if let Some(search) = command
+                .strip_prefix("historico")
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+            {
+                let search = search.trim();
+                let search = (!search.is_empty()).then(|| search.to_string());
+                return Some(ClientRequest::RoomHistory(room, search));
+            }
+            match parse_room_action(command) {
+                Some(Ok(action)) => return Some(ClientRequest::ModerateRoom(room, action)),
+                Some(Err(usage)) => {
+                    self.conversations[self.active].lines.push(usage);
+                    return None;
+                }
+                None => {}
+            }
+        }
+        let (message_type, destination_id, content) = parse_command(command);
+        match message_type {
+            MessageType::Text => {
+                let index = self.conversation_index(destination_id);
+                self.select_conversation(index);
+                Some(ClientRequest::Text(destination_id, content))
+            }
+            MessageType::SetName => Some(ClientRequest::SetName(content)),
+            MessageType::ListClients => Some(ClientRequest::ListClients),
+            MessageType::History => {
+                if destination_id != 0 {
+                    let index = self.conversation_index(destination_id);
+                    self.select_conversation(index);
+                }
+                let search = (!content.is_empty()).then_some(content);
+                Some(ClientRequest::History(destination_id, search))
+            }
+            MessageType::CreateRoom => Some(ClientRequest::CreateRoom(content)),
+            MessageType::JoinRoom => {
+                let (name, password) = match content.split_once(' ') {
+                    Some((name, password)) => (name, Some(password.trim().to_string())),
+                    None => (content.as_str(), None),
+                };
+                Some(ClientRequest::JoinRoom(RoomJoin {
+                    name: name.to_string(),
+                    password,
+                }))
+            }
+            MessageType::LeaveRoom => Some(ClientRequest::LeaveRoom(0, content)),
+            MessageType::ListRooms => Some(ClientRequest::ListRooms),
+            MessageType::Disconnect => {
+                self.quit = true;
+                Some(ClientRequest::Disconnect)
+            }
+            _ => {
+                self.push_system(&content);
+                None
+            }
+        }
+    }
+
+    pub fn request_sent(&mut self, key: u16, request: &ClientRequest) {
+        let pending = match request {
+            ClientRequest::Text(peer, content) => {
+                let index = self.conversation_index(*peer);
+                self.conversations[index]
+                    .lines
+                    .push(format!("Você: {0}", content));
+                PendingRequest::Text(*peer)
+            }
+            ClientRequest::Encrypted(peer, content, _) => {
+                let index = self.conversation_index(*peer);
+                self.conversations[index]
+                    .lines
+                    .push(format!("Você (e2e): {0}", content));
+                PendingRequest::Text(*peer)
+            }
+            ClientRequest::SetName(name) => PendingRequest::SetName(name.clone()),
+            ClientRequest::ListClients => PendingRequest::ListClients,
+            ClientRequest::Disconnect => PendingRequest::Disconnect,
+            ClientRequest::PublishKey(_) => PendingRequest::PublishKey,
+            ClientRequest::FetchKey(peer) => PendingRequest::FetchKey(*peer),
+            ClientRequest::History(peer, _) => PendingRequest::History(*peer),
+            ClientRequest::RoomHistory(room, _) => PendingRequest::RoomHistory(*room),
+            ClientRequest::CreateRoom(_) | ClientRequest::JoinRoom(_) => PendingRequest::Room,
+            ClientRequest::LeaveRoom(..) => PendingRequest::LeaveRoom,
+            ClientRequest::ListRooms => PendingRequest::ListRooms,
+            ClientRequest::RoomMessage(room, content) => {
+                let index = self.room_index(*room);
+                self.conversations[index]
+                    .lines
+                    .push(format!("Você: {0}", content));
+                PendingRequest::RoomMessage(*room)
+            }
+            ClientRequest::ModerateRoom(room, _) => PendingRequest::ModerateRoom(*room),
+            ClientRequest::Admin(_) => PendingRequest::Admin,
+        };
+        self.pending.insert(key, pending);
+    }
+
+    pub fn request_failed(&mut self, error: String) {
+        self.push_system(&format!("Falha ao enviar: {0}", error));
+    }
+
+    pub fn handle_message(&mut self, message: Message) -> Vec<ClientRequest> {
+        let key = message.metadata.key;
+        let peer = message.metadata.receiver_id;
+        match message.metadata.message_type {
+            MessageType::Text if peer == 0 => {
+                let notice = String::from_utf8_lossy(&message.content).to_string();
+                self.push_system(&format!("[servidor] {0}", notice));
+            }
+            MessageType::Text | MessageType::File => {
+                let sender = self.display_name(peer);
+                let line = if message.metadata.message_type == MessageType::Text {
+                    format!(
+                        "{0}: {1}",
+                        sender,
+                        String::from_utf8_lossy(&message.content)
+                    )
+                } else {
+                    format!("{0}: [arquivo, {1} bytes]", sender, message.content.len())
+                };
+                let index = self.conversation_index(peer);
+                self.conversations[index].lines.push(line);
+                if index != self.active {
+                    self.conversations[index].unread = true;
+                }
+            }
+            MessageType::Broadcast => {
+                let sender = self.display_name(peer);
+                let text = String::from_utf8_lossy(&message.content);
+                self.push_system(&format!("[todos] {0}: {1}", sender, text));
+            }
+            MessageType::Encrypted => self.receive_encrypted(peer, &message.content),
+            MessageType::RoomMessage => self.receive_room_message(peer, &message.content),
+            MessageType::LeaveRoom => {
+                let reason = String::from_utf8_lossy(&message.content).to_string();
+                self.rooms.remove(&peer);
+                if let Some(index) = self.find_room(peer) {
+                    self.conversations[index]
+                        .lines
+                        .push(format!("[sala] {0}", reason));
+                }
+                self.push_system(&format!("[sala] {0}", reason));
+            }
+            MessageType::Delivered => {
+                let line = format!(
+                    "[entregue] mensagem guardada entregue a {0}",
+                    self.display_name(peer)
+                );
+                let index = self.conversation_index(peer);
+                self.conversations[index].lines.push(line);
+            }
+            MessageType::Disconnect if peer == 0 => {
+                self.connection_lost("Servidor encerrou a conexão".to_string());
+            }
+            MessageType::Presence => {
+                let Ok((client_id, name, online)) =
+                    serde_json::from_slice::<(u16, String, bool)>(&message.content)
+                else {
+                    return Vec::new();
+                };
+                if client_id == self.id && online {
+                    self.name = name.clone();
+                }
+                let line = if online {
+                    match self.users.insert(client_id, name.clone()) {
+                        Some(old) if old != name => format!("{0} agora se chama {1}", old, name),
+                        Some(_) => return Vec::new(),
+                        None => format!("{0} (ID {1}) entrou", name, client_id),
+                    }
+                } else {
+                    self.users.remove(&client_id);
+                    self.peer_keys.remove(&client_id);
+                    self.queued.remove(&client_id);
+                    format!("{0} (ID {1}) saiu", name, client_id)
+                };
+                if let Some(index) = self.find_conversation(client_id) {
+                    self.conversations[index].lines.push(line.clone());
+                }
+                self.push_system(&line);
+            }
+            MessageType::Success | MessageType::Error => {
+                let success = message.metadata.message_type == MessageType::Success;
+                let pending = self.pending.remove(&key);
+                if let Some(PendingRequest::FetchKey(peer)) = pending {
+                    return self.key_received(peer, success, &message.content);
+                }
+                let content = if success {
+                    String::from_utf8_lossy(&message.content).to_string()
+                } else {
+                    message.error_reason()
+                };
+                match pending {
+                    Some(PendingRequest::ListClients) if success => {
+                        if let Ok(clients) = serde_json::from_str::<Vec<(u16, String)>>(&content) {
+                            self.users = clients.into_iter().collect();
+                        }
+                    }
+                    Some(PendingRequest::SetName(name)) if success => {
+                        self.push_system(&format!("Nome alterado para \"{0}\"", name));
+                        self.users.insert(self.id, name.clone());
+                        self.name = name;
+                    }
+                    Some(PendingRequest::History(peer)) if success => {
+                        self.show_history(peer, &message.content)
+                    }
+                    Some(PendingRequest::RoomHistory(room)) if success => {
+                        self.show_room_history(room, &message.content)
+                    }
+                    Some(PendingRequest::Room) if success => self.room_joined(&message.content),
+                    Some(PendingRequest::LeaveRoom) if success => {
+                        let name = self.rooms.remove(&peer).unwrap_or_default();
+                        if let Some(index) = self.find_room(peer) {
+                            self.conversations[index]
+                                .lines
+                                .push("Você saiu da sala".to_string());
+                        }
+                        self.push_system(&format!("Você saiu da sala #{0}", name));
+                    }
+                    Some(PendingRequest::ListRooms) if success => self.show_rooms(&message.content),
+                    Some(PendingRequest::RoomMessage(_))
+                    | Some(PendingRequest::ModerateRoom(_))
+                        if success => {}
+                    Some(PendingRequest::RoomMessage(room))
+                    | Some(PendingRequest::RoomHistory(room))
+                    | Some(PendingRequest::ModerateRoom(room)) => {
+                        let index = self.room_index(room);
+                        self.conversations[index]
+                            .lines
+                            .push(format!("[erro] {0}", content));
+                    }
+                    Some(PendingRequest::Admin) if success => {
+                        match serde_json::from_slice::<ServerStats>(&message.content) {
+                            Ok(stats) => self.push_system(&stats.to_string()),
+                            Err(_) => self.push_system(&content),
+                        }
+                    }
+                    Some(PendingRequest::PublishKey) if success => self.push_system(
+                        "Chave pública publicada: mensagens diretas serão cifradas (e2e)",
+                    ),
+                    Some(PendingRequest::Text(peer)) if success && !content.is_empty() => {
+                        let index = self.conversation_index(peer);
+                        self.conversations[index]
+                            .lines
+                            .push(format!("[fila] {0}", content));
+                    }
+                    Some(PendingRequest::Text(_)) | Some(PendingRequest::Disconnect) if success => {
+                    }
+                    Some(PendingRequest::Text(peer)) => {
+                        let index = self.conversation_index(peer);
+                        self.conversations[index]
+                            .lines
+                            .push(format!("[erro] {0}", content));
+                    }
+                    _ if !success => self.push_system(&format!("[erro] {0}", content)),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn key_received(&mut self, peer: u16, success: bool, content: &[u8]) -> Vec<ClientRequest> {
+        let queued = self.queued.remove(&peer).unwrap_or_default();
+        let public_key = match <[u8; PUBLIC_KEY_BYTES]>::try_from(content) {
+            Ok(public_key) if success => public_key,
+            _ => {
+                let reason = if success {
+                    "Chave pública inválida".to_string()
+                } else {
+                    String::from_utf8_lossy(content).to_string()
+                };
+                let index = self.conversation_index(peer);
+                for text in queued {
+                    self.conversations[index]
+                        .lines
+                        .push(format!("[erro] {0} - não enviada: {1}", reason, text));
+                }
+                return Vec::new();
+            }
+        };
+        self.peer_keys.insert(peer, public_key);
+        queued
+            .into_iter()
+            .map(|text| ClientRequest::Text(peer, text))
+            .collect()
+    }
+
+    /// Mostra uma página do histórico na conversa com `peer`, ou na aba do
+    /// servidor para uma busca em todas as conversas (`peer` 0).
+    fn show_history(&mut self, peer: u16, content: &[u8]) {
+        let Some(lines) = self.history_lines(content, peer == 0) else {
+            return;
+        };
+        if peer == 0 {
+            for line in lines {
+                self.push_system(&line);
+            }
+        } else {
+            let index = self.conversation_index(peer);
+            self.conversations[index].lines.extend(lines);
+        }
+    }
+
+    fn show_room_history(&mut self, room: u16, content: &[u8]) {
+        if let Some(lines) = self.history_lines(content, false) {
+            let index = self.room_index(room);
+            self.conversations[index].lines.extend(lines);
+        }
+    }
+
+    /// Linhas de uma página do histórico; `recipients` inclui o destinatário de
+    /// cada mensagem.
+    fn history_lines(&mut self, content: &[u8], recipients: bool) -> Option<Vec<String>> {
+        let Ok(entries) = serde_json::from_slice::<Vec<HistoryEntry>>(content) else {
+            self.push_system("[erro] Resposta de histórico inválida");
+            return None;
+        };
+        let mut lines = Vec::with_capacity(entries.len() + 1);
+        lines.push(if entries.is_empty() {
+            "[histórico] nenhuma mensagem encontrada".to_string()
+        } else {
+            format!("[histórico] {0} mensagens", entries.len())
+        });
+        for entry in &entries {
+            let time = DateTime::from_timestamp_millis(entry.timestamp)
+                .map(|time| time.with_timezone(&Local).format("%d/%m %H:%M").to_string())
+                .unwrap_or_default();
+            let sender = if entry.from == self.id {
+                "Você".to_string()
+            } else {
+                self.display_name(entry.from)
+            };
+            let text = match &entry.content {
+                Some(text) => text.clone(),
+                None => match MessageType::try_from(entry.message_type) {
+                    Ok(MessageType::Encrypted) => format!("[cifrada, {0} bytes]", entry.bytes),
+                    _ => format!("[arquivo, {0} bytes]", entry.bytes),
+                },
+            };
+            lines.push(if recipients {
+                format!(
+                    "[{0}] {1} -> {2}: {3}",
+                    time,
+                    sender,
+                    self.display_name(entry.to),
+                    text
+                )
+            } else {
+                format!("[{0}] {1}: {2}", time, sender, text)
+            });
+        }
+        Some(lines)
+    }
+
+    fn receive_room_message(&mut self, room: u16, content: &[u8]) {
+        if content.len() < 2 {
+            return;
+        }
+        let sender = u16::from_le_bytes([content[0], content[1]]);
+        let text = String::from_utf8_lossy(&content[2..]);
+        let line = if sender == 0 {
+            format!("[sala] {0}", text)
+        } else {
+            format!("{0}: {1}", self.display_name(sender), text)
+        };
+        let index = self.room_index(room);
+        self.conversations[index].lines.push(line);
+        if index != self.active {
+            self.conversations[index].unread = true;
+        }
+    }
+
+    fn room_joined(&mut self, content: &[u8]) {
+        let Ok(room) = serde_json::from_slice::<RoomInfo>(content) else {
+            self.push_system("[erro] Resposta de sala inválida");
+            return;
+        };
+        self.rooms.insert(room.id, room.name.clone());
+        let index = self.room_index(room.id);
+        self.select_conversation(index);
+        let members: Vec<String> = room
+            .members
+            .iter()
+            .map(|member| self.display_name(*member))
+            .collect();
+        self.conversations[index].lines.push(format!(
+            "Você está na sala #{0} - membros: {1}",
+            room.name,
+            members.join(", ")
+        ));
+        if let Some(topic) = room.topic {
+            self.conversations[index]
+                .lines
+                .push(format!("Tópico: {0}", topic));
+        }
+    }
+
+    fn show_rooms(&mut self, content: &[u8]) {
+        let Ok(rooms) = serde_json::from_slice::<Vec<RoomInfo>>(content) else {
+            self.push_system("[erro] Resposta de salas inválida");
+            return;
+        };
+        if rooms.is_empty() {
+            self.push_system("Nenhuma sala aberta");
+            return;
+        }
+        let rooms: Vec<String> = rooms
+            .iter()
+            .map(|room| {
+                format!(
+                    "#{0} (ID {1}, {2} membros)",
+                    room.name,
+                    room.id,
+                    room.members.len()
+                )
+            })
+            .collect();
+        self.push_system(&format!("Salas: {0}", rooms.join(", ")));
+    }
+
+    fn receive_encrypted(&mut self, peer: u16, envelope: &[u8]) {
+        let sender = self.display_name(peer);
+        let line = match &self.e2e {
+            None => format!(
+                "{0}: [mensagem cifrada, {1} bytes - inicie com --e2e para ler]",
+                sender,
+                envelope.len()
+            ),
+            Some(keys) => match keys.open(envelope) {
+                Err(e) => format!("[erro] Mensagem de {0}: {1}", sender, e),
+                Ok(opened) => match self.peer_keys.get(&peer) {
+                    Some(known) if *known != opened.sender_key => format!(
+                        "[erro] Mensagem de {0} descartada: chave do remetente não confere",
+                        sender
+                    ),
+                    _ => {
+                        self.peer_keys.insert(peer, opened.sender_key);
+                        if opened.message_type == MessageType::Text {
+                            format!(
+                                "{0} (e2e): {1}",
+                                sender,
+                                String::from_utf8_lossy(&opened.content)
+                            )
+                        } else {
+                            format!(
+                                "{0} (e2e): [arquivo, {1} bytes]",
+                                sender,
+                                opened.content.len()
+                            )
+                        }
+                    }
+                },
+            },
+        };
+        let index = self.conversation_index(peer);
+        self.conversations[index].lines.push(line);
+        if index != self.active {
+            self.conversations[index].unread = true;
+        }
+    }
+
+    pub fn connection_lost(&mut self, reason: String) {
+        self.push_system(&format!("Conexão perdida: {0}", reason));
+        self.state = ConnectionState::Disconnected(reason);
+    }
+
+    fn push_system(&mut self, line: &str) {
+        self.conversations[0].lines.push(line.to_string());
+        if self.active != 0 {
+            self.conversations[0].unread = true;
+        }
+    }
+
+    fn display_name(&self, id: u16) -> String {
+        match self.users.get(&id) {
+            Some(name) => name.clone(),
+            None => format!("ID {0}", id),
+        }
+    }
+
+    fn find_conversation(&self, peer: u16) -> Option<usize> {
+        self.conversations
+            .iter()
+            .position(|conversation| conversation.peer == Some(peer))
+    }
+
+    fn conversation_index(&mut self, peer: u16) -> usize {
+        match self.find_conversation(peer) {
+            Some(index) => index,
+            None => {
+                self.conversations.push(Conversation::new(Some(peer)));
+                self.conversations.len() - 1
+            }
+        }
+    }
+
+    fn find_room(&self, room: u16) -> Option<usize> {
+        self.conversations
+            .iter()
+            .position(|conversation| conversation.room == Some(room))
+    }
+
+    fn room_index(&mut self, room: u16) -> usize {
+        match self.find_room(room) {
+            Some(index) => index,
+            None => {
+                self.conversations.push(Conversation::for_room(room));
+                self.conversations.len() - 1
+            }
+        }
+    }
+
+    fn room_name(&self, room: u16) -> String {
+        match self.rooms.get(&room) {
+            Some(name) => format!("#{0}", name),
+            None => format!("Sala {0}", room),
+        }
+    }
+
+    fn select_conversation(&mut self, index: usize) {
+        self.active = index;
+        self.conversations[index].unread = false;
+    }
+}
+
+pub async fn run_client_ui<B, C>(
+    terminal: &mut Terminal<B>,
+    client: Arc<C>,
+    e2e: Option<E2eKeys>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    B: Backend,
+    C: Client + Send + Sync + 'static,
+{
+    let mut app = ClientApp::new(
+        client.get_id(),
+        client.get_name(),
+        client.get_protocol(),
+        e2e,
+    );
+
+    let (message_tx, mut message_rx) = mpsc::unbounded_channel();
+    let listener = {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            loop {
+                match client.listen().await {
+                    Ok(message) => {
+                        if message_tx.send(Ok(message)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = message_tx.send(Err(e.to_string()));
+                        break;
+                    }
+                }
+            }
+        })
+    };
+
+    let mut key_rx = spawn_key_reader();
+
+    for request in app.initial_requests() {
+        send_request(client.as_ref(), &mut app, request).await;
+    }
+
+    let mut tick = time::interval(Duration::from_millis(250));
+    while !app.should_quit() {
+        if panicked() {
+            return Err("Interface encerrada após pânico".into());
+        }
+        terminal.draw(|f| render_client(f, &app))?;
+        tokio::select! {
+            Some(key) = key_rx.recv() => {
+                if let Some(request) = app.handle_key(key) {
+                    if app.state != ConnectionState::Connected {
+                        continue;
+                    }
+                    send_request(client.as_ref(), &mut app, request).await;
+                }
+            }
+            Some(incoming) = message_rx.recv() => {
+                match incoming {
+                    Ok(message) => {
+                        for request in app.handle_message(message) {
+                            send_request(client.as_ref(), &mut app, request).await;
+                        }
+                    }
+                    Err(e) => app.connection_lost(e),
+                }
+            }
+            _ = tick.tick() => {}
+        }
+    }
+    listener.abort();
+    Ok(())
+}
+
+async fn send_request<C: Client>(client: &C, app: &mut ClientApp, request: ClientRequest) {
+    let Some(request) = app.prepare(request) else {
+        return;
+    };
+    let result = match &request {
+        ClientRequest::Text(peer, content) => client.send_text(content.clone(), *peer).await,
+        ClientRequest::Encrypted(peer, _, envelope) => {
+            send_keyed(client, |key| {
+                Message::new_encrypted(key, *peer, envelope.clone())
+            })
+            .await
+        }
+        ClientRequest::SetName(name) => client.set_name(name.clone()).await,
+        ClientRequest::ListClients => client.list_clients().await,
+        ClientRequest::Disconnect => client.disconnect().await,
+        ClientRequest::PublishKey(public_key) => {
+            send_keyed(client, |key| {
+                Message::new_public_key(key, public_key.to_vec())
+            })
+            .await
+        }
+        ClientRequest::FetchKey(peer) => {
+            send_keyed(client, |key| Message::new_public_key_request(key, *peer)).await
+        }
+        ClientRequest::History(peer, search) => {
+            let request = HistoryRequest {
+                search: search.clone(),
+                ..HistoryRequest::default()
+            };
+            send_keyed(client, |key| {
+                Message::new_history_request(key, *peer, &request)
+            })
+            .await
+        }
+        ClientRequest::RoomHistory(room, search) => {
+            let request = HistoryRequest {
+                search: search.clone(),
+                room: Some(*room),
+                ..HistoryRequest::default()
+            };
+            send_keyed(client, |key| Message::new_history_request(key, 0, &request)).await
+        }
+        ClientRequest::CreateRoom(name) => {
+            send_keyed(client, |key| Message::new_create_room_request(key, name)).await
+        }
+        ClientRequest::JoinRoom(join) => {
+            send_keyed(client, |key| Message::new_join_room_request(key, 0, join)).await
+        }
+        ClientRequest::LeaveRoom(room, name) => {
+            send_keyed(client, |key| {
+                Message::new_leave_room_request(key, *room, name)
+            })
+            .await
+        }
+        ClientRequest::ListRooms => send_keyed(client, Message::new_list_rooms_request).await,
+        ClientRequest::ModerateRoom(room, action) => {
+            send_keyed(client, |key| {
+                Message::new_moderate_room_request(key, *room, action)
+            })
+            .await
+        }
+        ClientRequest::Admin(command) => {
+            send_keyed(client, |key| Message::new_admin_request(key, command)).await
+        }
+        ClientRequest::RoomMessage(room, content) => {
+            send_keyed(client, |key| {
+                Message::new_room_message(key, *room, content.clone())
+            })
+            .await
+        }
+    };
+    match result {
+        Ok(key) => app.request_sent(key, &request),
+        Err(e) => app.request_failed(e.to_string()),
+    }
+}
+
+async fn send_keyed<C: Client>(
+    client: &C,
+    build: impl FnOnce(u16) -> Message,
+) -> ClientResult<u16> {
+    let key = Message::generate_key();
+    client.send(build(key)).await?;
+    Ok(key)
+}
+
+fn render_client<B: Backend>(f: &mut Frame<B>, app: &ClientApp) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let titles: Vec<Spans> = app
+        .conversations
+        .iter()
+        .map(|conversation| {
+            let title = match (conversation.peer, conversation.room) {
+                (Some(peer), _) => app.display_name(peer),
+                (None, Some(room)) => app.room_name(room),
+                (None, None) => "Servidor".to_string(),
+            };
+            let title = if conversation.unread {
+                format!("{0} *", title)
+            } else {
+                title
+            };
+            Spans::from(title)
+        })
+        .collect();
+    let tabs = Tabs::new(titles)
+        .block(Block::default().title("Conversas").borders(Borders::ALL))
+        .select(app.active)
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(tabs, chunks[0]);
+
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
+        .split(chunks[1]);
+
+    let conversation = &app.conversations[app.active];
+    let height = body[0].height.saturating_sub(2) as usize;
+    let lines: Vec<&str> = conversation
+        .lines
+        .iter()
+        .flat_map(|line| line.lines())
+        .collect();
+    let start = lines.len().saturating_sub(height);
+    let items: Vec<ListItem> = lines[start..]
+        .iter()
+        .map(|line| ListItem::new(line.to_string()))
+        .collect();
+    let title = match (conversation.peer, conversation.room) {
+        (Some(peer), _) => format!("{0} - ID {1}", app.display_name(peer), peer),
+        (None, Some(room)) => format!("Sala {0} - ID {1}", app.room_name(room), room),
+        (None, None) => "Mensagens do servidor".to_string(),
+    };
+    let messages = List::new(items).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(messages, body[0]);
+
+    let users: Vec<ListItem> = app
+        .users
+        .iter()
+        .map(|(id, name)| {
+            let item = ListItem::new(format!("{0} - {1}", id, name));
+            if *id == app.id {
+                item.style(Style::default().fg(Color::Cyan))
+            } else {
+                item
+            }
+        })
+        .collect();
+    let users = List::new(users).block(Block::default().title("Online").borders(Borders::ALL));
+    f.render_widget(users, body[1]);
+
+    let input = Paragraph::new(app.input.as_str())
+        .block(Block::default().title("Mensagem").borders(Borders::ALL));
+    f.render_widget(input, chunks[2]);
+    f.set_cursor(
+        chunks[2].x + app.input.chars().count() as u16 + 1,
+        chunks[2].y + 1,
+    );
+
+    let (state, state_style) = match &app.state {
+        ConnectionState::Connected => ("Conectado".to_string(), Style::default().fg(Color::Green)),
+        ConnectionState::Disconnected(_) => {
+            ("Desconectado".to_string(), Style::default().fg(Color::Red))
+        }
+    };
+    let status = Paragraph::new(Spans::from(vec![
+        Span::styled(state, state_style),
+        Span::raw(format!(
+            " | {0:?}{1} | ID {2} | {3} | Tab: trocar conversa, Ctrl-W: fechar, Esc: sair",
+            app.protocol,
+            if app.e2e.is_some() { " + E2E" } else { "" },
+            app.id,
+            app.name
+        )),
+    ]));
+    f.render_widget(status, chunks[3]);
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyEvent};
+use tokio::sync::mpsc;
+use tokio::task;
+
+pub mod client;
+pub mod server;
+pub mod terminal;
+
+pub fn spawn_key_reader() -> mpsc::UnboundedReceiver<KeyEvent> {
+    let (key_tx, key_rx) = mpsc::unbounded_channel();
+    task::spawn_blocking(move || {
+        while !key_tx.is_closed() {
+            if let Ok(true) = event::poll(Duration::from_millis(100)) {
+                if let Ok(Event::Key(key)) = event::read() {
+                    let _ = key_tx.send(key);
+                }
+            }
+        }
+    });
+    key_rx
+}
@@ -0,0 +1,359 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use tui::{Frame, Terminal};
+
+use crate::models::admin::{AdminCommand, ServerStats};
+use crate::models::event::ServerEvent;
+use crate::models::message::Message;
+use crate::network::server::Server;
+use crate::ui::spawn_key_reader;
+use crate::ui::terminal::panicked;
+use crate::utilities::enums::MessageType;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InputMode {
+    Normal,
+    Filter,
+    Rename(u16),
+    /// Aviso para um client, ou para todos.
+    Notice(Option<u16>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientRow {
+    pub id: u16,
+    pub name: String,
+    pub addr: String,
+}
+
+pub struct ServerApp {
+    clients: Vec<ClientRow>,
+    events: VecDeque<ServerEvent>,
+    capacity: usize,
+    logs: Vec<String>,
+    selected: usize,
+    log_scroll: usize,
+    filter: String,
+    mode: InputMode,
+    input: String,
+    status: String,
+    quit: bool,
+}
+
+impl ServerApp {
+    pub fn new(capacity: usize) -> Self {
+        ServerApp {
+            clients: Vec::new(),
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            logs: Vec::new(),
+            selected: 0,
+            log_scroll: 0,
+            filter: String::new(),
+            mode: InputMode::Normal,
+            input: String::new(),
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn update_clients(&mut self, clients: Vec<ClientRow>) {
+        self.clients = clients;
+        if self.selected >= self.clients.len() {
+            self.selected = self.clients.len().saturating_sub(1);
+        }
+    }
+
+    pub fn push_event(&mut self, event: ServerEvent) {
+        if self.events.len() >= self.capacity {
+            if let Some(oldest) = self.events.pop_front() {
+                if self.matches_filter(&oldest.string()) && !self.logs.is_empty() {
+                    self.logs.remove(0);
+                }
+            }
+        }
+        let line = event.string();
+        if self.matches_filter(&line) {
+            self.logs.push(line);
+            if self.log_scroll > 0 {
+                self.log_scroll += 1;
+            }
+        }
+        self.events.push_back(event);
+    }
+
+    pub fn reset_events(&mut self, events: Vec<ServerEvent>) {
+        self.events = events.into_iter().collect();
+        self.refresh_logs();
+    }
+
+    fn matches_filter(&self, line: &str) -> bool {
+        self.filter.is_empty() || line.to_lowercase().contains(&self.filter.to_lowercase())
+    }
+
+    fn refresh_logs(&mut self) {
+        self.logs = self
+            .events
+            .iter()
+            .map(|event| event.string())
+            .filter(|line| self.matches_filter(line))
+            .collect();
+        self.log_scroll = self.log_scroll.min(self.logs.len());
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = status;
+    }
+
+    fn selected_client(&self) -> Option<&ClientRow> {
+        self.clients.get(self.selected)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<AdminCommand> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return None;
+        }
+        if self.mode != InputMode::Normal {
+            return self.handle_input_key(key);
+        }
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < self.clients.len() => self.selected += 1,
+            KeyCode::PageUp => {
+                self.log_scroll = (self.log_scroll + 10).min(self.logs.len());
+            }
+            KeyCode::PageDown => self.log_scroll = self.log_scroll.saturating_sub(10),
+            KeyCode::Home => self.log_scroll = self.logs.len(),
+            KeyCode::End => self.log_scroll = 0,
+            KeyCode::Char('/') => {
+                self.input = self.filter.clone();
+                self.mode = InputMode::Filter;
+            }
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.refresh_logs();
+            }
+            KeyCode::Char('k') => {
+                return self
+                    .selected_client()
+                    .map(|client| AdminCommand::Kick { user: client.id });
+            }
+            KeyCode::Char('r') => {
+                if let Some(id) = self.selected_client().map(|client| client.id) {
+                    self.input.clear();
+                    self.mode = InputMode::Rename(id);
+                }
+            }
+            KeyCode::Char('n') => {
+                if let Some(id) = self.selected_client().map(|client| client.id) {
+                    self.input.clear();
+                    self.mode = InputMode::Notice(Some(id));
+                }
+            }
+            KeyCode::Char('a') => {
+                self.input.clear();
+                self.mode = InputMode::Notice(None);
+            }
+            KeyCode::Char('e') => return Some(AdminCommand::Stats),
+            KeyCode::Char('c') => return Some(AdminCommand::ReloadConfig),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) -> Option<AdminCommand> {
+        match key.code {
+            KeyCode::Esc => {
+                if self.mode == InputMode::Filter {
+                    self.filter.clear();
+                    self.refresh_logs();
+                }
+                self.input.clear();
+                self.mode = InputMode::Normal;
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Enter => {
+                let input = self.input.trim().to_string();
+                let mode = std::mem::replace(&mut self.mode, InputMode::Normal);
+                self.input.clear();
+                match mode {
+                    InputMode::Filter => {
+                        self.filter = input;
+                        self.log_scroll = 0;
+                        self.refresh_logs();
+                    }
+                    InputMode::Rename(user) if !input.is_empty() => {
+                        return Some(AdminCommand::Rename { user, name: input });
+                    }
+                    InputMode::Notice(user) if !input.is_empty() => {
+                        return Some(AdminCommand::Notice { user, text: input });
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+pub async fn run_server_ui<B: Backend>(
+    terminal: &mut Terminal<B>,
+    server: Server,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = ServerApp::new(server.config().await.event_log_capacity);
+    let (history, mut events_rx) = server.events.subscribe();
+    app.reset_events(history);
+    let mut key_rx = spawn_key_reader();
+    let mut tick = time::interval(Duration::from_millis(500));
+    while !app.should_quit() {
+        if panicked() {
+            return Err("Interface encerrada após pânico".into());
+        }
+        let clients = {
+            let sessions = server.sessions.read().await;
+            let name_table = server.name_table.read().await;
+            let mut clients: Vec<ClientRow> = sessions
+                .iter()
+                .map(|(id, addrs)| ClientRow {
+                    id: *id,
+                    name: name_table
+                        .get(id)
+                        .cloned()
+                        .unwrap_or(String::from("Sem nome")),
+                    addr: addrs.join(", "),
+                })
+                .collect();
+            clients.sort_by_key(|client| client.id);
+            clients
+        };
+        app.update_clients(clients);
+        terminal.draw(|f| render_server(f, &app))?;
+
+        tokio::select! {
+            Some(key) = key_rx.recv() => {
+                if let Some(command) = app.handle_key(key) {
+                    let response = server.admin(&command).await;
+                    app.set_status(admin_status(&response));
+                }
+            }
+            event = events_rx.recv() => {
+                match event {
+                    Ok(event) => app.push_event(event),
+                    Err(RecvError::Lagged(_)) => app.reset_events(server.events.recent()),
+                    Err(RecvError::Closed) => {}
+                }
+            }
+            _ = tick.tick() => {}
+        }
+    }
+    Ok(())
+}
+
+/// Texto da resposta a um comando administrativo, para a barra de status.
+fn admin_status(response: &Message) -> String {
+    if response.metadata.message_type == MessageType::Error {
+        return format!("Falha: {0}", response.error_reason());
+    }
+    match serde_json::from_slice::<ServerStats>(&response.content) {
+        Ok(stats) => stats.to_string(),
+        Err(_) => String::from_utf8_lossy(&response.content).to_string(),
+    }
+}
+
+fn render_server<B: Backend>(f: &mut Frame<B>, app: &ServerApp) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    let items: Vec<ListItem> = app
+        .clients
+        .iter()
+        .map(|client| {
+            ListItem::new(format!(
+                "Client ID:{0} \"{1}\" - {2}",
+                client.id, client.name, client.addr
+            ))
+        })
+        .collect();
+    let client_list = List::new(items)
+        .block(Block::default().title("Server").borders(Borders::ALL))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    let mut state = ListState::default();
+    if !app.clients.is_empty() {
+        state.select(Some(app.selected));
+    }
+    f.render_stateful_widget(client_list, chunks[0], &mut state);
+
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let end = app.logs.len() - app.log_scroll;
+    let start = end.saturating_sub(height);
+    let log_items: Vec<ListItem> = app.logs[start..end]
+        .iter()
+        .map(|log| ListItem::new(log.as_str()))
+        .collect();
+    let mut title = String::from("Logs");
+    if !app.filter.is_empty() {
+        title.push_str(&format!(" (filtro: {0})", app.filter));
+    }
+    if app.log_scroll > 0 {
+        title.push_str(&format!(" [+{0} linhas abaixo]", app.log_scroll));
+    }
+    let log_list = List::new(log_items).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(log_list, chunks[1]);
+
+    let (title, text) = match &app.mode {
+        InputMode::Normal => {
+            let help = "↑/↓: client  PgUp/PgDn/Home/End: logs  /: filtrar  k: expulsar  r: renomear  n: aviso  a: aviso geral  e: estatísticas  c: recarregar config  q: sair";
+            let text = if app.status.is_empty() {
+                help.to_string()
+            } else {
+                format!("{0} | {1}", app.status, help)
+            };
+            ("Comandos".to_string(), text)
+        }
+        InputMode::Filter => ("Filtrar logs".to_string(), app.input.clone()),
+        InputMode::Rename(id) => (format!("Novo nome para ID {0}", id), app.input.clone()),
+        InputMode::Notice(Some(id)) => (format!("Aviso para ID {0}", id), app.input.clone()),
+        InputMode::Notice(None) => ("Aviso para todos".to_string(), app.input.clone()),
+    };
+    let footer = Paragraph::new(text).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(footer, chunks[2]);
+    if app.mode != InputMode::Normal {
+        f.set_cursor(
+            chunks[2].x + app.input.chars().count() as u16 + 1,
+            chunks[2].y + 1,
+        );
+    }
+}
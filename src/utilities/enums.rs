@@ -1,5 +1,8 @@
 use std::convert::TryFrom;
 
+use clap::ValueEnum;
+use serde::Deserialize;
+
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MessageType {
@@ -12,6 +15,18 @@ pub enum MessageType {
     Disconnect = 6,
     Error = 7,
     Success = 8,
+    Presence = 10,
+    PublicKey = 11,
+    Encrypted = 12,
+    Delivered = 13,
+    History = 14,
+    CreateRoom = 15,
+    JoinRoom = 16,
+    LeaveRoom = 17,
+    ListRooms = 18,
+    RoomMessage = 19,
+    ModerateRoom = 20,
+    Admin = 21,
 }
 impl From<MessageType> for u8 {
     fn from(message_type: MessageType) -> Self {
@@ -32,13 +47,37 @@ impl TryFrom<u8> for MessageType {
             6 => Ok(MessageType::Disconnect),
             7 => Ok(MessageType::Error),
             8 => Ok(MessageType::Success),
+            10 => Ok(MessageType::Presence),
+            11 => Ok(MessageType::PublicKey),
+            12 => Ok(MessageType::Encrypted),
+            13 => Ok(MessageType::Delivered),
+            14 => Ok(MessageType::History),
+            15 => Ok(MessageType::CreateRoom),
+            16 => Ok(MessageType::JoinRoom),
+            17 => Ok(MessageType::LeaveRoom),
+            18 => Ok(MessageType::ListRooms),
+            19 => Ok(MessageType::RoomMessage),
+            20 => Ok(MessageType::ModerateRoom),
+            21 => Ok(MessageType::Admin),
             _ => Err("Tipo de mensagem inválido".to_string()),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Protocol {
     TCP,
     UDP,
 }
+
+/// O que o servidor faz com um client acima do limite de envio: descarta a
+/// mensagem, só avisa, silencia a sessão por um tempo ou a desconecta.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitAction {
+    Drop,
+    Warn,
+    Mute,
+    Disconnect,
+}
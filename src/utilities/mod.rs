@@ -1 +1,2 @@
 pub mod enums;
+pub mod signal;
@@ -0,0 +1,28 @@
+use std::future;
+
+use tokio::signal;
+
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if signal::ctrl_c().await.is_err() {
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::models::admin::AdminCommand;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::utilities::enums::MessageType;
+
+async fn start(file: &common::AccountsFile, require_auth: bool) -> common::RunningServer {
+    common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        require_auth,
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn password_and_token_authenticate_registered_name() {
+    let file = common::accounts_file();
+    let running = start(&file, false).await;
+
+    let alice = common::try_connect(running.tcp_port, "alice", Some("senha-da-alice"), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        running
+            .server
+            .authenticated
+            .read()
+            .await
+            .get(&alice.get_id()),
+        Some(&"alice".to_string())
+    );
+    let key = alice.disconnect().await.unwrap();
+    wait_response(&alice, key).await.unwrap();
+    drop(alice);
+
+    let alice = common::try_connect(running.tcp_port, "alice", None, Some(&file.token)).await;
+    assert!(alice.is_ok());
+}
+
+#[tokio::test]
+async fn invalid_credentials_are_rejected() {
+    let file = common::accounts_file();
+    let running = start(&file, false).await;
+
+    let wrong_password = common::try_connect(running.tcp_port, "alice", Some("errada"), None).await;
+    assert_eq!(wrong_password.err().unwrap(), "Credenciais inválidas");
+    let wrong_token = common::try_connect(running.tcp_port, "alice", None, Some("t1_errado")).await;
+    assert_eq!(wrong_token.err().unwrap(), "Credenciais inválidas");
+    let unknown = common::try_connect(running.tcp_port, "bob", Some("qualquer"), None).await;
+    assert_eq!(unknown.err().unwrap(), "Credenciais inválidas");
+    assert!(running.server.authenticated.read().await.is_empty());
+}
+
+#[tokio::test]
+async fn anonymous_clients_cannot_take_registered_names() {
+    let file = common::accounts_file();
+    let running = start(&file, false).await;
+
+    let anonymous = common::try_connect(running.tcp_port, "alice", None, None).await;
+    assert_eq!(anonymous.err().unwrap(), "Credenciais inválidas");
+
+    let bob = common::try_connect(running.tcp_port, "bob", None, None)
+        .await
+        .unwrap();
+    let key = bob.set_name("alice".to_string()).await.unwrap();
+    let renamed = wait_response(&bob, key).await;
+    assert_eq!(
+        renamed.unwrap_err().to_string(),
+        "Nome registrado por outro usuário: alice"
+    );
+    let forced = running
+        .server
+        .admin(&AdminCommand::Rename {
+            user: bob.get_id(),
+            name: "alice".to_string(),
+        })
+        .await;
+    assert_eq!(forced.metadata.message_type, MessageType::Error);
+}
+
+#[tokio::test]
+async fn require_auth_rejects_anonymous_clients() {
+    let file = common::accounts_file();
+    let running = start(&file, true).await;
+
+    let anonymous = common::try_connect(running.tcp_port, "bob", None, None).await;
+    assert_eq!(anonymous.err().unwrap(), "Autenticação obrigatória");
+    let alice = common::try_connect(running.tcp_port, "alice", Some("senha-da-alice"), None).await;
+    assert!(alice.is_ok());
+}
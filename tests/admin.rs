@@ -0,0 +1,175 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time;
+
+use t1_lab_redes::config::{ClientConfig, ServerConfig};
+use t1_lab_redes::models::admin::{AdminCommand, ServerStats};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::accounts::Accounts;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+fn alice_config(running: &common::RunningServer) -> ClientConfig {
+    ClientConfig {
+        password: Some("senha-da-alice".to_string()),
+        ..common::client_config(running, "alice", None)
+    }
+}
+
+fn set_admin(file: &common::AccountsFile, admin: bool) {
+    let mut accounts = Accounts::load(&file.path).unwrap();
+    accounts.set_admin("alice", admin).unwrap();
+    accounts.save().unwrap();
+}
+
+async fn start(file: &common::AccountsFile) -> common::RunningServer {
+    common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+async fn admin<C: Client>(client: &C, command: AdminCommand) -> Result<Message, String> {
+    let key = Message::generate_key();
+    client
+        .send(Message::new_admin_request(key, &command))
+        .await
+        .unwrap();
+    wait_response(client, key).await.map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn admins_kick_clients_and_others_are_refused() {
+    let file = common::accounts_file();
+    set_admin(&file, true);
+    let running = start(&file).await;
+    let alice = TcpClient::new(&alice_config(&running)).await.unwrap();
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+
+    let refused = admin(&bob, AdminCommand::Stats).await;
+    assert_eq!(
+        refused.unwrap_err(),
+        "Comando disponível apenas para administradores"
+    );
+
+    let bob_id = bob.get_id();
+    let kicked = admin(&alice, AdminCommand::Kick { user: bob_id })
+        .await
+        .unwrap();
+    assert_eq!(
+        kicked.content,
+        format!("Client ID {0} expulso", bob_id).as_bytes()
+    );
+    let notice = common::next_of(&bob, MessageType::Text).await;
+    assert_eq!(
+        notice.content,
+        "Você foi desconectado pelo servidor".as_bytes()
+    );
+    common::next_of(&bob, MessageType::Disconnect).await;
+    time::timeout(Duration::from_secs(5), async {
+        while running.server.sessions.read().await.contains_user(&bob_id) {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("client não foi desconectado");
+
+    let missing = admin(&alice, AdminCommand::Kick { user: bob_id }).await;
+    assert_eq!(
+        missing.unwrap_err(),
+        format!("ID {0} não encontrado", bob_id)
+    );
+}
+
+#[tokio::test]
+async fn admins_rename_notify_and_read_stats_over_tcp_and_udp() {
+    let file = common::accounts_file();
+    set_admin(&file, true);
+    let running = start(&file).await;
+    let alice = TcpClient::new(&alice_config(&running)).await.unwrap();
+    let bob = UdpClient::new(&common::client_config(&running, "bob", Some(Protocol::UDP)))
+        .await
+        .unwrap();
+
+    let bob_id = bob.get_id();
+    admin(
+        &alice,
+        AdminCommand::Rename {
+            user: bob_id,
+            name: "roberto".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    let presence = common::next_of(&bob, MessageType::Presence).await;
+    let presence: (u16, String, bool) = serde_json::from_slice(&presence.content).unwrap();
+    assert_eq!(presence, (bob_id, "roberto".to_string(), true));
+    let taken = admin(
+        &alice,
+        AdminCommand::Rename {
+            user: bob_id,
+            name: "alice".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(
+        taken.unwrap_err(),
+        "Nome registrado por outro usuário: alice"
+    );
+
+    admin(
+        &alice,
+        AdminCommand::Notice {
+            user: None,
+            text: "manutenção às 22h".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    for message in [
+        common::next_of(&alice, MessageType::Text).await,
+        common::next_of(&bob, MessageType::Text).await,
+    ] {
+        assert_eq!(message.metadata.receiver_id, 0);
+        assert_eq!(message.content, "manutenção às 22h".as_bytes());
+    }
+
+    let stats = admin(&alice, AdminCommand::Stats).await.unwrap();
+    let stats: ServerStats = serde_json::from_slice(&stats.content).unwrap();
+    assert_eq!(
+        (stats.users, stats.sessions, stats.authenticated),
+        (2, 2, 1)
+    );
+}
+
+#[tokio::test]
+async fn the_server_ui_reloads_the_accounts_file() {
+    let file = common::accounts_file();
+    let running = start(&file).await;
+    let alice = TcpClient::new(&alice_config(&running)).await.unwrap();
+    assert!(admin(&alice, AdminCommand::Stats).await.is_err());
+
+    set_admin(&file, true);
+    let reloaded = running.server.admin(&AdminCommand::ReloadConfig).await;
+    assert_eq!(reloaded.metadata.message_type, MessageType::Success);
+    assert_eq!(reloaded.content, "Configuração recarregada".as_bytes());
+    admin(&alice, AdminCommand::Stats).await.unwrap();
+
+    let notice = running
+        .server
+        .admin(&AdminCommand::Notice {
+            user: Some(alice.get_id()),
+            text: "olá do servidor".to_string(),
+        })
+        .await;
+    assert_eq!(notice.metadata.message_type, MessageType::Success);
+    let received = common::next_of(&alice, MessageType::Text).await;
+    assert_eq!(received.content, "olá do servidor".as_bytes());
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use t1_lab_redes::commands::bench;
+use t1_lab_redes::config::{ServerConfig, RATE_BURST, RATE_MESSAGES};
+use t1_lab_redes::network::tcp_client::TcpClient;
+
+#[tokio::test]
+async fn bench_paces_itself_under_the_default_rate_limit() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let client = TcpClient::new(&common::client_config(&running, "bench", None))
+        .await
+        .unwrap();
+
+    // Mais mensagens do que cabem na rajada, para esbarrar no limite da sessão.
+    let count = (RATE_MESSAGES * (RATE_BURST + 1)) as usize;
+    let report = bench::run(&client, count, 64).await.unwrap();
+    assert_eq!(report.count, count);
+    assert_eq!(report.latencies.len(), count);
+    assert!(report.throttled > 0);
+    assert!(report.to_string().contains("Limite de taxa do servidor"));
+}
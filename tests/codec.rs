@@ -0,0 +1,139 @@
+use std::io;
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use t1_lab_redes::models::error::ErrorCode;
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::codec::MessageCodec;
+use t1_lab_redes::utilities::enums::MessageType;
+
+fn encode(messages: &[Message]) -> BytesMut {
+    let mut codec = MessageCodec::default();
+    let mut bytes = BytesMut::new();
+    for message in messages {
+        codec.encode(message.frame(), &mut bytes).unwrap();
+    }
+    bytes
+}
+
+/// Entrega `bytes` ao codec um byte por vez e retorna tudo o que foi lido.
+fn decode_bytewise(codec: &mut MessageCodec, bytes: &[u8]) -> Vec<Result<Message, ErrorCode>> {
+    let mut buffer = BytesMut::new();
+    let mut decoded = Vec::new();
+    for byte in bytes {
+        buffer.extend_from_slice(&[*byte]);
+        while let Some(item) = codec.decode(&mut buffer).unwrap() {
+            decoded.push(item.map_err(|rejected| rejected.error.code));
+        }
+    }
+    assert!(buffer.is_empty());
+    decoded
+}
+
+#[test]
+fn encoding_matches_serialize() {
+    let message = Message::new_text(7, 42, "olá".to_string(), None, None);
+    let serialized = message.serialize();
+    assert_eq!(&encode(&[message])[..], &serialized[..]);
+}
+
+#[test]
+fn messages_are_decoded_from_partial_reads() {
+    let messages = [
+        Message::new_text(1, 2, "primeira".to_string(), None, None),
+        Message::new_list_clients_request(3),
+        Message::new_text(4, 5, "x".repeat(300), None, None),
+    ];
+    let bytes = encode(&messages);
+    let decoded = decode_bytewise(&mut MessageCodec::default(), &bytes);
+    assert_eq!(decoded.len(), messages.len());
+    for (decoded, message) in decoded.into_iter().zip(&messages) {
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded.metadata.key, message.metadata.key);
+        assert_eq!(decoded.metadata.receiver_id, message.metadata.receiver_id);
+        assert_eq!(decoded.metadata.message_type, message.metadata.message_type);
+        assert_eq!(decoded.content, message.content);
+    }
+}
+
+#[test]
+fn oversized_frames_are_skipped() {
+    let mut bytes = encode(&[Message::new_text(1, 2, "y".repeat(100), None, None)]);
+    bytes.extend_from_slice(&encode(&[Message::new_text(
+        5,
+        2,
+        "ok".to_string(),
+        None,
+        None,
+    )]));
+
+    let decoded = decode_bytewise(&mut MessageCodec::new(64), &bytes);
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(
+        decoded[0].as_ref().unwrap_err(),
+        &ErrorCode::MessageTooLarge
+    );
+    let last = decoded[1].as_ref().unwrap();
+    assert_eq!(
+        (last.metadata.key, last.metadata.message_type),
+        (5, MessageType::Text)
+    );
+    assert_eq!(last.content, "ok");
+}
+
+#[test]
+fn invalid_headers_are_stream_errors() {
+    let mut codec = MessageCodec::new(64);
+    let mut garbage = encode(&[Message::new_text(3, 2, String::new(), None, None)]);
+    garbage[4] = 200;
+    garbage[5..13].copy_from_slice(&u64::MAX.to_le_bytes());
+    let error = codec.decode(&mut garbage).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().starts_with("Metadata inválida"));
+}
+
+#[test]
+fn rejections_carry_the_key_before_the_content_arrives() {
+    let mut codec = MessageCodec::new(10);
+    let bytes = encode(&[Message::new_text(9, 1, "z".repeat(1000), None, None)]);
+    let mut buffer = BytesMut::from(&bytes[..20]);
+    let rejected = codec.decode(&mut buffer).unwrap().unwrap().unwrap_err();
+    assert_eq!(rejected.key, 9);
+    assert_eq!(
+        rejected.error.message,
+        "Mensagem de 1000 bytes excede o limite de 10 bytes"
+    );
+    buffer.extend_from_slice(&bytes[20..]);
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn declared_lengths_do_not_allocate_up_front() {
+    let mut codec = MessageCodec::default();
+    let mut message = Message::new_text(1, 2, String::new(), None, None);
+    message.metadata.message_length = 16 * 1024 * 1024;
+    let mut buffer = encode(&[message]);
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+    assert!(buffer.capacity() <= 2 * 64 * 1024);
+    buffer.extend_from_slice(&[0; 100 * 1024]);
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+    // Cresce com o que chegou, longe dos 16 MiB declarados.
+    assert!(
+        buffer.capacity() <= 2 * (buffer.len() + 64 * 1024),
+        "{0}",
+        buffer.capacity()
+    );
+}
+
+#[test]
+fn lengths_beyond_the_address_space_are_rejected() {
+    let mut codec = MessageCodec::new(u64::MAX);
+    let mut message = Message::new_text(3, 2, String::new(), None, None);
+    message.metadata.message_length = u64::MAX;
+    let mut buffer = encode(&[message]);
+    let rejected = codec.decode(&mut buffer).unwrap().unwrap().unwrap_err();
+    assert_eq!(rejected.key, 3);
+    assert_eq!(rejected.error.code, ErrorCode::MessageTooLarge);
+}
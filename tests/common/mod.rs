@@ -0,0 +1,167 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::time;
+
+use t1_lab_redes::config::{ClientConfig, ServerConfig};
+use t1_lab_redes::models::event::ServerEventKind;
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::accounts::Accounts;
+use t1_lab_redes::network::client::Client;
+use t1_lab_redes::network::datagram::DatagramTransport;
+use t1_lab_redes::network::server::Server;
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+pub struct RunningServer {
+    pub server: Server,
+    pub tcp_port: u16,
+    pub udp_port: u16,
+}
+
+/// Inicia o servidor em portas livres de 127.0.0.1 e aguarda o evento `Started`.
+pub async fn start_server(config: ServerConfig) -> RunningServer {
+    start(Server::new(local(config))).await
+}
+
+/// Como `start_server`, mas com os datagramas passando por `transport`.
+pub async fn start_server_with_udp(
+    config: ServerConfig,
+    transport: Box<dyn DatagramTransport>,
+) -> RunningServer {
+    start(Server::new(local(config)).with_udp_transport(transport)).await
+}
+
+fn local(config: ServerConfig) -> ServerConfig {
+    ServerConfig {
+        host: "127.0.0.1".to_string(),
+        tcp_port: 0,
+        udp_port: 0,
+        ..config
+    }
+}
+
+async fn start(server: Server) -> RunningServer {
+    let (_, mut events) = server.events.subscribe();
+    let runner = server.clone();
+    tokio::spawn(async move { runner.start().await });
+    loop {
+        if let ServerEventKind::Started { tcp_port, udp_port } = events.recv().await.unwrap().kind {
+            return RunningServer {
+                server,
+                tcp_port,
+                udp_port,
+            };
+        }
+    }
+}
+
+/// Configuração de um client chamado `name` para o servidor em execução; sem
+/// `transport`, usa o transporte padrão.
+pub fn client_config(
+    running: &RunningServer,
+    name: &str,
+    transport: Option<Protocol>,
+) -> ClientConfig {
+    let config = ClientConfig {
+        host: "127.0.0.1".to_string(),
+        tcp_port: running.tcp_port,
+        udp_port: running.udp_port,
+        name: name.to_string(),
+        ..ClientConfig::default()
+    };
+    match transport {
+        Some(transport) => ClientConfig {
+            transport,
+            ..config
+        },
+        None => config,
+    }
+}
+
+/// Configuração TLS de um client chamado `name`, validando o nome "localhost".
+pub fn tls_client_config(running: &RunningServer, name: &str) -> ClientConfig {
+    ClientConfig {
+        tls: true,
+        tls_server_name: Some("localhost".to_string()),
+        ..client_config(running, name, None)
+    }
+}
+
+/// Configuração UDP da `alice` com datagramas cifrados pela chave `psk`.
+pub fn udp_psk_client_config(running: &RunningServer, psk: &str) -> ClientConfig {
+    ClientConfig {
+        udp_psk: Some(psk.to_string()),
+        ..client_config(running, "alice", Some(Protocol::UDP))
+    }
+}
+
+/// Conecta por TCP, com a senha da conta se informada.
+pub async fn connect(tcp_port: u16, name: &str, password: Option<&str>) -> TcpClient {
+    try_connect(tcp_port, name, password, None).await.unwrap()
+}
+
+/// Como `connect`, aceitando também um token e devolvendo a recusa do servidor.
+pub async fn try_connect(
+    tcp_port: u16,
+    name: &str,
+    password: Option<&str>,
+    token: Option<&str>,
+) -> Result<TcpClient, String> {
+    let config = ClientConfig {
+        host: "127.0.0.1".to_string(),
+        tcp_port,
+        name: name.to_string(),
+        password: password.map(str::to_string),
+        token: token.map(str::to_string),
+        ..ClientConfig::default()
+    };
+    TcpClient::new(&config).await.map_err(|e| e.to_string())
+}
+
+/// Próxima mensagem do tipo pedido, ignorando as demais.
+pub async fn next_of<C: Client>(client: &C, message_type: MessageType) -> Message {
+    time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = client.listen().await.unwrap();
+            if message.metadata.message_type == message_type {
+                return message;
+            }
+        }
+    })
+    .await
+    .expect("mensagem não recebida")
+}
+
+pub struct AccountsFile {
+    pub dir: PathBuf,
+    pub path: PathBuf,
+    pub token: String,
+}
+
+impl Drop for AccountsFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Cria um arquivo com a conta `alice` (senha e token).
+pub fn accounts_file() -> AccountsFile {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "t1-accounts-{0}-{1}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("accounts.json");
+    let mut accounts = Accounts::load(&path).unwrap();
+    accounts.set_password("alice", "senha-da-alice").unwrap();
+    let token = accounts.add_token("alice").unwrap();
+    accounts.save().unwrap();
+    AccountsFile { dir, path, token }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::models::event::ServerEventKind;
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::e2e::E2eKeys;
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::utilities::enums::MessageType;
+
+async fn request(client: &TcpClient, message: Message) -> Result<Message, String> {
+    let key = message.metadata.key;
+    client.send(message).await.unwrap();
+    wait_response(client, key).await.map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn server_routes_ciphertext_between_published_keys() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let (_, mut events) = running.server.events.subscribe();
+    let alice = common::connect(running.tcp_port, "alice", None).await;
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+    let alice_keys = E2eKeys::generate();
+    let bob_keys = E2eKeys::generate();
+
+    let published = Message::new_public_key(1, bob_keys.public_key().to_vec());
+    request(&bob, published).await.unwrap();
+    let fetched = request(&alice, Message::new_public_key_request(2, bob.get_id()))
+        .await
+        .unwrap();
+    assert_eq!(fetched.content[..], bob_keys.public_key());
+
+    let secret = "segredo entre alice e bob";
+    let envelope = alice_keys
+        .seal(&bob_keys.public_key(), MessageType::Text, secret.as_bytes())
+        .unwrap();
+    request(&alice, Message::new_encrypted(3, bob.get_id(), envelope))
+        .await
+        .unwrap();
+
+    let received = loop {
+        let message = bob.listen().await.unwrap();
+        if message.metadata.message_type == MessageType::Encrypted {
+            break message;
+        }
+    };
+    assert_eq!(received.metadata.receiver_id, alice.get_id());
+    let opened = bob_keys.open(&received.content).unwrap();
+    assert_eq!(opened.message_type, MessageType::Text);
+    assert_eq!(opened.content, secret.as_bytes());
+    assert_eq!(opened.sender_key, alice_keys.public_key());
+    assert!(E2eKeys::generate().open(&received.content).is_err());
+
+    let routed = loop {
+        let event = events.recv().await.unwrap();
+        if let ServerEventKind::MessageRouted { .. } = &event.kind {
+            break event;
+        }
+    };
+    let ServerEventKind::MessageRouted {
+        message_type,
+        content,
+        ..
+    } = &routed.kind
+    else {
+        unreachable!();
+    };
+    assert_eq!(*message_type, MessageType::Encrypted);
+    assert!(content.is_none());
+    assert!(!routed.string().contains(secret));
+    running.server.shutdown().await;
+}
+
+#[tokio::test]
+async fn fetching_unpublished_or_invalid_keys_fails() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let alice = common::connect(running.tcp_port, "alice", None).await;
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+
+    let missing = request(&alice, Message::new_public_key_request(1, bob.get_id())).await;
+    assert!(missing.unwrap_err().contains("sem chave pública"));
+    let invalid = request(&bob, Message::new_public_key(2, vec![0u8; 5])).await;
+    assert!(invalid.is_err());
+    running.server.shutdown().await;
+}
+
+#[test]
+fn tampered_envelope_is_rejected() {
+    let alice = E2eKeys::generate();
+    let bob = E2eKeys::generate();
+    let mut envelope = alice
+        .seal(&bob.public_key(), MessageType::File, &[1, 2, 3])
+        .unwrap();
+    let opened = bob.open(&envelope).unwrap();
+    assert_eq!(opened.message_type, MessageType::File);
+    assert_eq!(opened.content, vec![1, 2, 3]);
+
+    let last = envelope.len() - 1;
+    envelope[last] ^= 1;
+    assert!(bob.open(&envelope).is_err());
+}
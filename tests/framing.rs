@@ -0,0 +1,247 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time;
+
+use t1_lab_redes::config::{
+    ClientConfig, ServerArgs, ServerConfig, BUFFER_SIZE, MAX_UDP_PACKETS, METADATA_BYTES,
+    UDP_METADATA_BYTES,
+};
+use t1_lab_redes::models::credentials::Credentials;
+use t1_lab_redes::models::error::{ErrorBody, ErrorCode};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::models::metadata::MsgMetadata;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::udp::{build_udp_message, fragment, Reassembly};
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+/// Conexão TCP sem client, para enviar bytes arbitrários ao servidor.
+async fn raw_connection(running: &common::RunningServer) -> TcpStream {
+    let mut stream = TcpStream::connect(("127.0.0.1", running.tcp_port))
+        .await
+        .unwrap();
+    let key = Message::generate_key();
+    let connect = Message::new_connection_request(key, &Credentials::anonymous("raw".to_string()));
+    stream.write_all(&connect.serialize()).await.unwrap();
+    response(&mut stream, key).await;
+    stream
+}
+
+async fn response(stream: &mut TcpStream, key: u16) -> Message {
+    time::timeout(Duration::from_secs(5), async {
+        loop {
+            let mut header = [0u8; METADATA_BYTES];
+            stream.read_exact(&mut header).await.unwrap();
+            let metadata = MsgMetadata::deserialize(&header, false).unwrap();
+            let mut content = vec![0u8; metadata.message_length as usize];
+            stream.read_exact(&mut content).await.unwrap();
+            if metadata.key == key {
+                return Message::new(metadata, content);
+            }
+        }
+    })
+    .await
+    .expect("resposta não recebida")
+}
+
+/// Cabeçalho TCP com os campos informados, sem conferir o tipo nem o tamanho.
+fn header(key: u16, message_type: u8, length: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(key.to_le_bytes());
+    bytes.extend(0u16.to_le_bytes());
+    bytes.push(message_type);
+    bytes.extend(length.to_le_bytes());
+    bytes
+}
+
+fn error_body(message: &Message) -> ErrorBody {
+    assert_eq!(message.metadata.message_type, MessageType::Error);
+    ErrorBody::decode(&message.content).unwrap()
+}
+
+async fn still_connected(stream: &mut TcpStream) {
+    let key = Message::generate_key();
+    let list = Message::new_list_clients_request(key);
+    stream.write_all(&list.serialize()).await.unwrap();
+    let listed = response(stream, key).await;
+    assert_eq!(listed.metadata.message_type, MessageType::Success);
+}
+
+#[test]
+fn short_headers_are_errors() {
+    for size in 0..13 {
+        assert!(MsgMetadata::deserialize(&vec![0; size], false).is_err());
+    }
+    assert!(MsgMetadata::deserialize(&[0; 16], true).is_err());
+    assert!(MsgMetadata::deserialize(&header(1, 200, 0), false).is_err());
+    let metadata = MsgMetadata::deserialize(&header(7, 1, 42), false).unwrap();
+    assert_eq!(
+        (metadata.key, metadata.message_type, metadata.message_length),
+        (7, MessageType::Text, 42)
+    );
+}
+
+#[test]
+fn udp_messages_fit_the_packet_sequence() {
+    // Um byte de conteúdo por pacote: o limite é de MAX_UDP_PACKETS bytes.
+    let packet_size = METADATA_BYTES + UDP_METADATA_BYTES + 1;
+    let largest = Message::new_file(1, 2, vec![7; MAX_UDP_PACKETS as usize], None, None);
+    let packets = fragment(&largest, 3, packet_size).unwrap();
+    assert_eq!(packets.len() as u64, MAX_UDP_PACKETS);
+    let last = MsgMetadata::deserialize(packets.last().unwrap(), true).unwrap();
+    assert_eq!(last.udp_seq, Some(u16::MAX));
+    // A remontagem de todos os pacotes, fora de ordem, não é quadrática.
+    let mut reassembly = Reassembly::default();
+    let mut rebuilt = None;
+    for packet in packets.into_iter().rev() {
+        if let Some(message) = build_udp_message(packet, &mut reassembly, MAX_UDP_PACKETS).unwrap()
+        {
+            rebuilt = Some(message);
+        }
+    }
+    assert_eq!(rebuilt.unwrap().content, largest.content);
+    assert_eq!(reassembly.pending_messages(), 0);
+
+    let too_large = Message::new_file(1, 2, vec![7; MAX_UDP_PACKETS as usize + 1], None, None);
+    assert!(fragment(&too_large, 4, packet_size).is_err());
+
+    let payload = (BUFFER_SIZE - METADATA_BYTES - UDP_METADATA_BYTES) as u64;
+    let args = |max_message_size| ServerArgs {
+        max_message_size: Some(max_message_size),
+        ..ServerArgs::default()
+    };
+    assert!(ServerConfig::load(&args(payload * MAX_UDP_PACKETS)).is_ok());
+    assert_eq!(
+        ServerConfig::load(&args(payload * MAX_UDP_PACKETS + 1)).unwrap_err(),
+        format!(
+            "max_message_size deve ser no máximo {0} com buffer_size {1}",
+            payload * MAX_UDP_PACKETS,
+            BUFFER_SIZE
+        )
+    );
+    assert!(ServerConfig::load(&args(0)).is_err());
+}
+
+#[tokio::test]
+async fn oversized_frames_are_answered_and_skipped() {
+    let running = common::start_server(ServerConfig {
+        max_message_size: 64,
+        ..ServerConfig::default()
+    })
+    .await;
+    let mut stream = raw_connection(&running).await;
+
+    let mut frame = header(10, MessageType::Text as u8, 100);
+    frame.extend([b'a'; 100]);
+    stream.write_all(&frame).await.unwrap();
+    let error = error_body(&response(&mut stream, 10).await);
+    assert_eq!(
+        error,
+        ErrorBody::new(
+            ErrorCode::MessageTooLarge,
+            "Mensagem de 100 bytes excede o limite de 64 bytes"
+        )
+    );
+    still_connected(&mut stream).await;
+}
+
+#[tokio::test]
+async fn invalid_headers_close_the_connection() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let mut stream = raw_connection(&running).await;
+
+    // O tamanho declarado não é confiável; o servidor não espera por ele.
+    stream.write_all(&header(11, 200, u64::MAX)).await.unwrap();
+    let mut rest = Vec::new();
+    time::timeout(Duration::from_secs(5), stream.read_to_end(&mut rest))
+        .await
+        .expect("conexão não foi encerrada")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn text_must_be_utf8_and_names_must_be_valid() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let mut stream = raw_connection(&running).await;
+
+    let mut frame = header(20, MessageType::Text as u8, 2);
+    frame.extend([0xff, 0xfe]);
+    stream.write_all(&frame).await.unwrap();
+    let error = error_body(&response(&mut stream, 20).await);
+    assert_eq!(
+        error,
+        ErrorBody::new(ErrorCode::InvalidRequest, "Conteúdo não é UTF-8 válido")
+    );
+
+    for (key, name, reason) in [
+        (
+            21,
+            "x".repeat(33),
+            "Nome com mais de 32 caracteres".to_string(),
+        ),
+        (22, "a\tb".to_string(), "Nome inválido: a\tb".to_string()),
+        (23, "   ".to_string(), "Nome vazio".to_string()),
+    ] {
+        let rename = Message::new_set_name_request(key, name, None, None);
+        stream.write_all(&rename.serialize()).await.unwrap();
+        let error = error_body(&response(&mut stream, key).await);
+        assert_eq!(error, ErrorBody::new(ErrorCode::InvalidRequest, reason));
+    }
+
+    let rename = Message::new_set_name_request(24, "Ana Clara".to_string(), None, None);
+    stream.write_all(&rename.serialize()).await.unwrap();
+    let renamed = response(&mut stream, 24).await;
+    assert_eq!(renamed.metadata.message_type, MessageType::Success);
+}
+
+#[tokio::test]
+async fn malformed_and_oversized_datagrams_do_not_break_the_server() {
+    let running = common::start_server(ServerConfig {
+        max_message_size: 64,
+        ..ServerConfig::default()
+    })
+    .await;
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    for size in [0, 1, 5, 13, 16] {
+        socket
+            .send_to(&vec![1; size], ("127.0.0.1", running.udp_port))
+            .await
+            .unwrap();
+    }
+
+    let bob = UdpClient::new(&ClientConfig {
+        host: "127.0.0.1".to_string(),
+        tcp_port: running.tcp_port,
+        udp_port: running.udp_port,
+        name: "bob".to_string(),
+        transport: Protocol::UDP,
+        ..ClientConfig::default()
+    })
+    .await
+    .unwrap();
+    let key = Message::generate_key();
+    bob.send(Message::new_text(
+        key,
+        bob.get_id(),
+        "b".repeat(100),
+        None,
+        None,
+    ))
+    .await
+    .unwrap();
+    let error = wait_response(&bob, key).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Mensagem de 100 bytes excede o limite de 64 bytes"
+    );
+
+    let key = Message::generate_key();
+    bob.send(Message::new_list_clients_request(key))
+        .await
+        .unwrap();
+    wait_response(&bob, key).await.unwrap();
+}
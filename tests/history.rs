@@ -0,0 +1,303 @@
+mod common;
+
+use std::fs;
+
+use t1_lab_redes::config::{ServerConfig, MEMORY_HISTORY_CAPACITY};
+use t1_lab_redes::models::history::{HistoryEntry, HistoryRequest};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::history::{HistoryFilter, HistoryStore, LogHistory, MemoryHistory};
+use t1_lab_redes::network::tcp_client::TcpClient;
+
+fn server_config(file: &common::AccountsFile) -> ServerConfig {
+    ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    }
+}
+
+async fn send_texts(client: &TcpClient, to: u16, texts: &[&str]) {
+    for text in texts {
+        let key = client.send_text(text.to_string(), to).await.unwrap();
+        wait_response(client, key).await.unwrap();
+    }
+}
+
+async fn history(
+    client: &TcpClient,
+    peer: u16,
+    request: HistoryRequest,
+) -> Result<Vec<HistoryEntry>, String> {
+    let key = Message::generate_key();
+    client
+        .send(Message::new_history_request(key, peer, &request))
+        .await
+        .unwrap();
+    let response = wait_response(client, key)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::from_slice(&response.content).unwrap())
+}
+
+fn contents(entries: &[HistoryEntry]) -> Vec<&str> {
+    entries
+        .iter()
+        .map(|entry| entry.content.as_deref().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn history_is_paged_backwards_by_id() {
+    let file = common::accounts_file();
+    let running = common::start_server(server_config(&file)).await;
+    let alice = common::connect(running.tcp_port, "alice", Some("senha-da-alice")).await;
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+    let carol = common::connect(running.tcp_port, "carol", None).await;
+
+    send_texts(&alice, bob.get_id(), &["um", "dois", "três", "quatro"]).await;
+    send_texts(&alice, carol.get_id(), &["para carol"]).await;
+
+    let limit = |before| HistoryRequest {
+        before,
+        limit: Some(2),
+        ..HistoryRequest::default()
+    };
+    let latest = history(&alice, bob.get_id(), limit(None)).await.unwrap();
+    assert_eq!(contents(&latest), ["três", "quatro"]);
+    assert!(latest
+        .iter()
+        .all(|entry| entry.from == alice.get_id() && entry.to == bob.get_id()));
+    let older = history(&alice, bob.get_id(), limit(Some(latest[0].id)))
+        .await
+        .unwrap();
+    assert_eq!(contents(&older), ["um", "dois"]);
+    let oldest = history(&alice, bob.get_id(), limit(Some(older[0].id)))
+        .await
+        .unwrap();
+    assert!(oldest.is_empty());
+
+    let all = history(&alice, 0, HistoryRequest::default()).await.unwrap();
+    assert_eq!(all.len(), 5);
+}
+
+#[tokio::test]
+async fn search_matches_text_case_insensitively() {
+    let file = common::accounts_file();
+    let running = common::start_server(server_config(&file)).await;
+    let alice = common::connect(running.tcp_port, "alice", Some("senha-da-alice")).await;
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+
+    send_texts(
+        &alice,
+        bob.get_id(),
+        &["Reunião amanhã", "almoço?", "a reunião mudou"],
+    )
+    .await;
+    let found = history(
+        &alice,
+        0,
+        HistoryRequest {
+            search: Some("REUNIÃO".to_string()),
+            ..HistoryRequest::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(contents(&found), ["Reunião amanhã", "a reunião mudou"]);
+}
+
+#[tokio::test]
+async fn anonymous_users_cannot_read_history() {
+    let file = common::accounts_file();
+    let running = common::start_server(server_config(&file)).await;
+    let alice = common::connect(running.tcp_port, "alice", Some("senha-da-alice")).await;
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+    send_texts(&alice, bob.get_id(), &["segredo"]).await;
+
+    let denied = history(&bob, alice.get_id(), HistoryRequest::default()).await;
+    assert_eq!(
+        denied.unwrap_err(),
+        "Histórico disponível apenas para usuários autenticados"
+    );
+
+    let key = Message::generate_key();
+    let mut invalid = Message::new_history_request(key, bob.get_id(), &HistoryRequest::default());
+    invalid.content = b"{".to_vec().into();
+    invalid.metadata.message_length = 1;
+    alice.send(invalid).await.unwrap();
+    let error = wait_response(&alice, key).await.unwrap_err();
+    assert_eq!(error.to_string(), "Requisição de histórico inválida");
+}
+
+#[tokio::test]
+async fn history_file_survives_restart() {
+    let file = common::accounts_file();
+    let config = ServerConfig {
+        history_file: Some(file.dir.join("history.jsonl")),
+        ..server_config(&file)
+    };
+
+    let first = common::start_server(config.clone()).await;
+    let alice = common::connect(first.tcp_port, "alice", Some("senha-da-alice")).await;
+    let bob = common::connect(first.tcp_port, "bob", None).await;
+    let bob_id = bob.get_id();
+    send_texts(&alice, bob_id, &["antes do reinício"]).await;
+    drop(alice);
+    drop(bob);
+    first.server.shutdown().await;
+
+    let second = common::start_server(config).await;
+    let alice = common::connect(second.tcp_port, "alice", Some("senha-da-alice")).await;
+    let entries = history(&alice, 0, HistoryRequest::default()).await.unwrap();
+    assert_eq!(contents(&entries), ["antes do reinício"]);
+    assert_eq!(entries[0].to, bob_id);
+}
+
+#[test]
+fn log_history_tolerates_a_truncated_last_line() {
+    let file = common::accounts_file();
+    let path = file.dir.join("history.jsonl");
+    let entry = HistoryEntry {
+        id: 7,
+        timestamp: 0,
+        from: 1,
+        to: 2,
+        message_type: 1,
+        bytes: 2,
+        content: Some("oi".to_string()),
+        room: None,
+    };
+    let line = serde_json::to_string(&entry).unwrap();
+    fs::write(&path, format!("{0}\n{1}", line, &line[..10])).unwrap();
+
+    let store = LogHistory::open(&path).unwrap();
+    let appended = store
+        .append(HistoryEntry {
+            id: 0,
+            content: Some("tchau".to_string()),
+            ..entry.clone()
+        })
+        .unwrap();
+    assert_eq!(appended.id, 8);
+    let entries = store
+        .query(&HistoryFilter::new(1, 2, HistoryRequest::default()))
+        .unwrap();
+    assert_eq!(contents(&entries), ["oi", "tchau"]);
+}
+
+#[test]
+fn room_entries_are_selected_by_room() {
+    let file = common::accounts_file();
+    let log: Box<dyn HistoryStore> =
+        Box::new(LogHistory::open(&file.dir.join("history.jsonl")).unwrap());
+    let memory: Box<dyn HistoryStore> = Box::new(MemoryHistory::default());
+    for store in [log, memory] {
+        let entry = |timestamp, to, text: &str, room| HistoryEntry {
+            id: 0,
+            timestamp,
+            from: 1,
+            to,
+            message_type: 1,
+            bytes: text.len(),
+            content: Some(text.to_string()),
+            room,
+        };
+        store.append(entry(10, 2, "direta", None)).unwrap();
+        store.append(entry(20, 2, "sala antiga", Some(2))).unwrap();
+        store.append(entry(30, 2, "na sala", Some(2))).unwrap();
+        store.append(entry(40, 3, "outra sala", Some(3))).unwrap();
+
+        let room = |room| HistoryRequest {
+            room: Some(room),
+            ..HistoryRequest::default()
+        };
+        let query = |filter| store.query(&filter).unwrap();
+        assert_eq!(
+            contents(&query(HistoryFilter::new(1, 2, HistoryRequest::default()))),
+            ["direta"]
+        );
+        assert_eq!(
+            contents(&query(HistoryFilter::new(1, 0, HistoryRequest::default()))),
+            ["direta"]
+        );
+        assert_eq!(
+            contents(&query(HistoryFilter::new(9, 0, room(2)))),
+            ["sala antiga", "na sala"]
+        );
+        let since = HistoryFilter {
+            since: Some(30),
+            ..HistoryFilter::new(9, 0, room(2))
+        };
+        assert_eq!(contents(&query(since)), ["na sala"]);
+        assert_eq!(
+            contents(&query(HistoryFilter::new(9, 0, room(3)))),
+            ["outra sala"]
+        );
+    }
+}
+
+fn numbered(id: usize) -> HistoryEntry {
+    let text = format!("{0:0>100}", id);
+    HistoryEntry {
+        id: 0,
+        timestamp: 0,
+        from: 1,
+        to: 2,
+        message_type: 1,
+        bytes: text.len(),
+        content: Some(text),
+        room: None,
+    }
+}
+
+#[test]
+fn log_history_pages_across_read_chunks() {
+    let file = common::accounts_file();
+    let path = file.dir.join("history.jsonl");
+    let store = LogHistory::open(&path).unwrap();
+    // Cerca de 400 KiB: várias páginas atravessam o limite dos blocos lidos do fim.
+    for id in 1..=2000 {
+        store.append(numbered(id)).unwrap();
+    }
+    let mut before = None;
+    let mut ids = Vec::new();
+    loop {
+        let request = HistoryRequest {
+            before,
+            limit: Some(150),
+            ..HistoryRequest::default()
+        };
+        let page = store.query(&HistoryFilter::new(1, 2, request)).unwrap();
+        let Some(first) = page.first() else {
+            break;
+        };
+        before = Some(first.id);
+        ids.splice(0..0, page.iter().map(|entry| entry.id));
+    }
+    assert_eq!(ids, (1..=2000).collect::<Vec<u64>>());
+
+    drop(store);
+    let store = LogHistory::open(&path).unwrap();
+    assert_eq!(store.append(numbered(2001)).unwrap().id, 2001);
+}
+
+#[test]
+fn memory_history_keeps_only_the_latest_entries() {
+    let store = MemoryHistory::default();
+    for id in 1..=MEMORY_HISTORY_CAPACITY + 10 {
+        store.append(numbered(id)).unwrap();
+    }
+    let request = HistoryRequest {
+        before: Some(11),
+        ..HistoryRequest::default()
+    };
+    let entries = store.query(&HistoryFilter::new(1, 2, request)).unwrap();
+    assert!(entries.is_empty());
+    let latest = store
+        .query(&HistoryFilter::new(1, 2, HistoryRequest::default()))
+        .unwrap();
+    assert_eq!(
+        latest.last().unwrap().id,
+        (MEMORY_HISTORY_CAPACITY + 10) as u64
+    );
+}
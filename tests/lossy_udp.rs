@@ -0,0 +1,357 @@
+mod common;
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::time;
+
+use t1_lab_redes::config::{
+    ClientConfig, ServerConfig, BUFFER_SIZE, MAX_DATAGRAM_SIZE, METADATA_BYTES, UDP_METADATA_BYTES,
+    UDP_PENDING_MESSAGES,
+};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::datagram::DatagramTransport;
+use t1_lab_redes::network::simulated::{Impairment, SimulatedNetwork, SimulatedSocket};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::network::udp::{build_udp_message, fragment, Reassembly};
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+/// Quanto esperar, depois da última mensagem, por entregas atrasadas.
+const QUIET: Duration = Duration::from_millis(300);
+
+/// Mensagens de tamanhos variados, de nenhum a vários pacotes, com chaves
+/// distintas.
+fn messages(count: u16) -> Vec<Message> {
+    (0..count)
+        .map(|key| {
+            let size = usize::from(key) * 397 % 5000;
+            let content: Vec<u8> = (0..size).map(|i| (i + usize::from(key)) as u8).collect();
+            Message::new_file(key, 7, content, None, None)
+        })
+        .collect()
+}
+
+async fn send_all(socket: &SimulatedSocket, target: SocketAddr, messages: &[Message]) {
+    for (udp_id, message) in messages.iter().enumerate() {
+        for packet in fragment(message, udp_id as u16, BUFFER_SIZE).unwrap() {
+            socket.send_to(&packet, target).await.unwrap();
+        }
+    }
+}
+
+/// Remonta o que chegar em `socket` até a rede ficar em silêncio por `QUIET`.
+async fn receive_all(socket: &SimulatedSocket, reassembly: &mut Reassembly) -> Vec<Message> {
+    let mut received = Vec::new();
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+    while let Ok(datagram) = time::timeout(QUIET, socket.recv_from(&mut buf)).await {
+        let (len, _) = datagram.unwrap();
+        if let Some(message) = build_udp_message(buf[..len].to_vec(), reassembly, u64::MAX).unwrap()
+        {
+            received.push(message);
+        }
+    }
+    received
+}
+
+/// Confere que cada mensagem recebida é idêntica à enviada com a mesma chave
+/// e que nenhuma chegou duas vezes.
+fn assert_intact(received: &[Message], sent: &[Message]) {
+    let sent: HashMap<u16, &Message> = sent.iter().map(|x| (x.metadata.key, x)).collect();
+    let mut seen = Vec::new();
+    for message in received {
+        let key = message.metadata.key;
+        assert!(
+            !seen.contains(&key),
+            "mensagem {0} entregue duas vezes",
+            key
+        );
+        seen.push(key);
+        let original = sent[&key];
+        assert_eq!(
+            message.metadata.message_type,
+            original.metadata.message_type
+        );
+        assert_eq!(message.metadata.receiver_id, original.metadata.receiver_id);
+        assert_eq!(
+            message.metadata.message_length,
+            original.metadata.message_length
+        );
+        assert_eq!(message.content, original.content);
+    }
+}
+
+#[tokio::test]
+async fn duplicated_and_reordered_fragments_are_reassembled_once() {
+    let network = SimulatedNetwork::new(
+        Impairment {
+            duplicate_rate: 0.3,
+            jitter: Duration::from_millis(20),
+            ..Impairment::default()
+        },
+        1,
+    );
+    let sender = network.bind_any().unwrap();
+    let receiver = network.bind_any().unwrap();
+    let sent = messages(40);
+
+    send_all(&sender, receiver.local_addr().unwrap(), &sent).await;
+    let mut reassembly = Reassembly::default();
+    let received = receive_all(&receiver, &mut reassembly).await;
+
+    assert!(network.stats().duplicated > 0);
+    assert_eq!(received.len(), sent.len());
+    assert_intact(&received, &sent);
+    assert_eq!(reassembly.pending_messages(), 0);
+}
+
+#[tokio::test]
+async fn lost_fragments_never_produce_corrupt_messages() {
+    let network = SimulatedNetwork::new(
+        Impairment {
+            drop_rate: 0.05,
+            duplicate_rate: 0.2,
+            jitter: Duration::from_millis(10),
+            ..Impairment::default()
+        },
+        2,
+    );
+    let sender = network.bind_any().unwrap();
+    let receiver = network.bind_any().unwrap();
+    let sent = messages(100);
+
+    send_all(&sender, receiver.local_addr().unwrap(), &sent).await;
+    let mut reassembly = Reassembly::default();
+    let received = receive_all(&receiver, &mut reassembly).await;
+
+    assert!(network.stats().dropped > 0);
+    assert!(!received.is_empty());
+    assert!(received.len() < sent.len());
+    assert_intact(&received, &sent);
+    assert!(reassembly.pending_messages() <= UDP_PENDING_MESSAGES);
+}
+
+#[tokio::test]
+async fn datagrams_above_the_mtu_are_dropped() {
+    let network = SimulatedNetwork::new(
+        Impairment {
+            mtu: Some(BUFFER_SIZE - 1),
+            ..Impairment::default()
+        },
+        3,
+    );
+    let sender = network.bind_any().unwrap();
+    let receiver = network.bind_any().unwrap();
+    let sent = messages(10);
+
+    send_all(&sender, receiver.local_addr().unwrap(), &sent).await;
+    let mut reassembly = Reassembly::default();
+    let received = receive_all(&receiver, &mut reassembly).await;
+    // Só as mensagens de um pacote menor que o MTU passam.
+    let small: Vec<Message> = sent
+        .iter()
+        .filter(|x| METADATA_BYTES + UDP_METADATA_BYTES + x.content.len() < BUFFER_SIZE)
+        .cloned()
+        .collect();
+    assert_eq!(received.len(), small.len());
+    assert_intact(&received, &small);
+    assert!(network.stats().oversized > 0);
+
+    network.set_impairment(Impairment {
+        mtu: Some(BUFFER_SIZE),
+        ..Impairment::default()
+    });
+    send_all(&sender, receiver.local_addr().unwrap(), &sent).await;
+    let mut reassembly = Reassembly::default();
+    let received = receive_all(&receiver, &mut reassembly).await;
+    assert_eq!(received.len(), sent.len());
+    assert_intact(&received, &sent);
+}
+
+/// Servidor com o UDP na rede simulada, alice por TCP e bob pela rede simulada.
+async fn lossy_chat(network: &SimulatedNetwork) -> (common::RunningServer, TcpClient, UdpClient) {
+    let server_socket = network.bind_any().unwrap();
+    let running =
+        common::start_server_with_udp(ServerConfig::default(), Box::new(server_socket)).await;
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+    let server_addr: SocketAddr = (Ipv4Addr::LOCALHOST, running.udp_port).into();
+    let bob = time::timeout(
+        Duration::from_secs(5),
+        UdpClient::connect(
+            Box::new(network.bind_any().unwrap()),
+            server_addr,
+            &common::client_config(&running, "bob", Some(Protocol::UDP)),
+        ),
+    )
+    .await
+    .expect("conexão não concluída")
+    .unwrap();
+    (running, alice, bob)
+}
+
+/// Mensagens que chegarem ao client até ele ficar em silêncio por `QUIET`.
+async fn drain<C: Client>(client: &C) -> Vec<Message> {
+    let mut received = Vec::new();
+    while let Ok(message) = time::timeout(QUIET, client.listen()).await {
+        received.push(message.unwrap());
+    }
+    received
+}
+
+#[tokio::test]
+async fn udp_client_and_server_agree_over_a_noisy_network() {
+    let network = SimulatedNetwork::new(
+        Impairment {
+            duplicate_rate: 0.3,
+            jitter: Duration::from_millis(15),
+            mtu: Some(BUFFER_SIZE),
+            ..Impairment::default()
+        },
+        4,
+    );
+    let (_running, alice, bob) = lossy_chat(&network).await;
+
+    let mut sent = Vec::new();
+    for key in 1..=20u16 {
+        let content: Vec<u8> = (0..usize::from(key) * 300).map(|i| i as u8).collect();
+        let message = Message::new_file(key, alice.get_id(), content, None, None);
+        bob.send(message.clone()).await.unwrap();
+        sent.push(message);
+    }
+
+    let received: Vec<Message> = drain(&alice)
+        .await
+        .into_iter()
+        .filter(|x| x.metadata.message_type == MessageType::File)
+        .collect();
+    assert_eq!(received.len(), sent.len());
+    for message in &received {
+        let original = &sent[usize::from(message.metadata.key) - 1];
+        assert_eq!(message.metadata.receiver_id, bob.get_id());
+        assert_eq!(message.content, original.content);
+    }
+    let mut confirmed: Vec<u16> = drain(&bob)
+        .await
+        .into_iter()
+        .filter(|x| x.metadata.message_type == MessageType::Success)
+        .map(|x| x.metadata.key)
+        .collect();
+    confirmed.sort();
+    assert_eq!(confirmed, (1..=20).collect::<Vec<u16>>());
+
+    let file: Vec<u8> = (0..20_000u32).map(|i| (i % 253) as u8).collect();
+    alice
+        .send(Message::new_file(
+            99,
+            bob.get_id(),
+            file.clone(),
+            None,
+            None,
+        ))
+        .await
+        .unwrap();
+    let received = drain(&bob).await;
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].metadata.message_type, MessageType::File);
+    assert_eq!(received[0].content, file);
+    assert!(network.stats().duplicated > 0);
+}
+
+#[tokio::test]
+async fn losses_drop_messages_without_corrupting_the_session() {
+    let network = SimulatedNetwork::new(Impairment::default(), 5);
+    let (_running, alice, bob) = lossy_chat(&network).await;
+    network.set_impairment(Impairment {
+        drop_rate: 0.1,
+        jitter: Duration::from_millis(5),
+        ..Impairment::default()
+    });
+
+    let text = "x".repeat(1500);
+    for key in 1..=30u16 {
+        let message = Message::new_text(
+            key,
+            alice.get_id(),
+            format!("{0}{1}", key, text),
+            None,
+            None,
+        );
+        bob.send(message).await.unwrap();
+    }
+    let received: Vec<Message> = drain(&alice)
+        .await
+        .into_iter()
+        .filter(|x| x.metadata.message_type == MessageType::Text)
+        .collect();
+    assert!(network.stats().dropped > 0);
+    assert!(received.len() < 30);
+    let mut keys = Vec::new();
+    for message in &received {
+        let key = message.metadata.key;
+        assert!(!keys.contains(&key));
+        keys.push(key);
+        assert_eq!(message.content, format!("{0}{1}", key, text));
+    }
+
+    network.set_impairment(Impairment::default());
+    let key = bob.list_clients().await.unwrap();
+    let listed = wait_response(&bob, key).await.unwrap();
+    let clients: Vec<(u16, String)> = serde_json::from_slice(&listed.content).unwrap();
+    assert_eq!(clients.len(), 2);
+}
+
+#[tokio::test]
+async fn connection_requests_are_retransmitted_until_answered() {
+    let network = SimulatedNetwork::new(
+        Impairment {
+            drop_rate: 0.5,
+            ..Impairment::default()
+        },
+        11,
+    );
+    let (_running, _alice, bob) = lossy_chat(&network).await;
+    assert!(network.stats().dropped > 0);
+
+    network.set_impairment(Impairment::default());
+    let key = bob.list_clients().await.unwrap();
+    let listed = wait_response(&bob, key).await.unwrap();
+    let clients: Vec<(u16, String)> = serde_json::from_slice(&listed.content).unwrap();
+    assert_eq!(clients.len(), 2);
+}
+
+#[tokio::test]
+async fn unanswered_connections_give_up() {
+    let network = SimulatedNetwork::new(
+        Impairment {
+            drop_rate: 1.0,
+            ..Impairment::default()
+        },
+        7,
+    );
+    let server = network.bind_any().unwrap();
+    let config = ClientConfig {
+        name: "bob".to_string(),
+        transport: Protocol::UDP,
+        ..ClientConfig::default()
+    };
+    let connected = time::timeout(
+        Duration::from_secs(10),
+        UdpClient::connect(
+            Box::new(network.bind_any().unwrap()),
+            server.local_addr().unwrap(),
+            &config,
+        ),
+    )
+    .await
+    .expect("conexão não desistiu");
+    assert_eq!(
+        connected.err().unwrap().to_string(),
+        "Tempo esgotado aguardando a resposta do servidor"
+    );
+    // Enviado uma vez e reenviado com intervalos crescentes.
+    assert!(network.stats().sent >= 4);
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time;
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::accounts::Accounts;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::offline::{OfflineQueue, QueuedMessage};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::utilities::enums::MessageType;
+
+fn server_config(file: &common::AccountsFile) -> ServerConfig {
+    ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    }
+}
+
+fn alice_id(file: &common::AccountsFile) -> u16 {
+    Accounts::load(&file.path).unwrap().get("alice").unwrap().id
+}
+
+async fn send_text(client: &TcpClient, to: u16, text: &str) -> Result<Message, String> {
+    let key = client.send_text(text.to_string(), to).await.unwrap();
+    wait_response(client, key).await.map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn queued_messages_are_flushed_in_order_and_confirmed() {
+    let file = common::accounts_file();
+    let running = common::start_server(server_config(&file)).await;
+    let alice_id = alice_id(&file);
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+
+    let mut keys = Vec::new();
+    for text in ["primeira", "segunda"] {
+        let response = send_text(&bob, alice_id, text).await.unwrap();
+        assert_eq!(response.metadata.receiver_id, alice_id);
+        assert!(!response.content.is_empty());
+        keys.push(response.metadata.key);
+    }
+    assert_eq!(running.server.offline.lock().await.queued(alice_id), 2);
+
+    let alice = common::connect(running.tcp_port, "alice", Some("senha-da-alice")).await;
+    for text in ["primeira", "segunda"] {
+        let received = common::next_of(&alice, MessageType::Text).await;
+        assert_eq!(received.metadata.receiver_id, bob.get_id());
+        assert_eq!(received.content, text.as_bytes());
+    }
+    for key in keys {
+        let delivered = common::next_of(&bob, MessageType::Delivered).await;
+        assert_eq!(delivered.metadata.key, key);
+        assert_eq!(delivered.metadata.receiver_id, alice_id);
+    }
+    assert_eq!(running.server.offline.lock().await.queued(alice_id), 0);
+}
+
+#[tokio::test]
+async fn queue_limits_and_unknown_recipients_are_rejected() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        offline_max_messages: 2,
+        offline_max_bytes: 16,
+        ..server_config(&file)
+    })
+    .await;
+    let alice_id = alice_id(&file);
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+
+    let too_big = send_text(&bob, alice_id, "mensagem longa demais para a fila").await;
+    assert_eq!(
+        too_big.unwrap_err(),
+        format!("Fila offline do ID {0} cheia", alice_id)
+    );
+    send_text(&bob, alice_id, "um").await.unwrap();
+    send_text(&bob, alice_id, "dois").await.unwrap();
+    let full = send_text(&bob, alice_id, "três").await;
+    assert_eq!(
+        full.unwrap_err(),
+        format!("Fila offline do ID {0} cheia", alice_id)
+    );
+
+    let unknown = (1..)
+        .find(|id| *id != alice_id && *id != bob.get_id())
+        .unwrap();
+    let offline = send_text(&bob, unknown, "ninguém").await;
+    assert_eq!(
+        offline.unwrap_err(),
+        format!("Destinatário {0} offline", unknown)
+    );
+}
+
+#[tokio::test]
+async fn each_sender_has_a_quota_within_the_queue() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        offline_max_per_sender: 2,
+        ..server_config(&file)
+    })
+    .await;
+    let alice_id = alice_id(&file);
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+    let carol = common::connect(running.tcp_port, "carol", None).await;
+
+    send_text(&bob, alice_id, "um").await.unwrap();
+    send_text(&bob, alice_id, "dois").await.unwrap();
+    let exhausted = send_text(&bob, alice_id, "três").await;
+    assert_eq!(
+        exhausted.unwrap_err(),
+        format!(
+            "Cota do remetente na fila offline do ID {0} esgotada",
+            alice_id
+        )
+    );
+    send_text(&carol, alice_id, "de outro remetente")
+        .await
+        .unwrap();
+    assert_eq!(running.server.offline.lock().await.queued(alice_id), 3);
+}
+
+#[tokio::test]
+async fn expired_messages_are_dropped() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        offline_ttl: 0,
+        ..server_config(&file)
+    })
+    .await;
+    let alice_id = alice_id(&file);
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+
+    send_text(&bob, alice_id, "vai vencer").await.unwrap();
+    time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(running.server.offline.lock().await.queued(alice_id), 1);
+    assert!(running
+        .server
+        .offline
+        .lock()
+        .await
+        .take(alice_id)
+        .is_empty());
+}
+
+#[tokio::test]
+async fn queue_file_survives_restart() {
+    let file = common::accounts_file();
+    let config = ServerConfig {
+        offline_queue_file: Some(file.dir.join("offline.json")),
+        ..server_config(&file)
+    };
+    let alice_id = alice_id(&file);
+
+    let first = common::start_server(config.clone()).await;
+    let bob = common::connect(first.tcp_port, "bob", None).await;
+    send_text(&bob, alice_id, "depois do reinício")
+        .await
+        .unwrap();
+    drop(bob);
+    first.server.shutdown().await;
+
+    let second = common::start_server(config).await;
+    let alice = common::connect(second.tcp_port, "alice", Some("senha-da-alice")).await;
+    let received = common::next_of(&alice, MessageType::Text).await;
+    assert_eq!(received.content, "depois do reinício".as_bytes());
+}
+
+#[test]
+fn queue_file_stores_content_in_base64_and_reads_byte_lists() {
+    let file = common::accounts_file();
+    let config = ServerConfig {
+        offline_queue_file: Some(file.dir.join("offline.json")),
+        ..ServerConfig::default()
+    };
+    let mut queue = OfflineQueue::new(&config);
+    let message = QueuedMessage::new(7, 2, MessageType::Text, b"oi".to_vec());
+    queue.push(1, message).unwrap();
+    queue.snapshot().unwrap().write().unwrap();
+    assert!(queue.snapshot().is_none());
+    let saved = std::fs::read_to_string(file.dir.join("offline.json")).unwrap();
+    assert!(saved.contains(r#""content":"b2k=""#));
+
+    let queued_at = chrono::Utc::now().timestamp();
+    let legacy = format!(
+        r#"{{"1":[{{"key":7,"from":2,"message_type":1,"content":[111,105],"queued_at":{0}}}]}}"#,
+        queued_at
+    );
+    std::fs::write(file.dir.join("offline.json"), legacy).unwrap();
+    let mut queue = OfflineQueue::new(&config);
+    queue.load().unwrap();
+    assert_eq!(queue.take(1)[0].content, b"oi");
+}
@@ -0,0 +1,151 @@
+use bytes::BytesMut;
+use proptest::prelude::*;
+use proptest::sample::select;
+use tokio_util::codec::{Decoder, Encoder};
+
+use t1_lab_redes::config::{METADATA_BYTES, UDP_METADATA_BYTES};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::models::metadata::MsgMetadata;
+use t1_lab_redes::network::codec::MessageCodec;
+use t1_lab_redes::network::udp::{build_udp_message, fragment, Reassembly};
+use t1_lab_redes::utilities::enums::MessageType;
+
+const MAX_SIZE: u64 = 64 * 1024;
+
+fn message_types() -> Vec<MessageType> {
+    (0..=u8::MAX)
+        .filter_map(|value| MessageType::try_from(value).ok())
+        .collect()
+}
+
+prop_compose! {
+    fn message()(
+        key in any::<u16>(),
+        receiver_id in any::<u16>(),
+        message_type in select(message_types()),
+        content in prop::collection::vec(any::<u8>(), 0..4096),
+    ) -> Message {
+        let metadata = MsgMetadata::new(
+            key,
+            receiver_id,
+            message_type,
+            content.len() as u64,
+            None,
+            None,
+        );
+        Message::new(metadata, content)
+    }
+}
+
+fn assert_same(decoded: &Message, message: &Message) {
+    assert_eq!(decoded.metadata.key, message.metadata.key);
+    assert_eq!(decoded.metadata.receiver_id, message.metadata.receiver_id);
+    assert_eq!(decoded.metadata.message_type, message.metadata.message_type);
+    assert_eq!(
+        decoded.metadata.message_length,
+        message.metadata.message_length
+    );
+    assert_eq!(decoded.content, message.content);
+}
+
+#[test]
+fn every_message_type_is_covered() {
+    for message_type in message_types() {
+        assert_eq!(
+            MessageType::try_from(u8::from(message_type)),
+            Ok(message_type)
+        );
+    }
+    assert_eq!(message_types().len(), 21);
+}
+
+proptest! {
+    #[test]
+    fn codec_round_trip(messages in prop::collection::vec(message(), 1..8), chunk in 1usize..512) {
+        let mut codec = MessageCodec::new(MAX_SIZE);
+        let mut stream = BytesMut::new();
+        for message in &messages {
+            codec.encode(message.frame(), &mut stream).unwrap();
+        }
+        let mut buffer = BytesMut::new();
+        let mut decoded = Vec::new();
+        for piece in stream.chunks(chunk) {
+            buffer.extend_from_slice(piece);
+            while let Some(item) = codec.decode(&mut buffer).unwrap() {
+                decoded.push(item.unwrap());
+            }
+        }
+        prop_assert!(buffer.is_empty());
+        prop_assert_eq!(decoded.len(), messages.len());
+        for (decoded, message) in decoded.iter().zip(&messages) {
+            assert_same(decoded, message);
+        }
+    }
+
+    #[test]
+    fn udp_round_trip(
+        message in message(),
+        udp_id in any::<u16>(),
+        payload in 1usize..256,
+        order in any::<prop::sample::Index>(),
+        duplicate in any::<prop::sample::Index>(),
+        position in any::<prop::sample::Index>(),
+    ) {
+        let packet_size = METADATA_BYTES + UDP_METADATA_BYTES + payload;
+        let mut packets = fragment(&message, udp_id, packet_size).unwrap();
+        // Embaralha os pacotes e repete um deles, inclusive depois do último,
+        // como a rede pode fazer.
+        let rotation = order.index(packets.len());
+        packets.rotate_left(rotation);
+        packets.reverse();
+        let repeated = packets[duplicate.index(packets.len())].clone();
+        let position = position.index(packets.len() + 1);
+        packets.insert(position, repeated);
+
+        let mut reassembly = Reassembly::default();
+        let mut rebuilt = Vec::new();
+        for packet in packets {
+            if let Some(message) = build_udp_message(packet, &mut reassembly, MAX_SIZE).unwrap() {
+                rebuilt.push(message);
+            }
+        }
+        prop_assert_eq!(reassembly.pending_messages(), 0);
+        prop_assert_eq!(rebuilt.len(), 1);
+        assert_same(&rebuilt[0], &message);
+        prop_assert_eq!(rebuilt[0].metadata.udp_id, None);
+    }
+
+    #[test]
+    fn arbitrary_headers_do_not_panic(data in prop::collection::vec(any::<u8>(), 0..64)) {
+        for is_udp in [false, true] {
+            if let Ok(metadata) = MsgMetadata::deserialize(&data, is_udp) {
+                let serialized = metadata.serialize();
+                prop_assert_eq!(&serialized[..], &data[..serialized.len()]);
+            }
+        }
+        prop_assert_eq!(MsgMetadata::peek_frame(&data).is_some(), data.len() >= METADATA_BYTES);
+    }
+
+    #[test]
+    fn arbitrary_streams_do_not_panic(data in prop::collection::vec(any::<u8>(), 0..2048)) {
+        let mut codec = MessageCodec::new(256);
+        let mut buffer = BytesMut::from(&data[..]);
+        while let Ok(Some(item)) = codec.decode(&mut buffer) {
+            if let Ok(message) = item {
+                prop_assert_eq!(message.content.len() as u64, message.metadata.message_length);
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_datagrams_do_not_panic(
+        datagrams in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..64), 0..16),
+    ) {
+        let mut reassembly = Reassembly::default();
+        for datagram in datagrams {
+            if let Ok(Some(message)) = build_udp_message(datagram, &mut reassembly, 256) {
+                prop_assert_eq!(message.content.len() as u64, message.metadata.message_length);
+            }
+        }
+    }
+}
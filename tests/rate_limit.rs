@@ -0,0 +1,217 @@
+mod common;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::time;
+
+use t1_lab_redes::config::{ServerConfig, BUFFER_SIZE, MAX_DATAGRAM_SIZE, MAX_MESSAGE_SIZE};
+use t1_lab_redes::models::credentials::Credentials;
+use t1_lab_redes::models::error::{ErrorBody, ErrorCode};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::network::udp::{build_udp_message, fragment, Reassembly};
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol, RateLimitAction};
+
+/// Envia `count` pedidos de lista de usuários de uma vez e retorna, em ordem,
+/// os erros de limite recebidos em resposta e quantos pedidos foram atendidos.
+async fn flood<C: Client>(client: &C, count: usize) -> (Vec<String>, usize) {
+    let mut keys = HashSet::new();
+    for _ in 0..count {
+        let key = Message::generate_key();
+        keys.insert(key);
+        client
+            .send(Message::new_list_clients_request(key))
+            .await
+            .unwrap();
+    }
+    let mut limited = Vec::new();
+    let mut answered = 0;
+    time::timeout(Duration::from_secs(5), async {
+        while !keys.is_empty() {
+            let message = client.listen().await.unwrap();
+            if !keys.remove(&message.metadata.key) {
+                continue;
+            }
+            if message.metadata.message_type == MessageType::Error {
+                let error = ErrorBody::decode(&message.content).unwrap();
+                assert_eq!(error.code, ErrorCode::RateLimited);
+                limited.push(error.message);
+            } else {
+                answered += 1;
+            }
+        }
+    })
+    .await
+    .expect("respostas não recebidas");
+    (limited, answered)
+}
+
+#[tokio::test]
+async fn sessions_over_the_limit_have_messages_dropped_until_refill() {
+    let running = common::start_server(ServerConfig {
+        rate_messages: 3,
+        rate_burst: 1,
+        ..ServerConfig::default()
+    })
+    .await;
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+
+    let (limited, answered) = flood(&alice, 10).await;
+    assert!(answered <= 3);
+    assert_eq!(limited.len(), 10 - answered);
+    assert!(limited
+        .iter()
+        .all(|reason| reason == "Limite de mensagens da sessão excedido"));
+
+    time::sleep(Duration::from_millis(1100)).await;
+    let key = Message::generate_key();
+    alice
+        .send(Message::new_list_clients_request(key))
+        .await
+        .unwrap();
+    wait_response(&alice, key).await.unwrap();
+}
+
+#[tokio::test]
+async fn muted_udp_sessions_are_silenced() {
+    let running = common::start_server(ServerConfig {
+        rate_messages: 2,
+        rate_burst: 1,
+        rate_action: RateLimitAction::Mute,
+        rate_mute_secs: 60,
+        ..ServerConfig::default()
+    })
+    .await;
+    let bob = UdpClient::new(&common::client_config(&running, "bob", Some(Protocol::UDP)))
+        .await
+        .unwrap();
+
+    let (limited, _) = flood(&bob, 5).await;
+    assert_eq!(
+        limited[0],
+        "Limite de mensagens da sessão excedido: silenciado por 60s"
+    );
+    assert!(limited[1..]
+        .iter()
+        .all(|reason| reason.starts_with("Sessão silenciada")));
+
+    time::sleep(Duration::from_millis(1100)).await;
+    let (limited, answered) = flood(&bob, 1).await;
+    assert_eq!((limited.len(), answered), (1, 0));
+}
+
+#[tokio::test]
+async fn flooding_sessions_are_disconnected() {
+    let running = common::start_server(ServerConfig {
+        rate_messages: 1,
+        rate_burst: 1,
+        rate_action: RateLimitAction::Disconnect,
+        ..ServerConfig::default()
+    })
+    .await;
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+    let alice_id = alice.get_id();
+
+    let key = Message::generate_key();
+    alice
+        .send(Message::new_list_clients_request(key))
+        .await
+        .unwrap();
+    let error = time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = alice.listen().await.unwrap();
+            if message.metadata.message_type == MessageType::Error {
+                return ErrorBody::decode(&message.content).unwrap();
+            }
+        }
+    })
+    .await
+    .expect("erro não recebido");
+    assert_eq!(error.code, ErrorCode::RateLimited);
+
+    time::timeout(Duration::from_secs(5), async {
+        while running
+            .server
+            .sessions
+            .read()
+            .await
+            .contains_user(&alice_id)
+        {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("client não foi desconectado");
+}
+
+#[tokio::test]
+async fn new_sessions_are_charged_to_the_source_ip() {
+    let running = common::start_server(ServerConfig {
+        ip_rate_messages: 2,
+        rate_burst: 1,
+        ..ServerConfig::default()
+    })
+    .await;
+    // A sessão e o pedido de conexão consomem o limite do IP.
+    TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+
+    let refused = time::timeout(
+        Duration::from_secs(5),
+        TcpClient::new(&common::client_config(&running, "bob", None)),
+    )
+    .await
+    .expect("conexão não foi encerrada");
+    assert!(refused.is_err());
+}
+
+#[tokio::test]
+async fn udp_fragments_are_charged_before_reassembly() {
+    let running = common::start_server(ServerConfig {
+        rate_bytes: 4096,
+        rate_burst: 1,
+        ..ServerConfig::default()
+    })
+    .await;
+    let server = ("127.0.0.1", running.udp_port);
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let connection =
+        Message::new_connection_request(1, &Credentials::anonymous("mallory".to_string()));
+    for packet in fragment(&connection, 0, BUFFER_SIZE).unwrap() {
+        socket.send_to(&packet, server).await.unwrap();
+    }
+    // Só o primeiro pacote de cada mensagem: nenhuma delas chega a se completar.
+    let large = Message::new_file(2, 1, vec![0; 60_000], None, None);
+    for udp_id in 1..=20 {
+        let packets = fragment(&large, udp_id, BUFFER_SIZE).unwrap();
+        socket.send_to(&packets[0], server).await.unwrap();
+    }
+
+    let mut reassembly = Reassembly::default();
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+    let error = time::timeout(Duration::from_secs(5), async {
+        loop {
+            let len = socket.recv(&mut buf).await.unwrap();
+            let received =
+                build_udp_message(buf[..len].to_vec(), &mut reassembly, MAX_MESSAGE_SIZE);
+            if let Ok(Some(message)) = received {
+                if message.metadata.message_type == MessageType::Error {
+                    return ErrorBody::decode(&message.content).unwrap();
+                }
+            }
+        }
+    })
+    .await
+    .expect("erro não recebido");
+    assert_eq!(error.code, ErrorCode::RateLimited);
+    assert_eq!(error.message, "Limite de bytes da sessão excedido");
+}
@@ -0,0 +1,422 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time;
+
+use t1_lab_redes::config::{ClientConfig, ServerConfig};
+use t1_lab_redes::models::error::{ErrorBody, ErrorCode};
+use t1_lab_redes::models::history::{HistoryEntry, HistoryRequest};
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::models::room::{RoomAction, RoomInfo, RoomJoin};
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+async fn request<C: Client>(
+    client: &C,
+    build: impl FnOnce(u16) -> Message,
+) -> Result<Message, String> {
+    let key = Message::generate_key();
+    client.send(build(key)).await.unwrap();
+    wait_response(client, key).await.map_err(|e| e.to_string())
+}
+
+async fn create<C: Client>(client: &C, name: &str) -> RoomInfo {
+    let response = request(client, |key| Message::new_create_room_request(key, name))
+        .await
+        .unwrap();
+    serde_json::from_slice(&response.content).unwrap()
+}
+
+async fn join<C: Client>(client: &C, name: &str) -> Result<RoomInfo, String> {
+    join_with(client, name, None).await
+}
+
+async fn join_with<C: Client>(
+    client: &C,
+    name: &str,
+    password: Option<&str>,
+) -> Result<RoomInfo, String> {
+    let join = RoomJoin {
+        name: name.to_string(),
+        password: password.map(str::to_string),
+    };
+    let response = request(client, |key| Message::new_join_room_request(key, 0, &join)).await?;
+    Ok(serde_json::from_slice(&response.content).unwrap())
+}
+
+async fn moderate<C: Client>(
+    client: &C,
+    room: u16,
+    action: RoomAction,
+) -> Result<RoomInfo, String> {
+    let response = request(client, |key| {
+        Message::new_moderate_room_request(key, room, &action)
+    })
+    .await?;
+    Ok(serde_json::from_slice(&response.content).unwrap())
+}
+
+/// Código do `Error` recebido em resposta à mensagem.
+async fn error_code<C: Client>(client: &C, build: impl FnOnce(u16) -> Message) -> ErrorCode {
+    let key = Message::generate_key();
+    client.send(build(key)).await.unwrap();
+    time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = client.listen().await.unwrap();
+            if message.metadata.key == key {
+                assert_eq!(message.metadata.message_type, MessageType::Error);
+                return ErrorBody::decode(&message.content).unwrap().code;
+            }
+        }
+    })
+    .await
+    .expect("resposta não recebida")
+}
+
+/// Próxima mensagem de sala recebida: (sala, remetente, texto).
+async fn next_room_message<C: Client>(client: &C) -> (u16, u16, String) {
+    time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = client.listen().await.unwrap();
+            if message.metadata.message_type == MessageType::RoomMessage {
+                let sender = u16::from_le_bytes([message.content[0], message.content[1]]);
+                let text = String::from_utf8_lossy(&message.content[2..]).to_string();
+                return (message.metadata.receiver_id, sender, text);
+            }
+        }
+    })
+    .await
+    .expect("mensagem de sala não recebida")
+}
+
+#[tokio::test]
+async fn room_messages_fan_out_over_tcp_and_udp() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+    let bob = UdpClient::new(&common::client_config(&running, "bob", Some(Protocol::UDP)))
+        .await
+        .unwrap();
+    let carol = TcpClient::new(&common::client_config(&running, "carol", None))
+        .await
+        .unwrap();
+
+    let room = create(&alice, "projeto").await;
+    assert_eq!(room.members, [alice.get_id()]);
+    let joined = join(&bob, "projeto").await.unwrap();
+    assert_eq!(joined.id, room.id);
+    assert_eq!(joined.members.len(), 2);
+    assert_eq!(
+        next_room_message(&alice).await,
+        (room.id, 0, "bob entrou na sala".to_string())
+    );
+
+    request(&alice, |key| {
+        Message::new_room_message(key, room.id, "bom dia, projeto".to_string())
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        next_room_message(&bob).await,
+        (room.id, alice.get_id(), "bom dia, projeto".to_string())
+    );
+
+    let outsider = request(&carol, |key| {
+        Message::new_room_message(key, room.id, "posso?".to_string())
+    })
+    .await;
+    assert_eq!(outsider.unwrap_err(), "Você não está na sala projeto");
+
+    let listed = request(&carol, Message::new_list_rooms_request)
+        .await
+        .unwrap();
+    let rooms: Vec<RoomInfo> = serde_json::from_slice(&listed.content).unwrap();
+    assert_eq!(rooms, [joined]);
+}
+
+#[tokio::test]
+async fn rooms_close_when_the_last_member_leaves() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+
+    let room = create(&alice, "geral").await;
+    let duplicate = request(&bob, |key| Message::new_create_room_request(key, "geral")).await;
+    assert_eq!(duplicate.unwrap_err(), "Sala já existente: geral");
+    join(&bob, "geral").await.unwrap();
+
+    let left = request(&alice, |key| {
+        Message::new_leave_room_request(key, room.id, "")
+    })
+    .await
+    .unwrap();
+    assert_eq!(left.metadata.receiver_id, room.id);
+    assert_eq!(
+        next_room_message(&bob).await,
+        (room.id, 0, "alice saiu da sala".to_string())
+    );
+
+    let key = bob.disconnect().await.unwrap();
+    wait_response(&bob, key).await.unwrap();
+    time::timeout(Duration::from_secs(5), async {
+        while !running.server.rooms.read().await.is_empty() {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("sala não foi fechada");
+    assert_eq!(
+        join(&alice, "geral").await.unwrap_err(),
+        "Sala geral não encontrada"
+    );
+}
+
+#[tokio::test]
+async fn operators_moderate_and_the_server_enforces_it() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+    let carol = TcpClient::new(&common::client_config(&running, "carol", None))
+        .await
+        .unwrap();
+    let room = create(&alice, "equipe").await;
+    join(&bob, "equipe").await.unwrap();
+
+    let kick_owner = RoomAction::Kick {
+        user: alice.get_id(),
+    };
+    let code = error_code(&bob, |key| {
+        Message::new_moderate_room_request(key, room.id, &kick_owner)
+    })
+    .await;
+    assert_eq!(code, ErrorCode::NotOperator);
+
+    let topic = Some("entrega na sexta".to_string());
+    let info = moderate(&alice, room.id, RoomAction::Topic { topic })
+        .await
+        .unwrap();
+    assert_eq!(info.topic.as_deref(), Some("entrega na sexta"));
+    loop {
+        let (_, sender, text) = next_room_message(&bob).await;
+        if text.contains("tópico") {
+            assert_eq!(
+                (sender, text.as_str()),
+                (0, "alice mudou o tópico: entrega na sexta")
+            );
+            break;
+        }
+    }
+
+    let bob_id = bob.get_id();
+    moderate(&alice, room.id, RoomAction::Mute { user: bob_id })
+        .await
+        .unwrap();
+    let code = error_code(&bob, |key| {
+        Message::new_room_message(key, room.id, "oi".to_string())
+    })
+    .await;
+    assert_eq!(code, ErrorCode::Muted);
+
+    moderate(&alice, room.id, RoomAction::Kick { user: bob_id })
+        .await
+        .unwrap();
+    let removed = common::next_of(&bob, MessageType::LeaveRoom).await;
+    assert_eq!(removed.metadata.receiver_id, room.id);
+    assert_eq!(
+        removed.content,
+        "bob foi expulso por alice na sala equipe".as_bytes()
+    );
+
+    let password = Some("segredo".to_string());
+    let info = moderate(&alice, room.id, RoomAction::Password { password })
+        .await
+        .unwrap();
+    assert!(info.protected);
+    let join_code = error_code(&bob, |key| {
+        Message::new_join_room_request(key, room.id, &RoomJoin::default())
+    })
+    .await;
+    assert_eq!(join_code, ErrorCode::WrongPassword);
+    join_with(&bob, "equipe", Some("segredo")).await.unwrap();
+
+    moderate(&alice, room.id, RoomAction::InviteOnly { enabled: true })
+        .await
+        .unwrap();
+    assert_eq!(
+        join_with(&carol, "equipe", Some("segredo"))
+            .await
+            .unwrap_err(),
+        "Sala equipe exige convite"
+    );
+    let carol_id = carol.get_id();
+    moderate(&alice, room.id, RoomAction::Invite { user: carol_id })
+        .await
+        .unwrap();
+    let invitation = common::next_of(&carol, MessageType::Text).await;
+    assert_eq!(invitation.metadata.receiver_id, 0);
+    assert_eq!(
+        invitation.content,
+        "alice convidou você para a sala equipe".as_bytes()
+    );
+    let info = join(&carol, "equipe").await.unwrap();
+    assert_eq!(info.members.len(), 3);
+}
+
+#[tokio::test]
+async fn bans_follow_the_account_and_ownership_passes_on() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    })
+    .await;
+    let alice_config = ClientConfig {
+        password: Some("senha-da-alice".to_string()),
+        ..common::client_config(&running, "alice", None)
+    };
+    let alice = TcpClient::new(&alice_config).await.unwrap();
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+    let carol = TcpClient::new(&common::client_config(&running, "carol", None))
+        .await
+        .unwrap();
+    let room = create(&bob, "clube").await;
+    join(&alice, "clube").await.unwrap();
+    join(&carol, "clube").await.unwrap();
+
+    let info = moderate(
+        &bob,
+        room.id,
+        RoomAction::Ban {
+            user: alice.get_id(),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!info.members.contains(&alice.get_id()));
+    common::next_of(&alice, MessageType::LeaveRoom).await;
+
+    let second_session = TcpClient::new(&alice_config).await.unwrap();
+    let code = error_code(&second_session, |key| {
+        Message::new_join_room_request(key, room.id, &RoomJoin::default())
+    })
+    .await;
+    assert_eq!(code, ErrorCode::Banned);
+    moderate(
+        &bob,
+        room.id,
+        RoomAction::Unban {
+            identity: "conta:alice".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    join(&second_session, "clube").await.unwrap();
+
+    moderate(
+        &bob,
+        room.id,
+        RoomAction::Op {
+            user: carol.get_id(),
+        },
+    )
+    .await
+    .unwrap();
+    request(&bob, |key| {
+        Message::new_leave_room_request(key, room.id, "")
+    })
+    .await
+    .unwrap();
+    loop {
+        let (_, sender, text) = next_room_message(&alice).await;
+        if text == "carol agora é dono da sala" {
+            assert_eq!(sender, 0);
+            break;
+        }
+    }
+    let room = running
+        .server
+        .rooms
+        .read()
+        .await
+        .get(&room.id)
+        .unwrap()
+        .info();
+    assert_eq!(room.owner, carol.get_id());
+}
+
+#[tokio::test]
+async fn room_messages_are_recorded_in_the_room_history() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    })
+    .await;
+    let alice = TcpClient::new(&ClientConfig {
+        password: Some("senha-da-alice".to_string()),
+        ..common::client_config(&running, "alice", None)
+    })
+    .await
+    .unwrap();
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+    let room = create(&bob, "projeto").await;
+    for text in ["bom dia", "reunião às 10"] {
+        request(&bob, |key| {
+            Message::new_room_message(key, room.id, text.to_string())
+        })
+        .await
+        .unwrap();
+    }
+    let room_history = HistoryRequest {
+        room: Some(room.id),
+        ..HistoryRequest::default()
+    };
+    let code = error_code(&alice, |key| {
+        Message::new_history_request(key, 0, &room_history)
+    })
+    .await;
+    assert_eq!(code, ErrorCode::NotInRoom);
+
+    join(&alice, "projeto").await.unwrap();
+    let response = request(&alice, |key| {
+        Message::new_history_request(key, 0, &room_history)
+    })
+    .await
+    .unwrap();
+    let entries: Vec<HistoryEntry> = serde_json::from_slice(&response.content).unwrap();
+    let recorded: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.from, entry.to, entry.room, entry.content.as_deref()))
+        .collect();
+    assert_eq!(
+        recorded,
+        [
+            (bob.get_id(), room.id, Some(room.id), Some("bom dia")),
+            (bob.get_id(), room.id, Some(room.id), Some("reunião às 10")),
+        ]
+    );
+
+    let direct = request(&alice, |key| {
+        Message::new_history_request(key, 0, &HistoryRequest::default())
+    })
+    .await
+    .unwrap();
+    let entries: Vec<HistoryEntry> = serde_json::from_slice(&direct.content).unwrap();
+    assert!(entries.is_empty());
+}
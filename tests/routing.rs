@@ -0,0 +1,354 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time;
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::client::Client;
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+/// Três clients no mesmo servidor: alice e carol por TCP, bob por UDP.
+struct Chat {
+    _running: common::RunningServer,
+    alice: TcpClient,
+    bob: UdpClient,
+    carol: TcpClient,
+}
+
+/// Conecta os três clients, conferindo o aviso de presença que cada um
+/// recebe da entrada dos seguintes.
+async fn chat() -> Chat {
+    let running = common::start_server(ServerConfig::default()).await;
+    let alice = TcpClient::new(&common::client_config(
+        &running,
+        "alice",
+        Some(Protocol::TCP),
+    ))
+    .await
+    .unwrap();
+    let bob = UdpClient::new(&common::client_config(&running, "bob", Some(Protocol::UDP)))
+        .await
+        .unwrap();
+    assert_presence(
+        &next(&alice).await,
+        alice.get_id(),
+        bob.get_id(),
+        "bob",
+        true,
+    );
+    let carol = TcpClient::new(&common::client_config(
+        &running,
+        "carol",
+        Some(Protocol::TCP),
+    ))
+    .await
+    .unwrap();
+    assert_presence(
+        &next(&alice).await,
+        alice.get_id(),
+        carol.get_id(),
+        "carol",
+        true,
+    );
+    assert_presence(
+        &next(&bob).await,
+        bob.get_id(),
+        carol.get_id(),
+        "carol",
+        true,
+    );
+    Chat {
+        _running: running,
+        alice,
+        bob,
+        carol,
+    }
+}
+
+async fn next<C: Client>(client: &C) -> Message {
+    time::timeout(Duration::from_secs(5), client.listen())
+        .await
+        .expect("mensagem não recebida")
+        .unwrap()
+}
+
+fn assert_message(
+    message: &Message,
+    message_type: MessageType,
+    key: u16,
+    receiver_id: u16,
+    content: &[u8],
+) {
+    assert_eq!(message.metadata.message_type, message_type);
+    assert_eq!(message.metadata.key, key);
+    assert_eq!(message.metadata.receiver_id, receiver_id);
+    assert_eq!(message.metadata.message_length, content.len() as u64);
+    assert_eq!(message.content, content);
+}
+
+fn assert_presence(message: &Message, receiver_id: u16, id: u16, name: &str, online: bool) {
+    let content = serde_json::to_vec(&(id, name, online)).unwrap();
+    assert_message(message, MessageType::Presence, 0, receiver_id, &content);
+}
+
+/// Pede a lista de clients e confere que a resposta é a próxima mensagem
+/// recebida, ou seja, que nada mais foi encaminhado ao client até aqui.
+/// Retorna a lista ordenada pelo id.
+async fn list_next<C: Client>(client: &C) -> Vec<(u16, String)> {
+    let key = client.list_clients().await.unwrap();
+    let response = next(client).await;
+    assert_eq!(response.metadata.message_type, MessageType::Success);
+    assert_eq!(response.metadata.key, key);
+    assert_eq!(response.metadata.receiver_id, client.get_id());
+    let mut clients: Vec<(u16, String)> = serde_json::from_slice(&response.content).unwrap();
+    clients.sort();
+    clients
+}
+
+fn named(clients: &[(u16, &str)]) -> Vec<(u16, String)> {
+    let mut clients: Vec<(u16, String)> = clients
+        .iter()
+        .map(|(id, name)| (*id, name.to_string()))
+        .collect();
+    clients.sort();
+    clients
+}
+
+#[tokio::test]
+async fn clients_on_both_transports_are_listed() {
+    let chat = chat().await;
+    let (alice, bob, carol) = (chat.alice.get_id(), chat.bob.get_id(), chat.carol.get_id());
+    assert_ne!(alice, bob);
+    assert_ne!(bob, carol);
+    let expected = named(&[(alice, "alice"), (bob, "bob"), (carol, "carol")]);
+    assert_eq!(list_next(&chat.alice).await, expected);
+    assert_eq!(list_next(&chat.bob).await, expected);
+    assert_eq!(list_next(&chat.carol).await, expected);
+}
+
+#[tokio::test]
+async fn renames_are_confirmed_and_announced() {
+    let chat = chat().await;
+    let (alice, bob, carol) = (chat.alice.get_id(), chat.bob.get_id(), chat.carol.get_id());
+
+    let key = chat.bob.set_name("roberto".to_string()).await.unwrap();
+    assert_message(&next(&chat.bob).await, MessageType::Success, key, bob, b"");
+    assert_presence(&next(&chat.alice).await, alice, bob, "roberto", true);
+    assert_presence(&next(&chat.carol).await, carol, bob, "roberto", true);
+
+    let key = chat.alice.set_name("carol".to_string()).await.unwrap();
+    assert_message(
+        &next(&chat.alice).await,
+        MessageType::Error,
+        key,
+        alice,
+        "Nome já existente: carol".as_bytes(),
+    );
+
+    let expected = named(&[(alice, "alice"), (bob, "roberto"), (carol, "carol")]);
+    assert_eq!(list_next(&chat.alice).await, expected);
+    assert_eq!(list_next(&chat.bob).await, expected);
+    assert_eq!(list_next(&chat.carol).await, expected);
+}
+
+#[tokio::test]
+async fn direct_messages_reach_only_the_recipient() {
+    let chat = chat().await;
+    let (alice, bob, carol) = (chat.alice.get_id(), chat.bob.get_id(), chat.carol.get_id());
+
+    let key = chat
+        .alice
+        .send_text("oi, bob".to_string(), bob)
+        .await
+        .unwrap();
+    assert_message(
+        &next(&chat.bob).await,
+        MessageType::Text,
+        key,
+        alice,
+        b"oi, bob",
+    );
+    assert_message(
+        &next(&chat.alice).await,
+        MessageType::Success,
+        key,
+        bob,
+        b"",
+    );
+
+    let key = chat
+        .bob
+        .send_text("oi, carol".to_string(), carol)
+        .await
+        .unwrap();
+    assert_message(
+        &next(&chat.carol).await,
+        MessageType::Text,
+        key,
+        bob,
+        b"oi, carol",
+    );
+    assert_message(
+        &next(&chat.bob).await,
+        MessageType::Success,
+        key,
+        carol,
+        b"",
+    );
+
+    let key = chat
+        .carol
+        .send_text("ninguém".to_string(), 999)
+        .await
+        .unwrap();
+    assert_message(
+        &next(&chat.carol).await,
+        MessageType::Error,
+        key,
+        carol,
+        "Destinatário 999 offline".as_bytes(),
+    );
+
+    list_next(&chat.alice).await;
+    list_next(&chat.bob).await;
+    list_next(&chat.carol).await;
+}
+
+#[tokio::test]
+async fn broadcasts_reach_every_other_client() {
+    let chat = chat().await;
+    let (alice, bob) = (chat.alice.get_id(), chat.bob.get_id());
+
+    let key = Message::generate_key();
+    chat.bob
+        .send(Message::new_broadcast(key, "olá a todos".to_string()))
+        .await
+        .unwrap();
+    let text = "olá a todos".as_bytes();
+    assert_message(
+        &next(&chat.alice).await,
+        MessageType::Broadcast,
+        key,
+        bob,
+        text,
+    );
+    assert_message(
+        &next(&chat.carol).await,
+        MessageType::Broadcast,
+        key,
+        bob,
+        text,
+    );
+    assert_message(&next(&chat.bob).await, MessageType::Success, key, bob, b"");
+
+    let key = Message::generate_key();
+    chat.alice
+        .send(Message::new_broadcast(key, "de volta".to_string()))
+        .await
+        .unwrap();
+    assert_message(
+        &next(&chat.bob).await,
+        MessageType::Broadcast,
+        key,
+        alice,
+        b"de volta",
+    );
+    assert_message(
+        &next(&chat.carol).await,
+        MessageType::Broadcast,
+        key,
+        alice,
+        b"de volta",
+    );
+    assert_message(
+        &next(&chat.alice).await,
+        MessageType::Success,
+        key,
+        alice,
+        b"",
+    );
+
+    list_next(&chat.alice).await;
+    list_next(&chat.bob).await;
+    list_next(&chat.carol).await;
+}
+
+#[tokio::test]
+async fn files_cross_transports_intact() {
+    let chat = chat().await;
+    let (alice, bob) = (chat.alice.get_id(), chat.bob.get_id());
+    let file: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+    // O arquivo é maior que um datagrama e segue fragmentado entre bob e o servidor.
+    let key = Message::generate_key();
+    chat.bob
+        .send(Message::new_file(key, alice, file.clone(), None, None))
+        .await
+        .unwrap();
+    assert_message(&next(&chat.alice).await, MessageType::File, key, bob, &file);
+    assert_message(
+        &next(&chat.bob).await,
+        MessageType::Success,
+        key,
+        alice,
+        b"",
+    );
+
+    let key = Message::generate_key();
+    chat.alice
+        .send(Message::new_file(key, bob, file.clone(), None, None))
+        .await
+        .unwrap();
+    assert_message(&next(&chat.bob).await, MessageType::File, key, alice, &file);
+    assert_message(
+        &next(&chat.alice).await,
+        MessageType::Success,
+        key,
+        bob,
+        b"",
+    );
+
+    list_next(&chat.alice).await;
+    list_next(&chat.bob).await;
+    list_next(&chat.carol).await;
+}
+
+#[tokio::test]
+async fn disconnects_are_confirmed_and_announced() {
+    let chat = chat().await;
+    let (alice, bob, carol) = (chat.alice.get_id(), chat.bob.get_id(), chat.carol.get_id());
+
+    let key = chat.carol.disconnect().await.unwrap();
+    assert_message(
+        &next(&chat.carol).await,
+        MessageType::Success,
+        key,
+        carol,
+        b"",
+    );
+    assert_presence(&next(&chat.alice).await, alice, carol, "carol", false);
+    assert_presence(&next(&chat.bob).await, bob, carol, "carol", false);
+
+    let key = chat.bob.disconnect().await.unwrap();
+    assert_message(&next(&chat.bob).await, MessageType::Success, key, bob, b"");
+    assert_presence(&next(&chat.alice).await, alice, bob, "bob", false);
+
+    let key = chat
+        .alice
+        .send_text("ainda aí?".to_string(), bob)
+        .await
+        .unwrap();
+    let reason = format!("Destinatário {0} offline", bob);
+    assert_message(
+        &next(&chat.alice).await,
+        MessageType::Error,
+        key,
+        alice,
+        reason.as_bytes(),
+    );
+    assert_eq!(list_next(&chat.alice).await, named(&[(alice, "alice")]));
+}
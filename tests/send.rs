@@ -0,0 +1,87 @@
+mod common;
+
+use std::net::TcpListener;
+use std::process::Output;
+
+use tokio::process::Command;
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::utilities::enums::MessageType;
+
+/// Executa `t1-lab-redes send` com `args`, sem as variáveis T1_ do ambiente.
+async fn send(args: &[&str]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_t1-lab-redes"));
+    for (name, _) in std::env::vars().filter(|(name, _)| name.starts_with("T1_")) {
+        command.env_remove(name);
+    }
+    command
+        .arg("send")
+        .args(["--host", "127.0.0.1", "--timeout", "5"])
+        .args(args)
+        .output()
+        .await
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[tokio::test]
+async fn delivered_messages_exit_with_zero() {
+    let running = common::start_server(ServerConfig::default()).await;
+    let bob = common::connect(running.tcp_port, "bob", None).await;
+
+    let port = running.tcp_port.to_string();
+    let output = send(&["--port", &port, "--to", "bob", "--text", "oi"]).await;
+    assert_eq!(output.status.code(), Some(0), "{0}", stderr(&output));
+    let received = common::next_of(&bob, MessageType::Text).await;
+    assert_eq!(received.content, "oi".as_bytes());
+}
+
+#[tokio::test]
+async fn refused_connections_exit_with_three() {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+        .to_string();
+    let output = send(&["--port", &port, "--to", "bob", "--text", "oi"]).await;
+    assert_eq!(output.status.code(), Some(3), "{0}", stderr(&output));
+    assert!(stderr(&output).contains("Falha de conexão"));
+}
+
+#[tokio::test]
+async fn server_errors_exit_with_their_codes() {
+    let running = common::start_server(ServerConfig {
+        max_message_size: 64,
+        ..ServerConfig::default()
+    })
+    .await;
+    let _bob = common::connect(running.tcp_port, "bob", None).await;
+    let port = running.tcp_port.to_string();
+
+    let long = "x".repeat(100);
+    let output = send(&["--port", &port, "--to", "bob", "--text", &long]).await;
+    assert_eq!(output.status.code(), Some(1), "{0}", stderr(&output));
+    assert!(stderr(&output).contains("Mensagem recusada"));
+
+    let output = send(&["--port", &port, "--to", "ninguém", "--text", "oi"]).await;
+    assert_eq!(output.status.code(), Some(4), "{0}", stderr(&output));
+
+    let output = send(&["--port", &port, "--to", "bob", "--file", "/inexistente"]).await;
+    assert_eq!(output.status.code(), Some(6), "{0}", stderr(&output));
+}
+
+#[tokio::test]
+async fn invalid_usage_exits_with_two() {
+    for args in [
+        &["--to", "bob"][..],
+        &["--to", "bob", "--text", "oi", "--transport", "udp"],
+        &["--to", "bob", "--text", "oi", "--port", "0"],
+    ] {
+        let output = send(args).await;
+        assert_eq!(output.status.code(), Some(2), "{0}", stderr(&output));
+    }
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use t1_lab_redes::config::{ClientConfig, ServerConfig};
+use t1_lab_redes::models::event::ServerEventKind;
+use t1_lab_redes::network::accounts::Accounts;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::utilities::enums::{MessageType, Protocol};
+
+async fn alice_tcp(running: &common::RunningServer) -> TcpClient {
+    let config = ClientConfig {
+        password: Some("senha-da-alice".to_string()),
+        ..common::client_config(running, "alice", None)
+    };
+    TcpClient::new(&config).await.unwrap()
+}
+
+async fn disconnect<C: Client>(client: &C) {
+    let key = client.disconnect().await.unwrap();
+    wait_response(client, key).await.unwrap();
+}
+
+#[tokio::test]
+async fn account_keeps_its_id_across_transports_and_reconnections() {
+    let file = common::accounts_file();
+    let account_id = Accounts::load(&file.path).unwrap().get("alice").unwrap().id;
+    let running = common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    })
+    .await;
+
+    let tcp = alice_tcp(&running).await;
+    let udp_config = ClientConfig {
+        token: Some(file.token.clone()),
+        ..common::client_config(&running, "alice", Some(Protocol::UDP))
+    };
+    let udp = UdpClient::new(&udp_config).await.unwrap();
+    assert_eq!(tcp.get_id(), account_id);
+    assert_eq!(udp.get_id(), account_id);
+    assert_eq!(
+        running
+            .server
+            .sessions
+            .read()
+            .await
+            .sessions(&account_id)
+            .len(),
+        2
+    );
+
+    disconnect(&tcp).await;
+    disconnect(&udp).await;
+    assert!(running.server.sessions.read().await.is_empty());
+    let tcp = alice_tcp(&running).await;
+    assert_eq!(tcp.get_id(), account_id);
+}
+
+#[tokio::test]
+async fn messages_reach_every_session_of_the_user() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    })
+    .await;
+    let first = alice_tcp(&running).await;
+    let second = alice_tcp(&running).await;
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+
+    let key = bob.list_clients().await.unwrap();
+    let listed = wait_response(&bob, key).await.unwrap();
+    let clients: Vec<(u16, String)> = serde_json::from_slice(&listed.content).unwrap();
+    assert_eq!(clients.len(), 2);
+
+    let key = bob
+        .send_text("olá, alice".to_string(), first.get_id())
+        .await
+        .unwrap();
+    wait_response(&bob, key).await.unwrap();
+    for session in [&first, &second] {
+        let text = common::next_of(session, MessageType::Text).await;
+        assert_eq!(text.metadata.receiver_id, bob.get_id());
+        assert_eq!(text.content, "olá, alice".as_bytes());
+    }
+}
+
+#[tokio::test]
+async fn user_goes_offline_only_after_the_last_session() {
+    let file = common::accounts_file();
+    let running = common::start_server(ServerConfig {
+        accounts_file: Some(file.path.clone()),
+        ..ServerConfig::default()
+    })
+    .await;
+    let bob = TcpClient::new(&common::client_config(&running, "bob", None))
+        .await
+        .unwrap();
+    let first = alice_tcp(&running).await;
+    let second = alice_tcp(&running).await;
+    let id = first.get_id();
+    let (_, mut events) = running.server.events.subscribe();
+
+    disconnect(&first).await;
+    loop {
+        match events.recv().await.unwrap().kind {
+            ServerEventKind::SessionClosed { id: closed, .. } => {
+                assert_eq!(closed, id);
+                break;
+            }
+            ServerEventKind::Disconnected { .. } => panic!("usuário ficou offline"),
+            _ => {}
+        }
+    }
+    assert_eq!(
+        running.server.name_table.read().await.get(&id).unwrap(),
+        "alice"
+    );
+
+    disconnect(&second).await;
+    loop {
+        let presence = common::next_of(&bob, MessageType::Presence).await;
+        let (presence_id, name, online): (u16, String, bool) =
+            serde_json::from_slice(&presence.content).unwrap();
+        if !online {
+            assert_eq!((presence_id, name.as_str()), (id, "alice"));
+            break;
+        }
+    }
+}
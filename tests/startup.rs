@@ -0,0 +1,68 @@
+mod common;
+
+use std::net::{TcpListener, UdpSocket};
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::models::event::ServerEventKind;
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::server::Server;
+use t1_lab_redes::network::tcp_client::TcpClient;
+
+fn local_config(tcp_port: u16, udp_port: u16) -> ServerConfig {
+    ServerConfig {
+        host: "127.0.0.1".to_string(),
+        tcp_port,
+        udp_port,
+        ..ServerConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn ports_in_use_are_startup_errors() {
+    let taken_tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+    let tcp_port = taken_tcp.local_addr().unwrap().port();
+    let server = Server::new(local_config(tcp_port, 0));
+    let error = server.start().await.unwrap_err();
+    assert!(
+        error.starts_with(&format!(
+            "Falha ao abrir a porta TCP 127.0.0.1:{0}",
+            tcp_port
+        )),
+        "{0}",
+        error
+    );
+    let emitted = server.events.recent().into_iter().any(
+        |event| matches!(&event.kind, ServerEventKind::Error { message } if *message == error),
+    );
+    assert!(emitted);
+
+    let taken_udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let udp_port = taken_udp.local_addr().unwrap().port();
+    let server = Server::new(local_config(0, udp_port));
+    let error = server.start().await.unwrap_err();
+    assert!(
+        error.starts_with(&format!(
+            "Falha ao abrir a porta UDP 127.0.0.1:{0}",
+            udp_port
+        )),
+        "{0}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn zero_event_capacity_keeps_no_events() {
+    let running = common::start_server(ServerConfig {
+        event_log_capacity: 0,
+        ..ServerConfig::default()
+    })
+    .await;
+    assert!(running.server.events.recent().is_empty());
+
+    let alice = TcpClient::new(&common::client_config(&running, "alice", None))
+        .await
+        .unwrap();
+    let key = alice.list_clients().await.unwrap();
+    wait_response(&alice, key).await.unwrap();
+    assert!(running.server.events.recent().is_empty());
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rcgen::{BasicConstraints, CertificateParams, IsCa, Issuer, KeyPair};
+
+use t1_lab_redes::config::{ClientConfig, ServerConfig};
+use t1_lab_redes::network::client::{wait_response, Client};
+use t1_lab_redes::network::tcp_client::TcpClient;
+use t1_lab_redes::utilities::enums::MessageType;
+
+struct Certificates {
+    dir: PathBuf,
+    ca: PathBuf,
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+impl Drop for Certificates {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Gera uma CA própria e um certificado para "localhost" assinado por ela.
+fn generate_certificates() -> Certificates {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "t1-tls-{0}-{1}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+    let issuer = Issuer::new(ca_params, ca_key);
+
+    let key = KeyPair::generate().unwrap();
+    let cert = CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .signed_by(&key, &issuer)
+        .unwrap();
+
+    let certificates = Certificates {
+        ca: dir.join("ca.pem"),
+        cert: dir.join("cert.pem"),
+        key: dir.join("key.pem"),
+        dir,
+    };
+    fs::write(&certificates.ca, ca_cert.pem()).unwrap();
+    fs::write(&certificates.cert, cert.pem()).unwrap();
+    fs::write(&certificates.key, key.serialize_pem()).unwrap();
+    certificates
+}
+
+async fn start_server(certificates: &Certificates) -> common::RunningServer {
+    common::start_server(ServerConfig {
+        tls_cert: Some(certificates.cert.clone()),
+        tls_key: Some(certificates.key.clone()),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn client_with_custom_ca_exchanges_messages() {
+    let certificates = generate_certificates();
+    let running = start_server(&certificates).await;
+    let config = ClientConfig {
+        tls_ca: Some(certificates.ca.clone()),
+        ..common::tls_client_config(&running, "alice")
+    };
+    let client = TcpClient::new(&config).await.unwrap();
+    assert_ne!(client.get_id(), 0);
+
+    let key = client.list_clients().await.unwrap();
+    let response = wait_response(&client, key).await.unwrap();
+    let clients: Vec<(u16, String)> = serde_json::from_slice(&response.content).unwrap();
+    assert_eq!(clients, vec![(client.get_id(), "alice".to_string())]);
+
+    let key = client
+        .send_text("olá".to_string(), client.get_id())
+        .await
+        .unwrap();
+    let forwarded = loop {
+        let message = client.listen().await.unwrap();
+        if message.metadata.key == key && message.metadata.message_type == MessageType::Text {
+            break message;
+        }
+    };
+    assert_eq!(forwarded.content, "olá".as_bytes());
+    running.server.shutdown().await;
+}
+
+#[tokio::test]
+async fn client_rejects_certificate_from_unknown_ca() {
+    let certificates = generate_certificates();
+    let running = start_server(&certificates).await;
+    let result = TcpClient::new(&common::tls_client_config(&running, "alice")).await;
+    assert!(result.is_err());
+    running.server.shutdown().await;
+}
+
+#[tokio::test]
+async fn client_rejects_certificate_for_other_name() {
+    let certificates = generate_certificates();
+    let running = start_server(&certificates).await;
+    let config = ClientConfig {
+        tls_ca: Some(certificates.ca.clone()),
+        tls_server_name: Some("example.com".to_string()),
+        ..common::tls_client_config(&running, "alice")
+    };
+    assert!(TcpClient::new(&config).await.is_err());
+    running.server.shutdown().await;
+}
+
+#[tokio::test]
+async fn plaintext_client_cannot_register_on_tls_listener() {
+    let certificates = generate_certificates();
+    let running = start_server(&certificates).await;
+    let config = ClientConfig {
+        tls: false,
+        ..common::tls_client_config(&running, "alice")
+    };
+    assert!(TcpClient::new(&config).await.is_err());
+    assert!(running.server.sessions.read().await.is_empty());
+    running.server.shutdown().await;
+}
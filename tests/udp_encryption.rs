@@ -0,0 +1,182 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::time;
+
+use t1_lab_redes::config::ServerConfig;
+use t1_lab_redes::models::credentials::Credentials;
+use t1_lab_redes::models::message::Message;
+use t1_lab_redes::network::client::Client;
+use t1_lab_redes::network::udp_client::UdpClient;
+use t1_lab_redes::network::udp_crypto::{UdpSession, UsedSessions, SESSION_MAX_AGE};
+use t1_lab_redes::utilities::enums::MessageType;
+
+const PSK: &str = "chave-pre-compartilhada-de-teste";
+
+async fn start_server() -> common::RunningServer {
+    common::start_server(ServerConfig {
+        udp_psk: Some(PSK.to_string()),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn encrypted_client_exchanges_fragmented_messages() {
+    let running = start_server().await;
+    let client = UdpClient::new(&common::udp_psk_client_config(&running, PSK))
+        .await
+        .unwrap();
+    let text = "x".repeat(2000);
+    let key = client
+        .send_text(text.clone(), client.get_id())
+        .await
+        .unwrap();
+    let forwarded = time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = client.listen().await.unwrap();
+            if message.metadata.key == key && message.metadata.message_type == MessageType::Text {
+                return message;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(forwarded.content, text.as_bytes());
+    running.server.shutdown().await;
+}
+
+#[tokio::test]
+async fn client_with_wrong_key_is_ignored() {
+    let running = start_server().await;
+    let config = common::udp_psk_client_config(&running, "outra-chave-pre-compartilhada");
+    assert!(
+        time::timeout(Duration::from_millis(300), UdpClient::new(&config))
+            .await
+            .is_err()
+    );
+    assert!(running.server.sessions.read().await.is_empty());
+    running.server.shutdown().await;
+}
+
+#[tokio::test]
+async fn plaintext_datagrams_are_dropped_before_reassembly() {
+    let running = start_server().await;
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let message =
+        Message::new_connection_request(1, &Credentials::anonymous("mallory".to_string()));
+    let mut packet = message.serialize();
+    packet.splice(13..13, [0, 0, 0, 0]);
+    socket
+        .send_to(&packet, ("127.0.0.1", running.udp_port))
+        .await
+        .unwrap();
+    let mut buf = [0u8; 512];
+    assert!(
+        time::timeout(Duration::from_millis(300), socket.recv(&mut buf))
+            .await
+            .is_err()
+    );
+    assert!(running.server.sessions.read().await.is_empty());
+    running.server.shutdown().await;
+}
+
+#[test]
+fn session_rejects_tampered_and_replayed_datagrams() {
+    let client = UdpSession::client(PSK);
+    let server = UdpSession::server(PSK, client.id());
+    let first = client.seal(b"primeiro");
+    let second = client.seal(b"segundo");
+
+    assert_eq!(server.open(&second).unwrap(), b"segundo");
+    assert_eq!(server.open(&first).unwrap(), b"primeiro");
+    assert!(server.open(&first).is_none());
+    assert!(server.open(&second).is_none());
+
+    let mut tampered = client.seal(b"terceiro");
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert!(server.open(&tampered).is_none());
+
+    let reply = server.seal(b"resposta");
+    assert!(server.open(&reply).is_none());
+    assert_eq!(client.open(&reply).unwrap(), b"resposta");
+}
+
+/// Pacote único de uma mensagem, com a sequência zerada, cifrado em `session`.
+fn sealed(session: &UdpSession, udp_id: u16, message: &Message) -> Vec<u8> {
+    let mut packet = message.serialize();
+    packet.splice(13..13, [udp_id.to_le_bytes(), [0, 0]].concat());
+    session.seal(&packet)
+}
+
+/// Primeiro pacote recebido em `socket` que autentica em `session`.
+async fn reply(socket: &UdpSocket, session: &UdpSession) -> Option<Vec<u8>> {
+    let mut buf = [0u8; 2048];
+    time::timeout(Duration::from_millis(300), async {
+        loop {
+            let len = socket.recv(&mut buf).await.unwrap();
+            if let Some(packet) = session.open(&buf[..len]) {
+                return packet;
+            }
+        }
+    })
+    .await
+    .ok()
+}
+
+#[tokio::test]
+async fn replayed_handshake_opens_no_session() {
+    let running = start_server().await;
+    let server = ("127.0.0.1", running.udp_port);
+    let alice = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mallory = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let credentials = Credentials::anonymous("alice".to_string());
+
+    let first = UdpSession::client(PSK);
+    let handshake = sealed(&first, 1, &Message::new_connection_request(1, &credentials));
+    alice.send_to(&handshake, server).await.unwrap();
+    let response = reply(&alice, &first).await.unwrap();
+    assert_eq!(response[4], u8::from(MessageType::Success));
+
+    mallory.send_to(&handshake, server).await.unwrap();
+    assert!(reply(&mallory, &first).await.is_none());
+    assert_eq!(running.server.sessions.read().await.len(), 1);
+
+    let second = UdpSession::client(PSK);
+    let handshake_2 = sealed(
+        &second,
+        2,
+        &Message::new_connection_request(2, &credentials),
+    );
+    alice.send_to(&handshake_2, server).await.unwrap();
+    assert!(reply(&alice, &second).await.is_some());
+
+    // Repetido no endereço da alice, o handshake antigo não derruba a sessão atual.
+    alice.send_to(&handshake, server).await.unwrap();
+    assert!(reply(&alice, &first).await.is_none());
+    let list = sealed(&second, 3, &Message::new_list_clients_request(3));
+    alice.send_to(&list, server).await.unwrap();
+    let response = reply(&alice, &second).await.unwrap();
+    assert_eq!(response[4], u8::from(MessageType::Success));
+    running.server.shutdown().await;
+}
+
+#[test]
+fn used_sessions_reject_repeated_and_stale_ids() {
+    let now: i64 = 1_000_000_000;
+    let mut used = UsedSessions::default();
+    let mut id = [7u8; 16];
+    id[..8].copy_from_slice(&now.to_le_bytes());
+    assert!(used.admit(id, now));
+    assert!(!used.admit(id, now + 1));
+
+    let mut stale = [8u8; 16];
+    stale[..8].copy_from_slice(&(now - SESSION_MAX_AGE as i64 - 1).to_le_bytes());
+    assert!(!used.admit(stale, now));
+    let mut future = [9u8; 16];
+    future[..8].copy_from_slice(&(now + SESSION_MAX_AGE as i64 + 1).to_le_bytes());
+    assert!(!used.admit(future, now));
+}